@@ -1,5 +1,65 @@
 // src/config/app.rs
-use std::env;
+use crate::config::source::ConfigSource;
+use crate::middleware::{OVERRIDABLE_METHODS, TrailingSlash};
+use crate::services::chaos::ChaosConfig;
+use crate::services::client::{Http2Mode, UpstreamTls};
+use crate::services::cors::CorsOverride;
+use crate::services::headers::{DuplicateHeaderMode, OriginPolicy};
+use crate::services::iplist::IpList;
+use crate::services::ratelimit::RateLimit;
+use crate::services::remap::StatusRemap;
+use crate::services::required::RequiredHeader;
+use crate::services::rewrite::PathRewrite;
+use crate::services::store::StoreBackend;
+use crate::services::telemetry::{
+    HeaderLabel, LogFormat, MAX_LABEL_VALUES, MetricsBackend, RequestIdFormat,
+};
+use crate::services::tenant::TenantSource;
+use crate::services::timeouts::RouteTimeout;
+use crate::services::transform::BodyTransform;
+use crate::services::upstream::BasicAuth;
+use crate::services::waf::WafRule;
+use rocket::http::{Method, Status};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Secret the user service falls back to when JWT_SECRET is unset
+pub const DEFAULT_JWT_SECRET: &str = "default-secret-change-me";
+
+/// Paths reachable without a bearer token unless PUBLIC_PATHS says otherwise.
+/// Admin and generic proxy routes check their own credentials.
+const DEFAULT_PUBLIC_PATHS: &[&str] = &[
+    "/api/health",
+    "/api/metrics",
+    "/api/version",
+    "/api/admin",
+    "/api/users/login",
+    "/api/users/register",
+    "/api/users/refresh",
+    "/api/users/logout",
+    "/api/users/forgot-password",
+    "/api/users/reset-password",
+    "/api/proxy",
+];
+
+/// Startup configuration that can't be used, reported instead of panicking
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Unreadable config file {0}")]
+    File(String),
+
+    #[error("Invalid upstream configuration: {0}")]
+    Upstream(String),
+}
+
+impl ConfigError {
+    fn invalid(message: impl Into<String>) -> Self {
+        ConfigError::Invalid(message.into())
+    }
+}
 
 /// Application configuration loaded from environment variables
 #[allow(dead_code)]
@@ -7,64 +67,1176 @@ use std::env;
 pub struct AppConfig {
     pub port: u16,
     pub host: String,
+    pub keep_alive: u32,
     pub user_service_url: String,
     pub payments_service_url: String,
     pub sales_service_url: String,
     pub purchasing_service_url: String,
     pub inventory_service_url: String,
     pub customer_service_url: String,
+    pub notifications_service_url: String,
+    pub user_service_options: ServiceOptions,
+    pub payments_service_options: ServiceOptions,
+    pub sales_service_options: ServiceOptions,
+    pub purchasing_service_options: ServiceOptions,
+    pub inventory_service_options: ServiceOptions,
+    pub customer_service_options: ServiceOptions,
+    pub notifications_service_options: ServiceOptions,
     pub environment: String,
     pub log_level: String,
+    pub log_format: LogFormat,
+    pub request_id_format: RequestIdFormat,
+    pub log_query_string: bool,
+    pub api_keys: Vec<String>,
+    pub jwt_secret: String,
+    pub metrics_token: Option<String>,
+    pub metrics_open_in_development: bool,
+    pub duplicate_header_mode: DuplicateHeaderMode,
+    pub origin_policy: OriginPolicy,
+    pub gateway_request_timeout: Duration,
+    pub upstream_connect_timeout: Duration,
+    pub upstream_total_timeout: Duration,
+    pub proxy_retry_statuses: Vec<u16>,
+    pub proxy_max_retries: u32,
+    pub proxy_retry_backoff: Duration,
+    pub bulkhead_queue_timeout: Duration,
+    pub upstream_http2: Http2Mode,
+    pub upstream_accept_invalid_certs: bool,
+    pub coalesce_gets: bool,
+    pub stale_max_age: Duration,
+    pub upstream_tls: UpstreamTls,
+    pub route_timeouts: Vec<RouteTimeout>,
+    pub expose_version: bool,
+    pub expose_server_timing: bool,
+    pub max_json_depth: usize,
+    pub max_json_keys: usize,
+    pub min_password_length: usize,
+    pub wrap_upstream_errors: bool,
+    pub response_envelope: bool,
+    pub idempotency_ttl: Duration,
+    pub metrics_backend: MetricsBackend,
+    pub statsd_host: String,
+    pub statsd_port: u16,
+    pub statsd_prefix: String,
+    pub metric_label: Option<HeaderLabel>,
+    pub log_request_bodies: bool,
+    pub log_upstream_timings: bool,
+    pub slow_request_threshold: Duration,
+    pub redact_fields: Vec<String>,
+    pub max_upstream_response_bytes: usize,
+    pub max_upload_bytes: u64,
+    pub grpc_web_url: Option<String>,
+    /// Client headers sent upstream as gRPC metadata besides the built-in ones
+    pub grpc_web_metadata: Vec<String>,
+    pub liveness_max_scheduling_delay: Duration,
+    pub health_probe_timeout: Duration,
+    pub health_cache_ttl: Duration,
+    pub critical_services: Vec<String>,
+    pub breaker_failure_threshold: u64,
+    pub breaker_cooldown: Duration,
+    pub self_test: bool,
+    pub path_rewrites: Vec<PathRewrite>,
+    pub required_headers: Vec<RequiredHeader>,
+    pub status_remaps: Vec<StatusRemap>,
+    pub body_transforms: Vec<BodyTransform>,
+    pub gateway_id: String,
+    pub max_proxy_hops: usize,
+    pub max_inflight: usize,
+    pub warmup_connections: usize,
+    pub startup_probe_timeout: Duration,
+    pub shed_retry_after: u64,
+    pub password_reset_rate_limit: RateLimit,
+    pub quota: RateLimit,
+    pub store: StoreBackend,
+    pub maintenance_mode: bool,
+    pub chaos: ChaosConfig,
+    pub enforce_https: bool,
+    pub trailing_slash: TrailingSlash,
+    pub max_uri_length: usize,
+    pub ip_denylist: IpList,
+    pub admin_ip_allowlist: IpList,
+    /// Header the proxy in front sets to the caller's address, believed
+    /// instead of the socket peer for allowlists and rate limits
+    pub trusted_ip_header: Option<String>,
+    pub waf_rules: Vec<WafRule>,
+    pub waf_max_headers: usize,
+    pub method_overrides: Vec<Method>,
+    pub hsts_max_age: u64,
+    pub csp_header: String,
+    pub cors_max_age: Option<usize>,
+    pub cors_expose_headers: Vec<String>,
+    pub cors_overrides: Vec<CorsOverride>,
+    pub proxy_public_routes: Vec<String>,
+    pub proxy_stream_routes: Vec<String>,
+    pub public_paths: Vec<String>,
+    pub tenant_sources: Vec<TenantSource>,
+    pub tenant_domain: Option<String>,
+    pub tenant_scoped_paths: Vec<String>,
 }
 
 impl AppConfig {
-    /// Load configuration from environment variables
-    pub fn from_env() -> Self {
-        let port = env::var("PORT")
+    /// Load configuration from environment variables, falling back to the
+    /// TOML file named by `CONFIG_FILE` for anything the environment leaves unset
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_source(ConfigSource::load()?)
+    }
+
+    /// Build the configuration from the settings in `source`
+    pub fn from_source(source: ConfigSource) -> Result<Self, ConfigError> {
+        let port = source
+            .var("PORT")
             .unwrap_or_else(|_| "3000".to_string())
             .parse::<u16>()
-            .expect("PORT must be a valid port number");
+            .map_err(|_| ConfigError::invalid("PORT must be a valid port number"))?;
+
+        let host = source.var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
 
-        let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
+        let keep_alive = source
+            .var("KEEP_ALIVE_SECONDS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u32>()
+            .map_err(|_| ConfigError::invalid("KEEP_ALIVE_SECONDS must be a number of seconds"))?;
 
-        let user_service_url =
-            env::var("USER_SERVICE_URL").unwrap_or_else(|_| "http://user-service:3000".to_string());
+        let user_service_url = source
+            .var("USER_SERVICE_URL")
+            .unwrap_or_else(|_| "http://user-service:3000".to_string());
 
-        let payments_service_url = env::var("PAYMENTS_SERVICE_URL")
+        let payments_service_url = source
+            .var("PAYMENTS_SERVICE_URL")
             .unwrap_or_else(|_| "http://payments-service:3000".to_string());
 
-        let sales_service_url = env::var("SALES_SERVICE_URL")
+        let sales_service_url = source
+            .var("SALES_SERVICE_URL")
             .unwrap_or_else(|_| "http://sales-service:3000".to_string());
 
-        let purchasing_service_url = env::var("PURCHASING_SERVICE_URL")
+        let purchasing_service_url = source
+            .var("PURCHASING_SERVICE_URL")
             .unwrap_or_else(|_| "http://purchasing-service:3000".to_string());
 
-        let inventory_service_url = env::var("INVENTORY_SERVICE_URL")
+        let inventory_service_url = source
+            .var("INVENTORY_SERVICE_URL")
             .unwrap_or_else(|_| "http://inventory-service:3000".to_string());
 
-        let customer_service_url = env::var("CUSTOMER_SERVICE_URL")
+        let customer_service_url = source
+            .var("CUSTOMER_SERVICE_URL")
             .unwrap_or_else(|_| "http://customer-activity-service:3000".to_string());
 
-        let environment = env::var("NODE_ENV").unwrap_or_else(|_| "development".to_string());
+        let notifications_service_url = source
+            .var("NOTIFICATIONS_SERVICE_URL")
+            .unwrap_or_else(|_| "ws://notifications-service:3000".to_string());
+
+        let user_service_options = ServiceOptions::from_env(&source, "USER_SERVICE")?;
+        let payments_service_options = ServiceOptions::from_env(&source, "PAYMENTS_SERVICE")?;
+        let sales_service_options = ServiceOptions::from_env(&source, "SALES_SERVICE")?;
+        let purchasing_service_options = ServiceOptions::from_env(&source, "PURCHASING_SERVICE")?;
+        let inventory_service_options = ServiceOptions::from_env(&source, "INVENTORY_SERVICE")?;
+        let customer_service_options = ServiceOptions::from_env(&source, "CUSTOMER_SERVICE")?;
+        let notifications_service_options =
+            ServiceOptions::from_env(&source, "NOTIFICATIONS_SERVICE")?;
+
+        let environment = source
+            .var("NODE_ENV")
+            .unwrap_or_else(|_| "development".to_string());
+
+        let log_level = source
+            .var("LOG_LEVEL")
+            .unwrap_or_else(|_| "info".to_string());
+
+        let log_format = source
+            .var("LOG_FORMAT")
+            .unwrap_or_else(|_| "text".to_string())
+            .parse::<LogFormat>()
+            .map_err(|_| ConfigError::invalid("LOG_FORMAT must be text or json"))?;
+
+        let request_id_format = source
+            .var("REQUEST_ID_FORMAT")
+            .unwrap_or_else(|_| "uuid".to_string())
+            .parse::<RequestIdFormat>()
+            .map_err(|_| ConfigError::invalid("REQUEST_ID_FORMAT must be uuid, ulid or hex16"))?;
+
+        let log_query_string = source
+            .var("LOG_QUERY_STRING")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+
+        let api_keys = source
+            .var("API_KEYS")
+            .map(|keys| parse_list(&keys))
+            .unwrap_or_default();
+
+        let jwt_secret = source
+            .var("JWT_SECRET")
+            .unwrap_or_else(|_| DEFAULT_JWT_SECRET.to_string());
+        if jwt_secret == DEFAULT_JWT_SECRET && environment != "development" {
+            return Err(ConfigError::invalid(
+                "JWT_SECRET must be set outside development",
+            ));
+        }
+
+        let metrics_token = source
+            .var("METRICS_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty());
+
+        let metrics_open_in_development = source
+            .var("METRICS_OPEN_IN_DEVELOPMENT")
+            .map(|value| value != "false")
+            .unwrap_or(true);
+
+        let duplicate_header_mode = source
+            .var("DUPLICATE_HEADERS")
+            .unwrap_or_else(|_| "combine".to_string())
+            .parse::<DuplicateHeaderMode>()
+            .map_err(|_| {
+                ConfigError::invalid("DUPLICATE_HEADERS must be one of first, last or combine")
+            })?;
+
+        let origin_policy = source
+            .var("UPSTREAM_ORIGIN")
+            .unwrap_or_else(|_| "strip".to_string())
+            .parse::<OriginPolicy>()
+            .map_err(|_| {
+                ConfigError::invalid("UPSTREAM_ORIGIN must be strip, forward or rewrite:<origin>")
+            })?;
+
+        let gateway_request_timeout = source
+            .var("GATEWAY_REQUEST_TIMEOUT_MS")
+            .unwrap_or_else(|_| "30000".to_string())
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| {
+                ConfigError::invalid("GATEWAY_REQUEST_TIMEOUT_MS must be a number of milliseconds")
+            })?;
+
+        let upstream_connect_timeout = source
+            .var("UPSTREAM_CONNECT_TIMEOUT_MS")
+            .unwrap_or_else(|_| "2000".to_string())
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| {
+                ConfigError::invalid("UPSTREAM_CONNECT_TIMEOUT_MS must be a number of milliseconds")
+            })?;
+
+        let upstream_total_timeout = source
+            .var("UPSTREAM_TOTAL_TIMEOUT_MS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| {
+                ConfigError::invalid("UPSTREAM_TOTAL_TIMEOUT_MS must be a number of milliseconds")
+            })?;
+
+        let proxy_retry_statuses = parse_list(
+            &source
+                .var("PROXY_RETRY_STATUSES")
+                .unwrap_or_else(|_| "502,503,504".to_string()),
+        )
+        .iter()
+        .map(|code| {
+            code.parse::<u16>()
+                .ok()
+                .filter(|code| Status::from_code(*code).is_some())
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| {
+            ConfigError::invalid(
+                "PROXY_RETRY_STATUSES must be a comma-separated list of HTTP status codes",
+            )
+        })?;
+
+        let proxy_max_retries = source
+            .var("PROXY_MAX_RETRIES")
+            .unwrap_or_else(|_| "1".to_string())
+            .parse::<u32>()
+            .map_err(|_| {
+                ConfigError::invalid("PROXY_MAX_RETRIES must be a non-negative integer")
+            })?;
+
+        let proxy_retry_backoff = source
+            .var("PROXY_RETRY_BACKOFF_MS")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| {
+                ConfigError::invalid("PROXY_RETRY_BACKOFF_MS must be a number of milliseconds")
+            })?;
+
+        let bulkhead_queue_timeout = source
+            .var("BULKHEAD_QUEUE_TIMEOUT_MS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| {
+                ConfigError::invalid("BULKHEAD_QUEUE_TIMEOUT_MS must be a number of milliseconds")
+            })?;
+
+        let upstream_http2 = source
+            .var("UPSTREAM_HTTP2")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<Http2Mode>()
+            .map_err(|_| {
+                ConfigError::invalid("UPSTREAM_HTTP2 must be true, false or prior-knowledge")
+            })?;
+
+        let upstream_accept_invalid_certs = source
+            .var("UPSTREAM_ACCEPT_INVALID_CERTS")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+
+        let coalesce_gets = source
+            .var("COALESCE_GETS")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+
+        let stale_max_age = source
+            .var("STALE_MAX_AGE")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .map(Duration::from_secs)
+            .map_err(|_| ConfigError::invalid("STALE_MAX_AGE must be a number of seconds"))?;
+
+        let tls_path = |name: &str| source.var(name).ok().filter(|path| !path.trim().is_empty());
+        let upstream_tls = UpstreamTls::from_files(
+            tls_path("UPSTREAM_CLIENT_CERT").as_deref(),
+            tls_path("UPSTREAM_CLIENT_KEY").as_deref(),
+            tls_path("UPSTREAM_CA_CERT").as_deref(),
+        )
+        .map_err(|e| ConfigError::invalid(format!("Upstream TLS: {}", e)))?;
+
+        let route_timeouts = source
+            .var("ROUTE_TIMEOUTS")
+            .map(|rules| parse_list(&rules))
+            .unwrap_or_default()
+            .iter()
+            .map(|rule| rule.parse::<RouteTimeout>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                ConfigError::invalid("ROUTE_TIMEOUTS must be a comma-separated list of <path>=<ms>")
+            })?;
+
+        let expose_version = source
+            .var("EXPOSE_VERSION")
+            .map(|value| value != "false")
+            .unwrap_or(true);
+
+        let expose_server_timing = source
+            .var("EXPOSE_SERVER_TIMING")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+
+        let max_json_depth = source
+            .var("MAX_JSON_DEPTH")
+            .unwrap_or_else(|_| "32".to_string())
+            .parse::<usize>()
+            .map_err(|_| ConfigError::invalid("MAX_JSON_DEPTH must be a positive integer"))?;
+
+        let max_json_keys = source
+            .var("MAX_JSON_KEYS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse::<usize>()
+            .map_err(|_| ConfigError::invalid("MAX_JSON_KEYS must be a positive integer"))?;
+
+        let min_password_length = source
+            .var("MIN_PASSWORD_LENGTH")
+            .unwrap_or_else(|_| "8".to_string())
+            .parse::<usize>()
+            .map_err(|_| ConfigError::invalid("MIN_PASSWORD_LENGTH must be a positive integer"))?;
+
+        let idempotency_ttl = source
+            .var("IDEMPOTENCY_TTL_SECONDS")
+            .unwrap_or_else(|_| "86400".to_string())
+            .parse::<u64>()
+            .map(Duration::from_secs)
+            .map_err(|_| {
+                ConfigError::invalid("IDEMPOTENCY_TTL_SECONDS must be a number of seconds")
+            })?;
+
+        let metrics_backend = source
+            .var("METRICS_BACKEND")
+            .unwrap_or_else(|_| "prometheus".to_string())
+            .parse::<MetricsBackend>()
+            .map_err(|_| ConfigError::invalid("METRICS_BACKEND must be prometheus or statsd"))?;
+
+        let statsd_host = source
+            .var("STATSD_HOST")
+            .unwrap_or_else(|_| "127.0.0.1".to_string());
+
+        let statsd_port = source
+            .var("STATSD_PORT")
+            .unwrap_or_else(|_| "8125".to_string())
+            .parse::<u16>()
+            .map_err(|_| ConfigError::invalid("STATSD_PORT must be a valid port number"))?;
+
+        let statsd_prefix = source
+            .var("STATSD_PREFIX")
+            .unwrap_or_else(|_| "api_gateway".to_string());
+
+        let metric_label_name = source
+            .var("METRIC_LABEL_NAME")
+            .unwrap_or_else(|_| "client_app".to_string());
+        let metric_label_values =
+            parse_list(&source.var("METRIC_LABEL_VALUES").unwrap_or_default());
+        let metric_label = source
+            .var("METRIC_LABEL_HEADER")
+            .ok()
+            .filter(|header| !header.trim().is_empty())
+            .map(|header| HeaderLabel {
+                name: metric_label_name,
+                header: header.trim().to_string(),
+                allowed: metric_label_values,
+            });
+        if let Some(label) = &metric_label
+            && label.allowed.len() > MAX_LABEL_VALUES
+        {
+            return Err(ConfigError::invalid(format!(
+                "METRIC_LABEL_VALUES must list at most {} values",
+                MAX_LABEL_VALUES
+            )));
+        }
+
+        let log_request_bodies = source
+            .var("LOG_REQUEST_BODIES")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+
+        let log_upstream_timings = source
+            .var("LOG_UPSTREAM_TIMINGS")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+
+        let slow_request_threshold = source
+            .var("SLOW_REQUEST_MS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| {
+                ConfigError::invalid("SLOW_REQUEST_MS must be a number of milliseconds")
+            })?;
+
+        let redact_fields = source
+            .var("REDACT_FIELDS")
+            .map(|fields| parse_list(&fields))
+            .unwrap_or_else(|_| parse_list("password,new_password,refresh_token,token"));
+
+        let max_upstream_response_bytes = source
+            .var("MAX_UPSTREAM_RESPONSE_BYTES")
+            .unwrap_or_else(|_| "10485760".to_string())
+            .parse::<usize>()
+            .map_err(|_| {
+                ConfigError::invalid("MAX_UPSTREAM_RESPONSE_BYTES must be a number of bytes")
+            })?;
+
+        let max_upload_bytes = source
+            .var("MAX_UPLOAD_BYTES")
+            .unwrap_or_else(|_| "104857600".to_string())
+            .parse::<u64>()
+            .map_err(|_| ConfigError::invalid("MAX_UPLOAD_BYTES must be a number of bytes"))?;
+
+        let grpc_web_url = source
+            .var("GRPC_WEB_UPSTREAM_URL")
+            .ok()
+            .map(|url| url.trim().trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty());
+        if grpc_web_url
+            .as_deref()
+            .is_some_and(|url| !url.starts_with("http://"))
+        {
+            return Err(ConfigError::invalid(
+                "GRPC_WEB_UPSTREAM_URL must be an http:// URL, gRPC upstreams are reached over cleartext HTTP/2",
+            ));
+        }
+
+        let grpc_web_metadata = source
+            .var("GRPC_WEB_METADATA")
+            .map(|headers| parse_list(&headers.to_ascii_lowercase()))
+            .unwrap_or_default();
+
+        let liveness_max_scheduling_delay = source
+            .var("LIVENESS_MAX_SCHEDULING_DELAY_MS")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| {
+                ConfigError::invalid(
+                    "LIVENESS_MAX_SCHEDULING_DELAY_MS must be a number of milliseconds",
+                )
+            })?;
+
+        let health_probe_timeout = source
+            .var("HEALTH_PROBE_TIMEOUT_MS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| {
+                ConfigError::invalid("HEALTH_PROBE_TIMEOUT_MS must be a number of milliseconds")
+            })?;
+
+        let health_cache_ttl = source
+            .var("HEALTH_CACHE_MS")
+            .unwrap_or_else(|_| "5000".to_string())
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| {
+                ConfigError::invalid("HEALTH_CACHE_MS must be a number of milliseconds")
+            })?;
 
-        let log_level = env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
+        let critical_services = source
+            .var("CRITICAL_SERVICES")
+            .map(|services| parse_list(&services))
+            .unwrap_or_default();
 
-        Self {
+        let breaker_failure_threshold = source
+            .var("BREAKER_FAILURE_THRESHOLD")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u64>()
+            .map_err(|_| {
+                ConfigError::invalid("BREAKER_FAILURE_THRESHOLD must be a non-negative integer")
+            })?;
+
+        let breaker_cooldown = source
+            .var("BREAKER_COOLDOWN_MS")
+            .unwrap_or_else(|_| "30000".to_string())
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| {
+                ConfigError::invalid("BREAKER_COOLDOWN_MS must be a number of milliseconds")
+            })?;
+
+        let self_test = source
+            .var("SELF_TEST")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+
+        let path_rewrites = source
+            .var("UPSTREAM_PATH_REWRITES")
+            .map(|rules| parse_list(&rules))
+            .unwrap_or_default()
+            .iter()
+            .map(|rule| rule.parse::<PathRewrite>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                ConfigError::invalid(
+                    "UPSTREAM_PATH_REWRITES must be a comma-separated list of <from>=<to>",
+                )
+            })?;
+
+        let required_headers = source
+            .var("REQUIRED_HEADERS")
+            .map(|rules| parse_list(&rules))
+            .unwrap_or_default()
+            .iter()
+            .map(|rule| rule.parse::<RequiredHeader>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                ConfigError::invalid(
+                    "REQUIRED_HEADERS must be a comma-separated list of <path>=<header>[>=<version>]",
+                )
+            })?;
+
+        let status_remaps = source
+            .var("UPSTREAM_STATUS_REMAP")
+            .map(|rules| parse_list(&rules))
+            .unwrap_or_default()
+            .iter()
+            .map(|rule| rule.parse::<StatusRemap>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                ConfigError::invalid(
+                    "UPSTREAM_STATUS_REMAP must be a comma-separated list of <from>=<to> status codes",
+                )
+            })?;
+
+        let body_transforms = source
+            .var("BODY_TRANSFORMS")
+            .map(|rules| parse_list(&rules))
+            .unwrap_or_default()
+            .iter()
+            .map(|rule| rule.parse::<BodyTransform>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                ConfigError::invalid(
+                    "BODY_TRANSFORMS must be a comma-separated list of <path>=set:<field>:<value>, <path>=drop:<field> or <path>=rename:<from>:<to>",
+                )
+            })?;
+
+        let gateway_id = source
+            .var("GATEWAY_ID")
+            .unwrap_or_else(|_| "api-gateway".to_string());
+
+        let max_proxy_hops = source
+            .var("MAX_PROXY_HOPS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<usize>()
+            .map_err(|_| ConfigError::invalid("MAX_PROXY_HOPS must be a positive integer"))?;
+
+        let max_inflight = source
+            .var("MAX_INFLIGHT")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<usize>()
+            .map_err(|_| ConfigError::invalid("MAX_INFLIGHT must be a non-negative integer"))?;
+
+        let warmup_connections = source
+            .var("WARMUP_CONNECTIONS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<usize>()
+            .map_err(|_| {
+                ConfigError::invalid("WARMUP_CONNECTIONS must be a non-negative integer")
+            })?;
+
+        let startup_probe_timeout = source
+            .var("STARTUP_PROBE_TIMEOUT_MS")
+            .unwrap_or_else(|_| "2000".to_string())
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| {
+                ConfigError::invalid("STARTUP_PROBE_TIMEOUT_MS must be a number of milliseconds")
+            })?;
+
+        let shed_retry_after = source
+            .var("SHED_RETRY_AFTER_SECONDS")
+            .unwrap_or_else(|_| "1".to_string())
+            .parse::<u64>()
+            .map_err(|_| {
+                ConfigError::invalid("SHED_RETRY_AFTER_SECONDS must be a number of seconds")
+            })?;
+
+        let password_reset_rate_limit = RateLimit {
+            limit: source
+                .var("PASSWORD_RESET_RATE_LIMIT")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse::<u32>()
+                .map_err(|_| {
+                    ConfigError::invalid("PASSWORD_RESET_RATE_LIMIT must be a non-negative integer")
+                })?,
+            window: source
+                .var("PASSWORD_RESET_RATE_WINDOW_SECONDS")
+                .unwrap_or_else(|_| "900".to_string())
+                .parse::<u64>()
+                .ok()
+                .filter(|seconds| *seconds > 0)
+                .map(Duration::from_secs)
+                .ok_or_else(|| {
+                    ConfigError::invalid(
+                        "PASSWORD_RESET_RATE_WINDOW_SECONDS must be a positive number of seconds",
+                    )
+                })?,
+            fail_open: source
+                .var("PASSWORD_RESET_RATE_FAIL_OPEN")
+                .map(|value| value == "true")
+                .unwrap_or(false),
+        };
+
+        let quota = RateLimit {
+            limit: source
+                .var("QUOTA_LIMIT")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse::<u32>()
+                .map_err(|_| ConfigError::invalid("QUOTA_LIMIT must be a non-negative integer"))?,
+            window: parse_window(
+                &source
+                    .var("QUOTA_WINDOW")
+                    .unwrap_or_else(|_| "1d".to_string()),
+            )
+            .ok_or_else(|| {
+                ConfigError::invalid(
+                    "QUOTA_WINDOW must be a positive duration such as 3600, 12h, 1d or 30d",
+                )
+            })?,
+            fail_open: source
+                .var("QUOTA_FAIL_OPEN")
+                .map(|value| value == "true")
+                .unwrap_or(false),
+        };
+
+        let store = StoreBackend::parse(
+            &source
+                .var("STORE_BACKEND")
+                .unwrap_or_else(|_| "memory".to_string()),
+            source.var("REDIS_URL").ok().as_deref(),
+        )
+        .map_err(|e| ConfigError::invalid(format!("STORE_BACKEND: {}", e)))?;
+
+        let maintenance_mode = source
+            .var("MAINTENANCE_MODE")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+
+        let enforce_https = source
+            .var("ENFORCE_HTTPS")
+            .map(|value| value != "false")
+            .unwrap_or(true);
+
+        let trailing_slash = source
+            .var("TRAILING_SLASH")
+            .unwrap_or_else(|_| "strip".to_string())
+            .parse::<TrailingSlash>()
+            .map_err(|_| ConfigError::invalid("TRAILING_SLASH must be strip or keep"))?;
+
+        let max_uri_length = source
+            .var("MAX_URI_LENGTH")
+            .unwrap_or_else(|_| "8192".to_string())
+            .parse::<usize>()
+            .map_err(|_| ConfigError::invalid("MAX_URI_LENGTH must be a number of bytes"))?;
+
+        let ip_denylist = source
+            .var("IP_DENYLIST")
+            .unwrap_or_default()
+            .parse::<IpList>()
+            .map_err(|e| ConfigError::invalid(format!("IP_DENYLIST: {}", e)))?;
+
+        let admin_ip_allowlist = source
+            .var("ADMIN_IP_ALLOWLIST")
+            .unwrap_or_default()
+            .parse::<IpList>()
+            .map_err(|e| ConfigError::invalid(format!("ADMIN_IP_ALLOWLIST: {}", e)))?;
+
+        let trusted_ip_header = source
+            .var("TRUSTED_IP_HEADER")
+            .ok()
+            .map(|header| header.trim().to_string())
+            .filter(|header| !header.is_empty());
+
+        let waf_rules = parse_list(
+            &source
+                .var("WAF_RULES")
+                .unwrap_or_else(|_| "header-count,null-bytes,path-traversal".to_string()),
+        )
+        .iter()
+        .map(|rule| rule.parse::<WafRule>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| {
+            ConfigError::invalid(
+                "WAF_RULES must list header-count, null-bytes and/or path-traversal",
+            )
+        })?;
+
+        let waf_max_headers = source
+            .var("WAF_MAX_HEADERS")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<usize>()
+            .map_err(|_| ConfigError::invalid("WAF_MAX_HEADERS must be a positive integer"))?;
+
+        let method_overrides = parse_list(
+            &source
+                .var("METHOD_OVERRIDE_METHODS")
+                .unwrap_or_else(|_| "PUT,PATCH,DELETE".to_string()),
+        )
+        .iter()
+        .map(|method| method.parse::<Method>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()
+        .filter(|methods| {
+            methods
+                .iter()
+                .all(|method| OVERRIDABLE_METHODS.contains(method))
+        })
+        .ok_or_else(|| {
+            ConfigError::invalid("METHOD_OVERRIDE_METHODS may only list PUT, PATCH and DELETE")
+        })?;
+
+        let hsts_max_age = source
+            .var("HSTS_MAX_AGE")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .map_err(|_| ConfigError::invalid("HSTS_MAX_AGE must be a number of seconds"))?;
+
+        let cors_max_age = source
+            .var("CORS_MAX_AGE")
+            .unwrap_or_else(|_| "600".to_string())
+            .parse::<usize>()
+            .map(|seconds| (seconds > 0).then_some(seconds))
+            .map_err(|_| ConfigError::invalid("CORS_MAX_AGE must be a number of seconds"))?;
+
+        let cors_expose_headers =
+            parse_list(&source.var("CORS_EXPOSE_HEADERS").unwrap_or_else(|_| {
+                "X-Request-Id,X-Upstream,Idempotent-Replayed,X-Cache".to_string()
+            }));
+
+        let cors_overrides = source
+            .var("CORS_ROUTE_ORIGINS")
+            .map(|rules| parse_list(&rules))
+            .unwrap_or_default()
+            .iter()
+            .map(|rule| rule.parse::<CorsOverride>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                ConfigError::invalid(
+                    "CORS_ROUTE_ORIGINS must be a comma-separated list of <path>=<origin>|... rules",
+                )
+            })?;
+
+        let csp_header = source
+            .var("CSP_HEADER")
+            .unwrap_or_else(|_| "default-src 'none'; frame-ancestors 'none'".to_string());
+
+        let proxy_public_routes = source
+            .var("PROXY_PUBLIC_ROUTES")
+            .map(|routes| parse_list(&routes))
+            .unwrap_or_default();
+
+        let proxy_stream_routes = source
+            .var("PROXY_STREAM_ROUTES")
+            .map(|routes| parse_list(&routes))
+            .unwrap_or_default();
+
+        let public_paths = parse_list(
+            &source
+                .var("PUBLIC_PATHS")
+                .unwrap_or_else(|_| DEFAULT_PUBLIC_PATHS.join(",")),
+        );
+
+        let tenant_sources = parse_list(
+            &source
+                .var("TENANT_RESOLUTION")
+                .unwrap_or_else(|_| "header".to_string()),
+        )
+        .iter()
+        .map(|strategy| strategy.parse::<TenantSource>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ConfigError::invalid("TENANT_RESOLUTION must list header and/or subdomain"))?;
+
+        let tenant_domain = source
+            .var("TENANT_DOMAIN")
+            .ok()
+            .map(|domain| domain.trim().trim_matches('.').to_string())
+            .filter(|domain| !domain.is_empty());
+        if tenant_sources.contains(&TenantSource::Subdomain) && tenant_domain.is_none() {
+            return Err(ConfigError::invalid(
+                "TENANT_DOMAIN is required to resolve tenants from subdomains",
+            ));
+        }
+
+        let tenant_scoped_paths = source
+            .var("TENANT_SCOPED_PATHS")
+            .map(|paths| parse_list(&paths))
+            .unwrap_or_default();
+
+        let chaos = ChaosConfig {
+            latency: source
+                .var("CHAOS_LATENCY_MS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse::<u64>()
+                .map(Duration::from_millis)
+                .map_err(|_| {
+                    ConfigError::invalid("CHAOS_LATENCY_MS must be a number of milliseconds")
+                })?,
+            latency_percent: parse_percent(&source, "CHAOS_LATENCY_PERCENT")?,
+            error_percent: parse_percent(&source, "CHAOS_ERROR_PERCENT")?,
+            error_status: source
+                .var("CHAOS_ERROR_STATUS")
+                .unwrap_or_else(|_| "503".to_string())
+                .parse::<u16>()
+                .ok()
+                .and_then(Status::from_code)
+                .ok_or_else(|| {
+                    ConfigError::invalid("CHAOS_ERROR_STATUS must be an HTTP status code")
+                })?,
+        };
+
+        let wrap_upstream_errors = source
+            .var("WRAP_UPSTREAM_ERRORS")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+
+        let response_envelope = source
+            .var("RESPONSE_ENVELOPE")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+
+        source.check_unread()?;
+
+        Ok(Self {
             port,
             host,
+            keep_alive,
             user_service_url,
             payments_service_url,
             sales_service_url,
             purchasing_service_url,
             inventory_service_url,
             customer_service_url,
+            notifications_service_url,
+            user_service_options,
+            payments_service_options,
+            sales_service_options,
+            purchasing_service_options,
+            inventory_service_options,
+            customer_service_options,
+            notifications_service_options,
             environment,
             log_level,
-        }
+            log_format,
+            request_id_format,
+            log_query_string,
+            api_keys,
+            jwt_secret,
+            metrics_token,
+            metrics_open_in_development,
+            duplicate_header_mode,
+            origin_policy,
+            gateway_request_timeout,
+            upstream_connect_timeout,
+            upstream_total_timeout,
+            proxy_retry_statuses,
+            proxy_max_retries,
+            proxy_retry_backoff,
+            bulkhead_queue_timeout,
+            upstream_http2,
+            upstream_accept_invalid_certs,
+            coalesce_gets,
+            stale_max_age,
+            upstream_tls,
+            route_timeouts,
+            expose_version,
+            expose_server_timing,
+            max_json_depth,
+            max_json_keys,
+            min_password_length,
+            wrap_upstream_errors,
+            response_envelope,
+            idempotency_ttl,
+            metrics_backend,
+            statsd_host,
+            statsd_port,
+            statsd_prefix,
+            metric_label,
+            log_request_bodies,
+            log_upstream_timings,
+            slow_request_threshold,
+            redact_fields,
+            max_upstream_response_bytes,
+            max_upload_bytes,
+            grpc_web_url,
+            grpc_web_metadata,
+            liveness_max_scheduling_delay,
+            health_probe_timeout,
+            health_cache_ttl,
+            critical_services,
+            breaker_failure_threshold,
+            breaker_cooldown,
+            self_test,
+            path_rewrites,
+            required_headers,
+            status_remaps,
+            body_transforms,
+            gateway_id,
+            max_proxy_hops,
+            max_inflight,
+            warmup_connections,
+            startup_probe_timeout,
+            shed_retry_after,
+            password_reset_rate_limit,
+            quota,
+            store,
+            maintenance_mode,
+            chaos,
+            enforce_https,
+            trailing_slash,
+            max_uri_length,
+            ip_denylist,
+            admin_ip_allowlist,
+            trusted_ip_header,
+            waf_rules,
+            waf_max_headers,
+            method_overrides,
+            hsts_max_age,
+            csp_header,
+            cors_max_age,
+            cors_expose_headers,
+            cors_overrides,
+            proxy_public_routes,
+            proxy_stream_routes,
+            public_paths,
+            tenant_sources,
+            tenant_domain,
+            tenant_scoped_paths,
+        })
     }
 
     /// Check if running in development mode
     pub fn is_development(&self) -> bool {
         self.environment == "development"
     }
+
+    /// Settings only read at startup that `reloaded` changes, so a reload
+    /// can't apply them. Named after their env vars.
+    pub fn restart_required(&self, reloaded: &AppConfig) -> Vec<&'static str> {
+        let changed = [
+            ("HOST", self.host != reloaded.host),
+            ("PORT", self.port != reloaded.port),
+            ("KEEP_ALIVE_SECONDS", self.keep_alive != reloaded.keep_alive),
+            ("LOG_LEVEL", self.log_level != reloaded.log_level),
+            ("LOG_FORMAT", self.log_format != reloaded.log_format),
+            (
+                "METRICS_BACKEND",
+                self.metrics_backend != reloaded.metrics_backend,
+            ),
+            ("STATSD_HOST", self.statsd_host != reloaded.statsd_host),
+            ("STATSD_PORT", self.statsd_port != reloaded.statsd_port),
+            (
+                "STATSD_PREFIX",
+                self.statsd_prefix != reloaded.statsd_prefix,
+            ),
+            ("CORS_MAX_AGE", self.cors_max_age != reloaded.cors_max_age),
+            (
+                "CORS_EXPOSE_HEADERS",
+                self.cors_expose_headers != reloaded.cors_expose_headers,
+            ),
+            ("MAX_INFLIGHT", self.max_inflight != reloaded.max_inflight),
+            (
+                "SHED_RETRY_AFTER_SECONDS",
+                self.shed_retry_after != reloaded.shed_retry_after,
+            ),
+            ("STORE_BACKEND", self.store != reloaded.store),
+            (
+                "IDEMPOTENCY_TTL_SECONDS",
+                self.idempotency_ttl != reloaded.idempotency_ttl,
+            ),
+            (
+                "MAINTENANCE_MODE",
+                self.maintenance_mode != reloaded.maintenance_mode,
+            ),
+        ];
+        changed
+            .into_iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+/// Optional per-service settings, read from `<PREFIX>_*` env vars
+/// (e.g. `USER_SERVICE_FALLBACK_URL`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServiceOptions {
+    /// Secondary URL used only when the primary fails
+    pub fallback_url: Option<String>,
+    /// Concurrent proxied calls allowed, 0 for no limit. Falls back to
+    /// `MAX_CONCURRENT_PER_SERVICE` when the service doesn't set one.
+    pub max_concurrent: usize,
+    /// Canary URL receiving `canary_percent` of the traffic
+    pub canary_url: Option<String>,
+    pub canary_percent: u8,
+    /// `Host` sent upstream instead of the one derived from the URL
+    pub host_header: Option<String>,
+    /// Credentials for a backend behind HTTP Basic auth
+    pub basic_auth: Option<BasicAuth>,
+    /// Retries earned per request, 0 for unlimited retries. Falls back to
+    /// `RETRY_BUDGET_RATIO` when the service doesn't set one.
+    pub retry_budget_ratio: f64,
+}
+
+impl ServiceOptions {
+    fn from_env(source: &ConfigSource, prefix: &str) -> Result<Self, ConfigError> {
+        let fallback_url = source
+            .var(&format!("{}_FALLBACK_URL", prefix))
+            .ok()
+            .map(|url| url.trim().trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty());
+
+        let max_concurrent = source
+            .var(&format!("{}_MAX_CONCURRENT", prefix))
+            // Read either way, so a file setting it is never taken as unknown
+            .or(source.var("MAX_CONCURRENT_PER_SERVICE"))
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<usize>()
+            .map_err(|_| {
+                ConfigError::invalid(format!(
+                    "{}_MAX_CONCURRENT must be a non-negative integer",
+                    prefix
+                ))
+            })?;
+
+        let canary_url = source
+            .var(&format!("{}_CANARY_URL", prefix))
+            .ok()
+            .map(|url| url.trim().trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty());
+        let canary_percent = parse_percent(source, &format!("{}_CANARY_PERCENT", prefix))?;
+
+        let host_header = source
+            .var(&format!("{}_HOST_HEADER", prefix))
+            .ok()
+            .map(|host| host.trim().to_string())
+            .filter(|host| !host.is_empty());
+
+        let basic_auth = match source.var(&format!("{}_BASIC_AUTH", prefix)) {
+            Ok(credentials) => Some(
+                credentials
+                    .parse::<BasicAuth>()
+                    .map_err(|e| ConfigError::invalid(format!("{}_BASIC_AUTH: {}", prefix, e)))?,
+            ),
+            Err(_) => None,
+        };
+
+        let retry_budget_ratio = source
+            .var(&format!("{}_RETRY_BUDGET_RATIO", prefix))
+            .or(source.var("RETRY_BUDGET_RATIO"))
+            .unwrap_or_else(|_| "0.1".to_string())
+            .parse::<f64>()
+            .ok()
+            .filter(|ratio| (0.0..=1.0).contains(ratio))
+            .ok_or_else(|| {
+                ConfigError::invalid(format!(
+                    "{}_RETRY_BUDGET_RATIO must be a ratio between 0 and 1",
+                    prefix
+                ))
+            })?;
+
+        Ok(Self {
+            fallback_url,
+            max_concurrent,
+            canary_url,
+            canary_percent,
+            host_header,
+            basic_auth,
+            retry_budget_ratio,
+        })
+    }
+}
+
+/// Split a comma-separated env value into trimmed, non-empty entries
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// A duration in seconds, or with an `s`, `m`, `h` or `d` suffix
+fn parse_window(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, "s"),
+    };
+    let scale = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        _ => return None,
+    };
+
+    number
+        .parse::<u64>()
+        .ok()
+        .filter(|number| *number > 0)
+        .and_then(|number| number.checked_mul(scale))
+        .map(Duration::from_secs)
+}
+
+/// Read a 0-100 percentage, defaulting to 0
+fn parse_percent(source: &ConfigSource, name: &str) -> Result<u8, ConfigError> {
+    source
+        .var(name)
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<u8>()
+        .ok()
+        .filter(|percent| *percent <= 100)
+        .ok_or_else(|| {
+            ConfigError::invalid(format!("{} must be a percentage between 0 and 100", name))
+        })
 }
@@ -0,0 +1,95 @@
+// src/config/live.rs
+use crate::config::app::{AppConfig, ConfigError};
+use crate::errors::ApiError;
+use crate::services::upstream::Upstreams;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use std::sync::{Arc, RwLock};
+
+/// Configuration and the upstreams built from it, swapped together
+pub struct Snapshot {
+    pub config: AppConfig,
+    pub upstreams: Upstreams,
+}
+
+/// The running configuration, replaced in one step by the admin reload
+/// endpoint. Requests already in flight keep the snapshot they started with.
+pub struct LiveConfig(RwLock<Arc<Snapshot>>);
+
+impl LiveConfig {
+    pub fn new(config: AppConfig, upstreams: Upstreams) -> Self {
+        Self(RwLock::new(Arc::new(Snapshot { config, upstreams })))
+    }
+
+    pub fn snapshot(&self) -> Arc<Snapshot> {
+        self.0.read().expect("config lock poisoned").clone()
+    }
+
+    /// Load the configuration again and swap it in, keeping the current one
+    /// when the new one doesn't validate. Upstreams it leaves unchanged keep
+    /// their runtime state.
+    pub fn reload(&self) -> Result<Arc<Snapshot>, ConfigError> {
+        self.replace(AppConfig::from_env()?)
+    }
+
+    /// Swap in `config`, as `reload` does once it has read it
+    pub fn replace(&self, config: AppConfig) -> Result<Arc<Snapshot>, ConfigError> {
+        let current = self.snapshot();
+        let upstreams = current.upstreams.reload(&current.config, &config)?;
+        let snapshot = Arc::new(Snapshot { config, upstreams });
+
+        *self.0.write().expect("config lock poisoned") = snapshot.clone();
+        Ok(snapshot)
+    }
+}
+
+/// Snapshot a request sees from its first look at the configuration on
+struct RequestSnapshot(Option<Arc<Snapshot>>);
+
+fn request_snapshot<'r>(request: &'r Request<'_>) -> Option<&'r Snapshot> {
+    request
+        .local_cache(|| {
+            RequestSnapshot(
+                request
+                    .rocket()
+                    .state::<LiveConfig>()
+                    .map(LiveConfig::snapshot),
+            )
+        })
+        .0
+        .as_deref()
+}
+
+/// The configuration for this request, for fairings and guards
+pub fn current<'r>(request: &'r Request<'_>) -> Option<&'r AppConfig> {
+    request_snapshot(request).map(|snapshot| &snapshot.config)
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for &'r AppConfig {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match current(request) {
+            Some(config) => Outcome::Success(config),
+            None => unavailable(),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for &'r Upstreams {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request_snapshot(request) {
+            Some(snapshot) => Outcome::Success(&snapshot.upstreams),
+            None => unavailable(),
+        }
+    }
+}
+
+fn unavailable<T>() -> Outcome<T, ApiError> {
+    let err = ApiError::InternalServerError("Configuration not available".into());
+    Outcome::Error((Status::InternalServerError, err))
+}
@@ -1,2 +1,6 @@
 /// API Gateway microservice application
 pub mod app;
+/// Reloadable configuration shared with request handlers
+pub mod live;
+/// Environment and optional config file lookup
+pub mod source;
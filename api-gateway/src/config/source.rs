@@ -0,0 +1,143 @@
+// src/config/source.rs
+use crate::config::app::ConfigError;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use toml::{Table, Value};
+
+/// Where configuration values are read from: the process environment
+/// first, then the optional TOML file named by `CONFIG_FILE`.
+///
+/// File keys are the env var names in lowercase, and tables prefix the
+/// keys inside them, so these two are equivalent:
+///
+/// ```toml
+/// user_service_url = "http://localhost:3001"
+/// api_keys = ["key-a", "key-b"]
+///
+/// [user_service]
+/// fallback_url = "http://localhost:3011"
+/// ```
+///
+/// `USER_SERVICE_URL=http://localhost:3001`, `API_KEYS=key-a,key-b` and
+/// `USER_SERVICE_FALLBACK_URL=http://localhost:3011`. A file key that no
+/// setting is read under, such as a misspelt one, fails the load.
+#[derive(Debug)]
+pub struct ConfigSource {
+    file: HashMap<String, String>,
+    /// Path of the file `file` was read from
+    path: Option<String>,
+    /// Whether the process environment is consulted before `file`
+    environment: bool,
+    /// Names looked up so far, for `check_unread`
+    read: RefCell<HashSet<String>>,
+}
+
+impl ConfigSource {
+    /// Load the file named by `CONFIG_FILE`, or only the environment when unset
+    pub fn load() -> Result<Self, ConfigError> {
+        match env::var("CONFIG_FILE") {
+            Ok(path) if !path.trim().is_empty() => Self::from_file(path.trim()),
+            _ => Ok(Self {
+                file: HashMap::new(),
+                path: None,
+                environment: true,
+                read: RefCell::default(),
+            }),
+        }
+    }
+
+    /// The environment, then the TOML file at `path`
+    pub fn from_file(path: &str) -> Result<Self, ConfigError> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| ConfigError::File(format!("{}: {}", path, e)))?;
+        let table = contents
+            .parse::<Table>()
+            .map_err(|e| ConfigError::File(format!("{}: {}", path, e)))?;
+
+        let mut file = HashMap::new();
+        flatten("", table, &mut file);
+        Ok(Self {
+            file,
+            path: Some(path.to_string()),
+            environment: true,
+            read: RefCell::default(),
+        })
+    }
+
+    /// Only the given settings, leaving the process environment out
+    #[cfg(test)]
+    pub fn from_pairs(pairs: &[(&str, &str)]) -> Self {
+        let file = pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Self {
+            file,
+            path: None,
+            environment: false,
+            read: RefCell::default(),
+        }
+    }
+
+    /// Look up a setting by env var name; the environment wins over the file
+    pub fn var(&self, name: &str) -> Result<String, env::VarError> {
+        self.read.borrow_mut().insert(name.to_string());
+        let from_env = if self.environment {
+            env::var(name)
+        } else {
+            Err(env::VarError::NotPresent)
+        };
+        from_env.or_else(|err| self.file.get(name).cloned().ok_or(err))
+    }
+
+    /// Fail when the file has keys no setting was read under. Call once
+    /// every setting has been looked up.
+    pub fn check_unread(&self) -> Result<(), ConfigError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let read = self.read.borrow();
+        let mut unknown: Vec<String> = self
+            .file
+            .keys()
+            .filter(|name| !read.contains(*name))
+            .map(|name| name.to_lowercase())
+            .collect();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        unknown.sort();
+        Err(ConfigError::File(format!(
+            "{}: unknown settings {}",
+            path,
+            unknown.join(", ")
+        )))
+    }
+}
+
+/// Turn nested tables into env-style names, joining arrays with commas
+fn flatten(prefix: &str, table: Table, out: &mut HashMap<String, String>) {
+    for (key, value) in table {
+        let name = format!("{}{}", prefix, key.to_uppercase());
+        match value {
+            Value::Table(inner) => flatten(&format!("{}_", name), inner, out),
+            Value::Array(items) => {
+                let items: Vec<String> = items.into_iter().map(scalar).collect();
+                out.insert(name, items.join(","));
+            }
+            value => {
+                out.insert(name, scalar(value));
+            }
+        }
+    }
+}
+
+fn scalar(value: Value) -> String {
+    match value {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
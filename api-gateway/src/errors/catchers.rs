@@ -0,0 +1,134 @@
+// src/errors/catchers.rs
+use super::{ErrorResponse, StashedError, ThrottleHeaders};
+use crate::config::live;
+use rocket::Request;
+use rocket::http::{Header, Status};
+use rocket::response::{self, Responder, status};
+use rocket::serde::json::Json;
+use std::collections::BTreeSet;
+
+#[catch(400)]
+pub fn bad_request(request: &Request<'_>) -> status::Custom<Json<ErrorResponse>> {
+    render(Status::BadRequest, request)
+}
+
+#[catch(401)]
+pub fn unauthorized(request: &Request<'_>) -> status::Custom<Json<ErrorResponse>> {
+    render(Status::Unauthorized, request)
+}
+
+#[catch(404)]
+pub fn not_found(request: &Request<'_>) -> status::Custom<Json<ErrorResponse>> {
+    render(Status::NotFound, request)
+}
+
+/// 404 for paths under /api. When no route matched at all, as opposed to
+/// a route answering 404 itself, the message names the unknown path, and in
+/// development the details list the mounted route prefixes.
+#[catch(404)]
+pub fn unknown_api_path(request: &Request<'_>) -> status::Custom<Json<ErrorResponse>> {
+    let stashed = request.local_cache(|| StashedError(None)).0.is_some();
+    if stashed || request.route().is_some() {
+        return render(Status::NotFound, request);
+    }
+
+    let message = format!(
+        "Not found: no API route for {} {}",
+        request.method(),
+        request.uri().path()
+    );
+    let details = live::current(request)
+        .filter(|config| config.is_development())
+        .map(|_| {
+            format!(
+                "Known route prefixes: {}",
+                mounted_prefixes(request).join(", ")
+            )
+        });
+
+    let error = ErrorResponse {
+        details,
+        ..ErrorResponse::new(Status::NotFound, message)
+    };
+    status::Custom(Status::NotFound, Json(error.for_request(request)))
+}
+
+/// Bases of the route groups mounted under /api, in order
+fn mounted_prefixes(request: &Request<'_>) -> Vec<String> {
+    let prefixes: BTreeSet<_> = request
+        .rocket()
+        .routes()
+        .map(|route| route.uri.base())
+        .filter(|base| base.starts_with("/api/"))
+        .map(str::to_string)
+        .collect();
+    prefixes.into_iter().collect()
+}
+
+#[catch(413)]
+pub fn payload_too_large(request: &Request<'_>) -> status::Custom<Json<ErrorResponse>> {
+    render(Status::PayloadTooLarge, request)
+}
+
+#[catch(422)]
+pub fn unprocessable_entity(request: &Request<'_>) -> status::Custom<Json<ErrorResponse>> {
+    render(Status::UnprocessableEntity, request)
+}
+
+#[catch(429)]
+pub fn too_many_requests(request: &Request<'_>) -> Throttled {
+    Throttled {
+        error: render(Status::TooManyRequests, request),
+        headers: request.local_cache(ThrottleHeaders::default).0.clone(),
+    }
+}
+
+/// 429 with the headers the rejecting guard asked for
+pub struct Throttled {
+    error: status::Custom<Json<ErrorResponse>>,
+    headers: Vec<Header<'static>>,
+}
+
+impl<'r> Responder<'r, 'static> for Throttled {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = self.error.respond_to(request)?;
+        for header in self.headers {
+            response.set_header(header);
+        }
+        Ok(response)
+    }
+}
+
+#[catch(500)]
+pub fn internal_server_error(request: &Request<'_>) -> status::Custom<Json<ErrorResponse>> {
+    render(Status::InternalServerError, request)
+}
+
+#[catch(503)]
+pub fn service_unavailable(request: &Request<'_>) -> status::Custom<Json<ErrorResponse>> {
+    render(Status::ServiceUnavailable, request)
+}
+
+#[catch(508)]
+pub fn loop_detected(request: &Request<'_>) -> status::Custom<Json<ErrorResponse>> {
+    render(Status::LoopDetected, request)
+}
+
+/// Any other status Rocket raises itself, so no error leaves as HTML
+#[catch(default)]
+pub fn default(status: Status, request: &Request<'_>) -> status::Custom<Json<ErrorResponse>> {
+    render(status, request)
+}
+
+/// Render a caught status in the ErrorResponse schema, using the message
+/// of the guard error that caused it when one was stashed
+fn render(status: Status, request: &Request<'_>) -> status::Custom<Json<ErrorResponse>> {
+    let message = request
+        .local_cache(|| StashedError(None))
+        .0
+        .clone()
+        .unwrap_or_else(|| status.reason_lossy().to_string());
+
+    let error = ErrorResponse::new(status, message).for_request(request);
+    status::Custom(status, Json(error))
+}
@@ -1,77 +1,180 @@
-use rocket::http::Status;
-use rocket::response::status;
-use rocket::serde::json::Json;
+pub mod catchers;
+
+use crate::middleware::RequestIdValue;
+use rocket::Request;
+use rocket::http::{Header, Status};
+use rocket::response::{self, Responder, status};
+use rocket::serde::json::{Json, Value};
 use rocket::serde::{Deserialize, Serialize};
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ApiError {
-    #[allow(dead_code)]
     #[error("Not found: {0}")]
     NotFound(String),
 
-    #[allow(dead_code)]
     #[error("Bad request: {0}")]
     BadRequest(String),
 
-    #[allow(dead_code)]
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
 
-    #[allow(dead_code)]
     #[error("Forbidden: {0}")]
     Forbidden(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
+    #[error("URI too long: {0}")]
+    UriTooLong(String),
+
+    #[error("Upgrade required: {0}")]
+    UpgradeRequired(String),
+
+    #[error("Loop detected: {0}")]
+    LoopDetected(String),
+
+    #[error("Bad gateway: {0}")]
+    BadGateway(String),
+
     #[error("Service unavailable: {0}")]
     ServiceUnavailable(String),
 
     #[error("Internal server error: {0}")]
     InternalServerError(String),
 
-    #[allow(dead_code)]
     #[error("Request timeout: {0}")]
     RequestTimeout(String),
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct ErrorResponse {
     pub status: u16,
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<String>,
+    /// Original body of an upstream error, when upstream errors are wrapped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<Value>,
+    /// ID of the failed request, for callers to quote when reporting it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ErrorResponse {
+    pub fn new(status: Status, message: impl Into<String>) -> Self {
+        Self {
+            status: status.code,
+            message: message.into(),
+            details: None,
+            upstream: None,
+            request_id: None,
+        }
+    }
+
+    /// This error as sent in answer to `request`, carrying its request ID
+    pub fn for_request(self, request: &Request<'_>) -> Self {
+        let request_id = request.local_cache(|| RequestIdValue(uuid::Uuid::new_v4().to_string()));
+        Self {
+            request_id: Some(request_id.0.clone()),
+            ..self
+        }
+    }
+}
+
+/// Error responder produced by the gateway itself, with any extra headers
+/// to send. The request ID is filled in when it is sent, so errors can be
+/// built without the request at hand. The body is boxed to keep the error
+/// small in the `Result`s it travels in.
+#[derive(Debug, Clone)]
+pub struct ErrorResponder(
+    pub Status,
+    pub Json<Box<ErrorResponse>>,
+    pub Vec<Header<'static>>,
+);
+
+impl ErrorResponder {
+    /// This error with a `Retry-After` telling the caller when to try again
+    pub fn retry_after(mut self, wait: Duration) -> Self {
+        self.2.extend(ThrottleHeaders::retry_after(wait).0);
+        self
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ErrorResponder {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let ErrorResponder(status, Json(error), headers) = self;
+        let mut response =
+            status::Custom(status, Json(error.for_request(request))).respond_to(request)?;
+        for header in headers {
+            response.set_header(header);
+        }
+        Ok(response)
+    }
+}
+
+/// The single conversion every gateway-produced error goes through, so all
+/// of them share the `ErrorResponse` schema
+pub trait IntoErrorResponse {
+    /// `details` should only carry diagnostics safe to expose to the caller
+    fn into_error_response(self, details: Option<String>) -> ErrorResponder;
 }
 
+impl IntoErrorResponse for ApiError {
+    fn into_error_response(self, details: Option<String>) -> ErrorResponder {
+        let status = self.status_code();
+        let response = ErrorResponse {
+            details,
+            ..ErrorResponse::new(status, self.to_string())
+        };
+
+        ErrorResponder(status, Json(Box::new(response)), Vec::new())
+    }
+}
+
+/// Headers for a throttled caller, left by the guard that rejected the
+/// request for the 429 catcher to send
+#[derive(Default)]
+pub struct ThrottleHeaders(pub Vec<Header<'static>>);
+
+impl ThrottleHeaders {
+    /// `Retry-After` in whole seconds, rounded up and at least one
+    pub fn retry_after(wait: Duration) -> Self {
+        let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+        Self(vec![Header::new("Retry-After", seconds.max(1).to_string())])
+    }
+}
+
+/// Message of an error raised by a request or data guard. Rocket doesn't
+/// pass guard errors to catchers, so guards leave it in the request cache.
+pub struct StashedError(pub Option<String>);
+
 impl ApiError {
+    /// Keep this error's message on the request for the catchers to render
+    pub fn stash(&self, request: &Request<'_>) {
+        request.local_cache(|| StashedError(Some(self.to_string())));
+    }
+
     pub fn status_code(&self) -> Status {
         match self {
             ApiError::NotFound(_) => Status::NotFound,
             ApiError::BadRequest(_) => Status::BadRequest,
             ApiError::Unauthorized(_) => Status::Unauthorized,
             ApiError::Forbidden(_) => Status::Forbidden,
+            ApiError::Conflict(_) => Status::Conflict,
+            ApiError::TooManyRequests(_) => Status::TooManyRequests,
+            ApiError::UriTooLong(_) => Status::UriTooLong,
+            ApiError::UpgradeRequired(_) => Status::UpgradeRequired,
+            ApiError::LoopDetected(_) => Status::LoopDetected,
+            ApiError::BadGateway(_) => Status::BadGateway,
             ApiError::ServiceUnavailable(_) => Status::ServiceUnavailable,
             ApiError::InternalServerError(_) => Status::InternalServerError,
             ApiError::RequestTimeout(_) => Status::GatewayTimeout,
         }
     }
-
-    #[allow(dead_code)]
-    pub fn to_response(&self, include_details: bool) -> status::Custom<Json<ErrorResponse>> {
-        let status = self.status_code();
-        let message = self.to_string();
-
-        let details = if include_details {
-            Some(message.clone())
-        } else {
-            None
-        };
-
-        let response = ErrorResponse {
-            status: status.code,
-            message,
-            details,
-        };
-
-        status::Custom(status, Json(response))
-    }
 }
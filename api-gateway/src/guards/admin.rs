@@ -0,0 +1,40 @@
+// src/guards/admin.rs
+use crate::config::live;
+use crate::errors::ApiError;
+use crate::guards::api_key::ApiKeyGuard;
+use crate::middleware;
+use log::warn;
+use rocket::request::{FromRequest, Outcome, Request};
+
+/// Request guard for `/api/admin` routes: the caller's address must be in
+/// `ADMIN_IP_ALLOWLIST`, when one is set, before its API key is checked.
+/// Only the socket peer, or `TRUSTED_IP_HEADER` when set, counts;
+/// `X-Forwarded-For` and `X-Real-IP` are ignored, as anyone can send them.
+pub struct AdminGuard;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminGuard {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(config) = live::current(request) else {
+            let err = ApiError::InternalServerError("Configuration not available".into());
+            return Outcome::Error((err.status_code(), err));
+        };
+
+        let allowlist = &config.admin_ip_allowlist;
+        let client_ip = middleware::peer_ip(request);
+        if !allowlist.is_empty() && !client_ip.is_some_and(|ip| allowlist.contains(ip)) {
+            warn!(
+                "Rejected admin request to {} from {:?}, not in ADMIN_IP_ALLOWLIST",
+                request.uri(),
+                client_ip
+            );
+            let err = ApiError::Forbidden("Admin endpoints are not reachable from here".into());
+            err.stash(request);
+            return Outcome::Error((err.status_code(), err));
+        }
+
+        request.guard::<ApiKeyGuard>().await.map(|_| AdminGuard)
+    }
+}
@@ -0,0 +1,86 @@
+// src/guards/api_key.rs
+use crate::config::live;
+use crate::errors::{ApiError, ThrottleHeaders};
+use crate::services::digest::sha256_hex;
+use crate::services::ratelimit::{Denied, RateLimiter};
+use log::warn;
+use rocket::http::{Header, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use subtle::{Choice, ConstantTimeEq};
+
+/// Header carrying the API key for service-to-service calls
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+
+/// Request guard authenticating internal callers with a static API key.
+/// Each key is also held to the `QUOTA_LIMIT` requests per `QUOTA_WINDOW`.
+pub struct ApiKeyGuard;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiKeyGuard {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(config) = live::current(request) else {
+            let err = ApiError::InternalServerError("Configuration not available".into());
+            return Outcome::Error((err.status_code(), err));
+        };
+
+        let Some(provided) = request.headers().get_one(API_KEY_HEADER) else {
+            return unauthorized(request, "Missing API key");
+        };
+
+        if !is_valid_key(&config.api_keys, provided) {
+            warn!("Rejected request with invalid API key to {}", request.uri());
+            return unauthorized(request, "Invalid API key");
+        }
+
+        let Some(limiter) = request.rocket().state::<RateLimiter>() else {
+            return Outcome::Success(ApiKeyGuard);
+        };
+        if !config.quota.is_enabled() {
+            return Outcome::Success(ApiKeyGuard);
+        }
+
+        // Counters are keyed on a hash, so keys never reach the store
+        let quota_key = format!("quota:{}", sha256_hex(provided.as_bytes()));
+        match limiter.hit(&quota_key, config.quota).await {
+            Ok(_) => Outcome::Success(ApiKeyGuard),
+            Err(Denied::Unchecked) => {
+                let err = ApiError::ServiceUnavailable("API key quota can't be checked".into());
+                err.stash(request);
+                Outcome::Error((err.status_code(), err))
+            }
+            Err(Denied::Limited(reset)) => {
+                warn!("API key quota exhausted for {}", request.uri());
+                let err = ApiError::TooManyRequests("API key quota exhausted".into());
+                err.stash(request);
+                request.local_cache(|| {
+                    let mut headers = ThrottleHeaders::retry_after(reset);
+                    headers.0.push(Header::new("X-RateLimit-Remaining", "0"));
+                    headers.0.push(Header::new(
+                        "X-RateLimit-Reset",
+                        reset.as_secs().max(1).to_string(),
+                    ));
+                    headers
+                });
+                Outcome::Error((Status::TooManyRequests, err))
+            }
+        }
+    }
+}
+
+/// Compare the provided key against every configured key in constant time.
+/// All keys are checked so the match position doesn't leak through timing.
+pub fn is_valid_key(valid_keys: &[String], provided: &str) -> bool {
+    let matched = valid_keys.iter().fold(Choice::from(0), |acc, key| {
+        acc | key.as_bytes().ct_eq(provided.as_bytes())
+    });
+
+    matched.into()
+}
+
+fn unauthorized(request: &Request<'_>, message: &str) -> Outcome<ApiKeyGuard, ApiError> {
+    let err = ApiError::Unauthorized(message.into());
+    err.stash(request);
+    Outcome::Error((Status::Unauthorized, err))
+}
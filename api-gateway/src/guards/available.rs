@@ -0,0 +1,46 @@
+// src/guards/available.rs
+use crate::errors::ApiError;
+use crate::services::switches::RouteSwitches;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+
+/// Request guard for proxied routes, rejecting them with 503 while the
+/// gateway is in maintenance mode or the route's group has been disabled.
+/// List it first so the request is turned away before the body is read.
+pub struct Available;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Available {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(switches) = request.rocket().state::<RouteSwitches>() else {
+            return Outcome::Success(Available);
+        };
+
+        if switches.maintenance() {
+            return unavailable(request, "Under maintenance".into());
+        }
+
+        if let Some(group) = route_group(request)
+            && !switches.is_enabled(group)
+        {
+            return unavailable(request, format!("{} routes are disabled", group));
+        }
+
+        Outcome::Success(Available)
+    }
+}
+
+/// Group of the matched route, taken from its `/api/<group>` mount point
+fn route_group<'r>(request: &'r Request<'_>) -> Option<&'r str> {
+    let base = request.route()?.uri.base();
+    base.strip_prefix("/api/")
+        .map(|rest| rest.split('/').next().unwrap_or(rest))
+}
+
+fn unavailable(request: &Request<'_>, message: String) -> Outcome<Available, ApiError> {
+    let err = ApiError::ServiceUnavailable(message);
+    err.stash(request);
+    Outcome::Error((Status::ServiceUnavailable, err))
+}
@@ -0,0 +1,56 @@
+// src/guards/caller.rs
+use crate::errors::ApiError;
+use crate::guards::api_key::{API_KEY_HEADER, ApiKeyGuard};
+use crate::guards::jwt::{self, Claims, JwtGuard};
+use rocket::request::{FromRequest, Outcome, Request};
+use std::fmt;
+
+/// Request guard for proxied service routes: a user with a valid bearer
+/// token, or an internal tool with a valid `X-Api-Key`, which skips the JWT
+/// flow but is held to its quota by `ApiKeyGuard`
+pub enum Caller {
+    User(Claims),
+    Service,
+}
+
+impl Caller {
+    /// ID of the calling user; internal tools call as no user
+    pub fn user_id(&self) -> Option<&str> {
+        match self {
+            Caller::User(claims) => Some(&claims.user_id),
+            Caller::Service => None,
+        }
+    }
+}
+
+impl fmt::Display for Caller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Caller::User(claims) => write!(f, "user {}", claims.user_id),
+            Caller::Service => write!(f, "an internal caller"),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Caller {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        if let Some(claims) = jwt::claims(request) {
+            return Outcome::Success(Caller::User(claims.clone()));
+        }
+
+        // Without a key, callers get the bearer token's error
+        if request.headers().contains(API_KEY_HEADER) {
+            return request
+                .guard::<ApiKeyGuard>()
+                .await
+                .map(|_| Caller::Service);
+        }
+        request
+            .guard::<JwtGuard>()
+            .await
+            .map(|JwtGuard(claims)| Caller::User(claims))
+    }
+}
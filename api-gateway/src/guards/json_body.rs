@@ -0,0 +1,154 @@
+// src/guards/json_body.rs
+use crate::config::live;
+use crate::errors::ApiError;
+use rocket::data::{Data, FromData, Limits, Outcome, ToByteUnit};
+use rocket::http::Status;
+use rocket::request::Request;
+use rocket::serde::DeserializeOwned;
+
+/// Size of the request body as read by a body guard, for requests that
+/// didn't announce a Content-Length
+pub struct RequestBodySize(pub usize);
+
+/// JSON body guard that checks the Content-Type and reports malformed
+/// payloads as `ApiError::BadRequest` instead of Rocket's bare 422
+pub struct JsonBody<T>(pub T);
+
+impl<T> JsonBody<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromData<'r> for JsonBody<T> {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        let is_json = request
+            .content_type()
+            .is_some_and(|content_type| content_type.is_json());
+
+        if !is_json {
+            let err = ApiError::BadRequest("Content-Type must be application/json".into());
+            return reject(request, Status::BadRequest, err);
+        }
+
+        let limit = request.limits().get("json").unwrap_or(Limits::JSON);
+        let body = match data.open(limit).into_string().await {
+            Ok(body) if body.is_complete() => {
+                let body = body.into_inner();
+                request.local_cache(|| RequestBodySize(body.len()));
+                body
+            }
+            Ok(_) => {
+                let err = ApiError::BadRequest(format!(
+                    "Request body exceeds the {} limit",
+                    limit.as_u64().bytes()
+                ));
+                return reject(request, Status::PayloadTooLarge, err);
+            }
+            Err(e) => {
+                let err = ApiError::BadRequest(format!("Unable to read request body: {}", e));
+                return reject(request, Status::BadRequest, err);
+            }
+        };
+
+        if let Some(config) = live::current(request)
+            && let Err(message) =
+                check_structure(&body, config.max_json_depth, config.max_json_keys)
+        {
+            return reject(request, Status::BadRequest, ApiError::BadRequest(message));
+        }
+
+        match serde_json::from_str::<T>(&body) {
+            Ok(value) => Outcome::Success(JsonBody(value)),
+            Err(e) => {
+                let err = ApiError::BadRequest(format!("Invalid JSON body: {}", e));
+                reject(request, Status::BadRequest, err)
+            }
+        }
+    }
+}
+
+/// Like `JsonBody`, for routes where the body is optional: an empty body
+/// without a Content-Type yields `None`, anything else must be valid JSON
+pub struct MaybeJsonBody<T>(pub Option<T>);
+
+impl<T> MaybeJsonBody<T> {
+    pub fn into_inner(self) -> Option<T> {
+        self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromData<'r> for MaybeJsonBody<T> {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, mut data: Data<'r>) -> Outcome<'r, Self> {
+        if request.content_type().is_none() && data.peek(1).await.is_empty() {
+            return Outcome::Success(MaybeJsonBody(None));
+        }
+
+        JsonBody::<T>::from_data(request, data)
+            .await
+            .map(|body| MaybeJsonBody(Some(body.into_inner())))
+    }
+}
+
+/// Scan raw JSON for its nesting depth and object key count without
+/// building a value, so pathological payloads are refused before parsing
+fn check_structure(body: &str, max_depth: usize, max_keys: usize) -> Result<(), String> {
+    let mut depth = 0usize;
+    let mut keys = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for byte in body.bytes() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+                if depth > max_depth {
+                    return Err(format!(
+                        "JSON body exceeds the maximum nesting depth of {}",
+                        max_depth
+                    ));
+                }
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            // Outside of strings a colon only ever separates an object key
+            b':' => {
+                keys += 1;
+                if keys > max_keys {
+                    return Err(format!(
+                        "JSON body exceeds the maximum of {} keys",
+                        max_keys
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn reject<'r, T>(
+    request: &'r Request<'_>,
+    status: Status,
+    err: ApiError,
+) -> Outcome<'r, T, ApiError> {
+    err.stash(request);
+    Outcome::Error((status, err))
+}
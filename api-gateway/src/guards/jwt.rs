@@ -0,0 +1,96 @@
+// src/guards/jwt.rs
+use crate::config::live;
+use crate::errors::ApiError;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode};
+use log::debug;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use serde::Deserialize;
+
+/// Claims carried by the user service's access tokens
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Claims {
+    pub user_id: String,
+    pub email: String,
+    pub role: String,
+    pub exp: u64,
+}
+
+/// Request guard requiring a valid `Authorization: Bearer <token>` issued
+/// by the user service. The header is still forwarded upstream.
+pub struct JwtGuard(pub Claims);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for JwtGuard {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match authenticate(request) {
+            Ok(claims) => Outcome::Success(JwtGuard(claims.clone())),
+            Err(err) => {
+                if err.status_code() == Status::Unauthorized {
+                    err.stash(request);
+                }
+                Outcome::Error((err.status_code(), err))
+            }
+        }
+    }
+}
+
+/// Outcome of checking the request's bearer token, worked out once per request
+enum Verified {
+    Valid(Claims),
+    Rejected(&'static str),
+    Unconfigured,
+}
+
+/// Claims of the request's bearer token, or the 401 it earns the request
+pub fn authenticate<'r>(request: &'r Request<'_>) -> Result<&'r Claims, ApiError> {
+    match verify(request) {
+        Verified::Valid(claims) => Ok(claims),
+        Verified::Rejected(message) => Err(ApiError::Unauthorized(message.to_string())),
+        Verified::Unconfigured => Err(ApiError::InternalServerError(
+            "Configuration not available".into(),
+        )),
+    }
+}
+
+/// Claims of the request's bearer token when it is valid, without failing
+/// the request when it isn't
+pub fn claims<'r>(request: &'r Request<'_>) -> Option<&'r Claims> {
+    match verify(request) {
+        Verified::Valid(claims) => Some(claims),
+        _ => None,
+    }
+}
+
+fn verify<'r>(request: &'r Request<'_>) -> &'r Verified {
+    request.local_cache(|| {
+        let Some(config) = live::current(request) else {
+            return Verified::Unconfigured;
+        };
+
+        let Some(token) = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+        else {
+            return Verified::Rejected("Missing bearer token");
+        };
+
+        let key = DecodingKey::from_secret(config.jwt_secret.as_bytes());
+        match decode::<Claims>(token.trim(), &key, &Validation::new(Algorithm::HS256)) {
+            Ok(data) => Verified::Valid(data.claims),
+            Err(e) => {
+                debug!("Rejected bearer token for {}: {}", request.uri(), e);
+                match e.kind() {
+                    ErrorKind::ExpiredSignature => Verified::Rejected("Token expired"),
+                    _ => Verified::Rejected("Invalid token"),
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,51 @@
+// src/guards/metrics.rs
+use super::api_key::{API_KEY_HEADER, is_valid_key};
+use crate::config::live;
+use crate::errors::ApiError;
+use log::warn;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+
+/// Request guard protecting the metrics endpoint. Accepts either a valid
+/// API key or `Authorization: Bearer <METRICS_TOKEN>`; left open in
+/// development unless `METRICS_OPEN_IN_DEVELOPMENT=false`.
+pub struct MetricsGuard;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MetricsGuard {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(config) = live::current(request) else {
+            let err = ApiError::InternalServerError("Configuration not available".into());
+            return Outcome::Error((err.status_code(), err));
+        };
+
+        if config.is_development() && config.metrics_open_in_development {
+            return Outcome::Success(MetricsGuard);
+        }
+
+        let api_key = request.headers().get_one(API_KEY_HEADER);
+        if api_key.is_some_and(|key| is_valid_key(&config.api_keys, key)) {
+            return Outcome::Success(MetricsGuard);
+        }
+
+        let bearer = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let token_matches = match (&config.metrics_token, bearer) {
+            (Some(token), Some(bearer)) => is_valid_key(std::slice::from_ref(token), bearer),
+            _ => false,
+        };
+
+        if token_matches {
+            Outcome::Success(MetricsGuard)
+        } else {
+            warn!("Rejected unauthenticated metrics request");
+            let err = ApiError::Unauthorized("Metrics require authentication".into());
+            err.stash(request);
+            Outcome::Error((Status::Unauthorized, err))
+        }
+    }
+}
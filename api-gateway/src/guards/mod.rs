@@ -0,0 +1,10 @@
+/// Request guards shared across route modules
+pub mod admin;
+pub mod api_key;
+pub mod available;
+pub mod caller;
+pub mod json_body;
+pub mod jwt;
+pub mod metrics;
+pub mod rate_limit;
+pub mod upload;
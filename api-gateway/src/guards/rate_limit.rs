@@ -0,0 +1,58 @@
+// src/guards/rate_limit.rs
+use crate::config::live;
+use crate::errors::{ApiError, ThrottleHeaders};
+use crate::middleware;
+use crate::services::ratelimit::{Denied, RateLimiter};
+use log::warn;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+
+/// Request guard throttling the password reset routes per client address
+/// (see `middleware::peer_ip`) and route, so they can't be used to enumerate accounts or flood inboxes.
+/// List it before the body guard.
+pub struct PasswordResetLimit;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for PasswordResetLimit {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let (Some(limiter), Some(config)) = (
+            request.rocket().state::<RateLimiter>(),
+            live::current(request),
+        ) else {
+            return Outcome::Success(PasswordResetLimit);
+        };
+
+        let limit = config.password_reset_rate_limit;
+        if !limit.is_enabled() {
+            return Outcome::Success(PasswordResetLimit);
+        }
+
+        // Not `client_ip`, which would let a rotating X-Real-IP reset the count
+        let client =
+            middleware::peer_ip(request).map_or_else(|| "unknown".to_string(), |ip| ip.to_string());
+        // Keyed on the route, so path variants share one allowance
+        let route = request.route().map_or_else(
+            || request.uri().path().to_string(),
+            |route| route.uri.to_string(),
+        );
+        let key = format!("{}:{}", route, client);
+
+        match limiter.hit(&key, limit).await {
+            Ok(_) => Outcome::Success(PasswordResetLimit),
+            Err(Denied::Unchecked) => {
+                let err = ApiError::ServiceUnavailable("Password reset is unavailable".into());
+                err.stash(request);
+                Outcome::Error((err.status_code(), err))
+            }
+            Err(Denied::Limited(wait)) => {
+                warn!("Throttled {} from {}", route, client);
+                let err = ApiError::TooManyRequests("Too many password reset attempts".into());
+                err.stash(request);
+                request.local_cache(|| ThrottleHeaders::retry_after(wait));
+                Outcome::Error((Status::TooManyRequests, err))
+            }
+        }
+    }
+}
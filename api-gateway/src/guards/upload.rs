@@ -0,0 +1,63 @@
+// src/guards/upload.rs
+use crate::config::live;
+use crate::errors::ApiError;
+use crate::guards::json_body::MaybeJsonBody;
+use rocket::data::{Data, FromData, Outcome};
+use rocket::http::Status;
+use rocket::request::Request;
+use rocket::serde::json::Value;
+
+/// Body of a catch-all proxy request: JSON as `MaybeJsonBody` reads it, or
+/// a multipart upload left unread so it can be streamed upstream, boxed as
+/// it's far larger than a JSON value
+pub enum ProxyBody<'r> {
+    Json(Option<Value>),
+    Multipart(Box<Upload<'r>>),
+}
+
+/// A `multipart/form-data` body with the headers needed to pass it on as is
+pub struct Upload<'r> {
+    /// Content-Type as received, boundary included
+    pub content_type: String,
+    pub length: Option<u64>,
+    pub limit: u64,
+    pub data: Data<'r>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for ProxyBody<'r> {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        let is_multipart = request
+            .content_type()
+            .is_some_and(|content_type| content_type.is_form_data());
+        let Some(content_type) = request
+            .headers()
+            .get_one("Content-Type")
+            .filter(|_| is_multipart)
+        else {
+            return MaybeJsonBody::<Value>::from_data(request, data)
+                .await
+                .map(|body| ProxyBody::Json(body.into_inner()));
+        };
+
+        let limit = live::current(request).map_or(u64::MAX, |config| config.max_upload_bytes);
+        let length = request
+            .headers()
+            .get_one("Content-Length")
+            .and_then(|length| length.parse::<u64>().ok());
+        if length.is_some_and(|length| length > limit) {
+            let err = ApiError::BadRequest(format!("Upload exceeds the {} byte limit", limit));
+            err.stash(request);
+            return Outcome::Error((Status::PayloadTooLarge, err));
+        }
+
+        Outcome::Success(ProxyBody::Multipart(Box::new(Upload {
+            content_type: content_type.to_string(),
+            length,
+            limit,
+            data,
+        })))
+    }
+}
@@ -5,48 +5,69 @@ extern crate dotenv;
 
 mod config;
 mod errors;
+mod guards;
 mod middleware;
 mod routes;
 mod services;
+#[cfg(test)]
+mod tests;
 
 use config::app::AppConfig;
+use config::live::LiveConfig;
+use guards::metrics::MetricsGuard;
 use dotenv::dotenv;
+use env_logger::Env;
 use log::{debug, error, info, warn};
-use metrics_exporter_prometheus::PrometheusBuilder;
+use metrics_exporter_prometheus::PrometheusHandle;
 use rocket::fairing::AdHoc;
-use rocket::http::Method;
+use rocket::{Build, Rocket};
+use rocket::http::{ContentType, Method};
 use rocket_cors::{AllowedHeaders, AllowedOrigins};
-use routes::{users, health};
+use routes::{admin, customer, grpc, users, health, notifications, preflight, proxy, purchasing, sales, version};
+use services::grpcweb::GrpcChannel;
+use services::idempotency::IdempotencyStore;
+use services::process::ProcessMetrics;
+use services::ratelimit::RateLimiter;
+use services::stats::RequestStats;
+use services::switches::RouteSwitches;
+use services::selftest;
+use services::telemetry::{self, LogFormat};
+use services::upstream::Upstreams;
 
 #[launch]
 fn rocket() -> _ {
-    // Force log level if not set
-    if std::env::var("RUST_LOG").is_err() {
-        unsafe {
-            std::env::set_var("RUST_LOG", "debug,rocket=info");
+    // Load environment variables from .env file if it exists
+    dotenv().ok();
+
+    // Load application configuration, exiting cleanly when it is invalid
+    let config = match AppConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            init_logging("info", LogFormat::Text);
+            error!("Invalid configuration: {}", e);
+            std::process::exit(1);
         }
-    }
+    };
 
-    // Initialize logging
-    env_logger::init();
+    init_logging(&config.log_level, config.log_format);
 
     info!("====== API Gateway Initialization Starting ======");
-
-    // Load environment variables from .env file if it exists
-    dotenv().ok();
-    debug!("Environment variables loaded");
-
-    // Load application configuration
-    let config = AppConfig::from_env();
     info!("Configuration loaded - API Gateway on port {}", config.port);
-    
+
     // Log service URLs for debugging
     debug!("Using USER_SERVICE_URL: {}", config.user_service_url);
 
+    let upstreams = match Upstreams::from_config(&config) {
+        Ok(upstreams) => upstreams,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
     // Set up metrics
     info!("Setting up metrics...");
-    let builder = PrometheusBuilder::new();
-    let recorder_result = builder.install_recorder();
+    let recorder_result = telemetry::install_recorder(&config);
     
     let prometheus_handle = match recorder_result {
         Ok(handle) => {
@@ -59,6 +80,25 @@ fn rocket() -> _ {
         }
     };
 
+    let self_test = selftest::requested(&config, std::env::args());
+    let rocket = build(config, upstreams, prometheus_handle);
+    if self_test {
+        // Not yet in Rocket's runtime, which only starts on launch
+        let report = rocket::execute(selftest::run(rocket));
+        println!("{}", report);
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
+    rocket
+}
+
+/// Assemble the gateway from loaded configuration. Process-wide setup
+/// (logging, the metrics recorder) is left to the caller, so tests can
+/// build as many instances as they need.
+fn build(
+    config: AppConfig,
+    upstreams: Upstreams,
+    prometheus_handle: PrometheusHandle,
+) -> Rocket<Build> {
     // Configure CORS
     info!("Configuring CORS...");
     let cors_options = rocket_cors::CorsOptions {
@@ -67,6 +107,7 @@ fn rocket() -> _ {
             Method::Get,
             Method::Post,
             Method::Put,
+            Method::Patch,
             Method::Delete,
             Method::Options,
         ]
@@ -75,6 +116,13 @@ fn rocket() -> _ {
         .collect(),
         allowed_headers: AllowedHeaders::all(),
         allow_credentials: true,
+        expose_headers: config
+            .cors_expose_headers
+            .iter()
+            .cloned()
+            .chain([middleware::REQUEST_ID_HEADER.to_string()])
+            .collect(),
+        max_age: config.cors_max_age,
         ..Default::default()
     };
     
@@ -91,17 +139,117 @@ fn rocket() -> _ {
         }
     };
 
+    let store = config.store.open();
+    let idempotency_store = IdempotencyStore::new(
+        store.clone(),
+        config.idempotency_ttl,
+        config.gateway_request_timeout,
+    );
+    if config.upstream_accept_invalid_certs {
+        if config.is_development() {
+            warn!("Upstream TLS certificates are not verified");
+        } else {
+            warn!("UPSTREAM_ACCEPT_INVALID_CERTS is set but ignored outside development");
+        }
+    }
+
+    if config.chaos.is_enabled() {
+        if config.is_development() {
+            warn!("Chaos injection enabled: {:?}", config.chaos);
+        } else {
+            warn!("Chaos injection is configured but ignored outside development");
+        }
+    }
+
+    let admission = middleware::AdmissionControl::new(config.max_inflight, config.shed_retry_after);
+    let route_switches = RouteSwitches::new(config.maintenance_mode);
+    if config.maintenance_mode {
+        warn!("Starting in maintenance mode, proxied routes will answer 503");
+    }
+
     info!("Building Rocket instance...");
     
     // Build and configure Rocket instance
-    let rocket_instance = rocket::build()
-        .manage(config)
-        .manage(prometheus_handle.clone())
+    let figment = rocket::Config::figment().merge(("keep_alive", config.keep_alive));
+    let rocket_instance = rocket::custom(figment)
+        .manage(LiveConfig::new(config, upstreams))
+        .manage(RequestStats::new())
+        .manage(health::ReadinessCache::default())
+        .manage(idempotency_store)
+        .manage(RateLimiter::new(store))
+        .manage(route_switches)
+        .manage(prometheus_handle)
+        .manage(ProcessMetrics::default())
+        .manage(GrpcChannel::default())
+        .register(
+            "/",
+            catchers![
+                errors::catchers::bad_request,
+                errors::catchers::unauthorized,
+                errors::catchers::not_found,
+                errors::catchers::payload_too_large,
+                errors::catchers::unprocessable_entity,
+                errors::catchers::too_many_requests,
+                errors::catchers::internal_server_error,
+                errors::catchers::service_unavailable,
+                errors::catchers::loop_detected,
+                errors::catchers::default
+            ],
+        )
+        .register("/api", catchers![errors::catchers::unknown_api_path])
+        .mount("/api", routes![preflight::preflight])
         .mount("/api/metrics", rocket::routes![metrics])
-        .mount("/api/health", routes![health::check])
+        .mount("/api/health", routes![health::check, health::live, health::ready])
+        .mount("/api/version", routes![version::info])
+        .mount(
+            "/api/admin",
+            routes![
+                admin::maintenance,
+                admin::set_maintenance,
+                admin::routes,
+                admin::set_route,
+                admin::reload,
+                admin::upstreams,
+                admin::health
+            ],
+        )
         .mount(
             "/api/users",
-            routes![users::login, users::register, users::refresh, users::logout],
+            routes![
+                users::login,
+                users::register,
+                users::refresh,
+                users::logout,
+                users::forgot_password,
+                users::reset_password,
+                users::me
+            ],
+        )
+        .mount(
+            "/api/sales",
+            routes![sales::create_order, sales::get_order, sales::get_orders],
+        )
+        .mount(
+            "/api/purchasing",
+            routes![
+                purchasing::create_purchase_order,
+                purchasing::get_purchase_order,
+                purchasing::get_purchase_orders
+            ],
+        )
+        .mount(
+            "/api/customers",
+            routes![
+                customer::get_customer,
+                customer::get_customer_activity,
+                customer::create_customer
+            ],
+        )
+        .mount("/api/notifications", routes![notifications::stream])
+        .mount("/api/grpc", routes![grpc::unary])
+        .mount(
+            "/api/proxy",
+            routes![proxy::get, proxy::post, proxy::put, proxy::patch, proxy::delete],
         )
         // Commented out services that are not implemented yet
         // .mount(
@@ -113,10 +261,6 @@ fn rocket() -> _ {
         //     ],
         // )
         // .mount(
-        //     "/api/sales",
-        //     routes![sales::create_order, sales::get_order, sales::get_orders],
-        // )
-        // .mount(
         //     "/api/inventory",
         //     routes![
         //         inventory::get_product,
@@ -124,41 +268,75 @@ fn rocket() -> _ {
         //         inventory::update_stock
         //     ],
         // )
-        // .mount(
-        //     "/api/purchasing",
-        //     routes![
-        //         purchasing::create_purchase_order,
-        //         purchasing::get_purchase_order,
-        //         purchasing::get_purchase_orders
-        //     ],
-        // )
-        // .mount(
-        //     "/api/customers",
-        //     routes![
-        //         customer::get_customer,
-        //         customer::get_customer_activity,
-        //         customer::create_customer
-        //     ],
-        // )
         .attach(cors)
+        .attach(middleware::CorsOverrides)
+        .attach(middleware::Rejections)
+        .attach(middleware::IpDenylist)
+        .attach(middleware::UriLength)
+        .attach(middleware::Waf)
+        .attach(middleware::TrailingSlashes)
+        .attach(middleware::MethodOverride)
+        .attach(admission)
         .attach(middleware::RequestId)
         .attach(middleware::RequestLogger)
+        .attach(middleware::HttpsOnly)
+        .attach(middleware::Authentication)
+        .attach(middleware::Tenants)
+        .attach(middleware::RequiredHeaders)
+        .attach(middleware::SecurityHeaders)
         .attach(middleware::ResponseTime)
-        .attach(AdHoc::on_liftoff("API Gateway Startup", |_| {
+        .attach(middleware::BodySizes)
+        .attach(middleware::RequestTimeout)
+        .attach(middleware::InFlightRequests)
+        .attach(AdHoc::on_liftoff("API Gateway Startup", |rocket| {
             Box::pin(async move {
                 info!("✅ API Gateway successfully started and ready!");
                 info!("Prometheus metrics available at /api/metrics");
                 
                 // This is the proper place to run Tokio tasks since we're in an async context
-                let user_service_url = "http://user-service:3000";
+                let Some(live) = rocket.state::<LiveConfig>() else {
+                    return;
+                };
+                let snapshot = live.snapshot();
+                let users = &snapshot.upstreams.users;
+                let user_service_url = users.next_target();
                 info!("Checking connectivity to user service...");
-                if let Err(e) = reqwest::get(&format!("{}/api/health", user_service_url)).await {
+                let probe = users
+                    .client()
+                    .get(format!("{}/api/health", user_service_url))
+                    .timeout(snapshot.config.startup_probe_timeout);
+                if let Err(e) = probe.send().await {
                     warn!("Could not connect to user service: {}. This may be expected if the service is not yet available.", e);
                 } else {
                     info!("Successfully connected to user service at {}", user_service_url);
                 }
             })
         }))
+        .attach(AdHoc::on_liftoff("Connection Pool Warmup", |rocket| {
+            Box::pin(async move {
+                let Some(live) = rocket.state::<LiveConfig>() else {
+                    return;
+                };
+                let snapshot = live.snapshot();
+                let connections = snapshot.config.warmup_connections;
+                if connections == 0 {
+                    return;
+                }
+
+                info!("Warming up {} connections per upstream replica...", connections);
+                let timeout = snapshot.config.startup_probe_timeout;
+                let upstreams = snapshot.upstreams.all();
+                let warmups = upstreams.iter().map(|upstream| upstream.warm_up(connections, timeout));
+                let results = rocket::futures::future::join_all(warmups).await;
+                for (upstream, answered) in upstreams.iter().zip(results) {
+                    if answered == 0 {
+                        warn!("Skipped warmup of {}, no replica answered", upstream.label);
+                    } else {
+                        info!("Warmed up {} connections to {}", answered, upstream.label);
+                    }
+                }
+            })
+        }))
         .attach(AdHoc::on_liftoff("Startup Info", |_| {
             Box::pin(async move {
                 info!("🚀 Rocket instance launched and processing requests");
@@ -169,7 +347,26 @@ fn rocket() -> _ {
     rocket_instance
 }
 
+/// Initialize logging from LOG_LEVEL, which RUST_LOG still overrides
+fn init_logging(level: &str, format: LogFormat) {
+    let mut builder = env_logger::Builder::from_env(Env::default().default_filter_or(level));
+    if format == LogFormat::Json {
+        builder.format(telemetry::json_record);
+    }
+    builder.init();
+}
+
+/// Prometheus text exposition format, which is what the exporter renders
+fn prometheus_text() -> ContentType {
+    ContentType::new("text", "plain").with_params([("version", "0.0.4"), ("charset", "utf-8")])
+}
+
 #[get("/")]
-fn metrics(prometheus_handle: &rocket::State<metrics_exporter_prometheus::PrometheusHandle>) -> String {
-    prometheus_handle.render()
+fn metrics(
+    _auth: MetricsGuard,
+    prometheus_handle: &rocket::State<PrometheusHandle>,
+    process: &rocket::State<ProcessMetrics>,
+) -> (ContentType, String) {
+    process.record();
+    (prometheus_text(), prometheus_handle.render())
 }
\ No newline at end of file
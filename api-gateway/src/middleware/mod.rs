@@ -1,13 +1,37 @@
 // src/middleware/mod.rs
-use log::{debug, info};
+use crate::config::live;
+use crate::errors::{ApiError, ErrorResponse};
+use crate::guards::api_key::{API_KEY_HEADER, is_valid_key};
+use crate::guards::json_body::RequestBodySize;
+use crate::guards::jwt::{self, Claims};
+use crate::services::cors::override_for;
+use crate::services::proxy::UpstreamCall;
+use crate::services::stats::RequestStats;
+use crate::services::telemetry::ACCESS_LOG_TARGET;
+use crate::services::tenant::{self, Tenant};
+use crate::services::timeouts::route_timeout;
+use crate::services::waf::WafRule;
+use log::{debug, info, warn};
+use metrics::Label;
 use rocket::{
     Request, Response,
     fairing::{Fairing, Info, Kind},
+    http::{ContentType, Header, Method, Status, ext::IntoOwned, uri::Origin},
+    request::{FromRequest, Outcome},
 };
+use std::convert::Infallible;
 use std::fmt;
-use std::time::Instant;
+use std::io::Cursor;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use uuid::Uuid;
 
+/// Header used to propagate the request ID to upstream services
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
 // Request ID middleware
 pub struct RequestId;
 
@@ -21,13 +45,18 @@ impl Fairing for RequestId {
     }
 
     async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
-        let request_id = Uuid::new_v4().to_string();
+        request.local_cache(Instant::now);
+        let request_id = match live::current(request) {
+            Some(config) => config.request_id_format.generate(),
+            None => Uuid::new_v4().to_string(),
+        };
         request.local_cache(|| RequestIdValue(request_id));
     }
 
-    async fn on_response<'r>(&self, request: &'r Request<'_>, _: &mut Response<'r>) {
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
         let request_id = request.local_cache(|| RequestIdValue(Uuid::new_v4().to_string()));
         debug!("Request ID: {}", request_id);
+        response.set_raw_header(REQUEST_ID_HEADER, request_id.0.clone());
     }
 }
 
@@ -41,6 +70,54 @@ impl fmt::Display for RequestIdValue {
     }
 }
 
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestIdValue {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(
+            request
+                .local_cache(|| RequestIdValue(Uuid::new_v4().to_string()))
+                .clone(),
+        )
+    }
+}
+
+/// Per-request values gathered in one guard: the request ID and start time
+/// stamped by the `RequestId` fairing, the client address and, when the
+/// request carries a valid bearer token, its claims. Requests without one
+/// still get a context; routes that require a user take `JwtGuard`.
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    pub request_id: String,
+    pub client_ip: Option<IpAddr>,
+    pub started: Instant,
+    pub auth: Option<Claims>,
+    pub tenant: Option<String>,
+}
+
+impl RequestContext {
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestContext {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let request_id = request.local_cache(|| RequestIdValue(Uuid::new_v4().to_string()));
+        Outcome::Success(RequestContext {
+            request_id: request_id.0.clone(),
+            client_ip: request.client_ip(),
+            started: *request.local_cache(Instant::now),
+            auth: jwt::claims(request).cloned(),
+            tenant: tenant::current(request).map(str::to_string),
+        })
+    }
+}
+
 // Request logger middleware
 pub struct RequestLogger;
 
@@ -54,28 +131,787 @@ impl Fairing for RequestLogger {
     }
 
     async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
-        let method = request.method();
-        let uri = request.uri();
+        // Increment request counter
+        metrics::counter!("api_requests_total", request_labels(request)).increment(1);
+    }
 
-        let request_id = request.local_cache(|| RequestIdValue(Uuid::new_v4().to_string()));
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        access_log(request, response.status());
 
-        info!("[{}] {} {}", request_id, method, uri);
+        // Increment response counter
+        metrics::counter!("api_responses_total", request_labels(request)).increment(1);
+    }
+}
 
-        // Increment request counter
-        metrics::counter!("api_requests_total").increment(1);
+/// The one access log entry of a completed request. The fields are attached
+/// as structured values, which the JSON log format writes out.
+fn access_log(request: &Request<'_>, status: Status) {
+    let request_id = request.local_cache(|| RequestIdValue(Uuid::new_v4().to_string()));
+    let method = request.method();
+    let uri = logged_uri(request);
+    let route = route_label(request);
+    let total_ms = millis(request.local_cache(Instant::now).elapsed());
+    let call = request.local_cache(UpstreamCall::default);
+    let upstream_ms = call.time.map(millis);
+    let client_ip = request.client_ip().map(|ip| ip.to_string());
+
+    info!(
+        target: ACCESS_LOG_TARGET,
+        request_id = request_id.0.as_str(),
+        method = method.as_str(),
+        route = route.as_str(),
+        status = status.code,
+        total_ms = total_ms,
+        upstream_ms = upstream_ms,
+        retries = call.retries,
+        cache_status = call.cache_status,
+        client_ip = client_ip.as_deref();
+        "[{}] {} {} ({}) => {} in {:.1}ms, upstream {}, {} retries, cache {}, client {}",
+        request_id,
+        method,
+        uri,
+        route,
+        status,
+        total_ms,
+        upstream_ms.map_or_else(|| "-".to_string(), |ms| format!("{:.1}ms", ms)),
+        call.retries,
+        call.cache_status.unwrap_or("-"),
+        client_ip.as_deref().unwrap_or("-")
+    );
+}
+
+/// Labels attached to the request's metrics, resolved once per request
+struct RequestLabels(Vec<Label>);
+
+fn request_labels(request: &Request<'_>) -> Vec<Label> {
+    request
+        .local_cache(|| {
+            let labels = live::current(request)
+                .and_then(|config| config.metric_label.as_ref())
+                .map(|label| {
+                    let value = label.value_for(request.headers().get_one(&label.header));
+                    vec![Label::new(label.name.clone(), value.to_string())]
+                })
+                .unwrap_or_default();
+            RequestLabels(labels)
+        })
+        .0
+        .clone()
+}
+
+/// The matched route's URI template, so IDs in the path don't fan out
+fn route_label(request: &Request<'_>) -> String {
+    request
+        .route()
+        .map_or_else(|| "unmatched".to_string(), |route| route.uri.to_string())
+}
+
+/// Path no route is mounted under. Rejected requests are pointed here so
+/// that Rocket runs none of the handlers.
+const REJECTED_PATH: &str = "/__gateway/rejected";
+
+/// Error a request fairing turned the request away with, and any headers
+/// to send along with it
+struct Rejection(Option<(Status, String, Vec<Header<'static>>)>);
+
+/// Turn a request away from a request fairing. Fairings can't end a request
+/// early, so the error is kept on the request and the request is routed to
+/// a path nothing serves; `Rejections` then swaps the resulting 404 for the
+/// error.
+pub fn reject(request: &mut Request<'_>, err: ApiError) {
+    reject_with(request, err, Vec::new());
+}
+
+/// `reject`, adding headers to the error response
+pub fn reject_with(request: &mut Request<'_>, err: ApiError, headers: Vec<Header<'static>>) {
+    request.local_cache(|| Rejection(Some((err.status_code(), err.to_string(), headers))));
+    let uri = request.uri().clone().into_owned();
+    request.local_cache(|| ReceivedUri(uri));
+    request.set_uri(Origin::parse(REJECTED_PATH).expect("valid rejection path"));
+}
+
+/// URI a rejected request came in with, before it was rerouted
+struct ReceivedUri(Origin<'static>);
+
+/// The request's URI as logged: the path, plus the query string when
+/// `LOG_QUERY_STRING` is on. Query strings often carry tokens and emails.
+fn logged_uri(request: &Request<'_>) -> String {
+    let uri = received_uri(request);
+    let with_query = live::current(request).is_some_and(|config| config.log_query_string);
+    match uri.query() {
+        Some(query) if with_query => format!("{}?{}", uri.path(), query),
+        _ => uri.path().to_string(),
+    }
+}
+
+/// The URI the request came in with, also once it has been rejected
+fn received_uri<'a>(request: &'a Request<'_>) -> &'a Origin<'a> {
+    if is_rejected(request) {
+        &request
+            .local_cache(|| ReceivedUri(request.uri().clone().into_owned()))
+            .0
+    } else {
+        request.uri()
+    }
+}
+
+/// Whether an earlier fairing already turned the request away
+fn is_rejected(request: &Request<'_>) -> bool {
+    request.uri().path() == REJECTED_PATH
+}
+
+// Rejected request responder, attach before any fairing that reads the status
+pub struct Rejections;
+
+#[rocket::async_trait]
+impl Fairing for Rejections {
+    fn info(&self) -> Info {
+        Info {
+            name: "Rejections",
+            kind: Kind::Response,
+        }
     }
 
     async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
-        let method = request.method();
+        let Some((status, message, headers)) = &request.local_cache(|| Rejection(None)).0 else {
+            return;
+        };
+
+        let error = ErrorResponse::new(*status, message.clone()).for_request(request);
+        let body = serde_json::to_string(&error).unwrap_or_default();
+        response.set_status(*status);
+        response.set_header(ContentType::JSON);
+        for header in headers {
+            response.set_header(header.clone());
+        }
+        response.set_sized_body(body.len(), Cursor::new(body));
+    }
+}
+
+/// Global admission control: caps the requests in flight across the whole
+/// gateway and sheds the excess with an immediate 503 instead of queuing it
+pub struct AdmissionControl {
+    slots: Option<Arc<Semaphore>>,
+    retry_after: u64,
+}
+
+impl AdmissionControl {
+    /// `max_inflight` of 0 admits everything
+    pub fn new(max_inflight: usize, retry_after: u64) -> Self {
+        Self {
+            slots: (max_inflight > 0).then(|| Arc::new(Semaphore::new(max_inflight))),
+            retry_after,
+        }
+    }
+}
+
+/// Admission slot held by a request. Like `InFlightGuard` it lives in the
+/// local cache and is released when Rocket drops the request.
+struct AdmissionSlot {
+    _permit: OwnedSemaphorePermit,
+}
+
+#[rocket::async_trait]
+impl Fairing for AdmissionControl {
+    fn info(&self) -> Info {
+        Info {
+            name: "Admission Control",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        let Some(slots) = &self.slots else {
+            return;
+        };
+        // Shedding health checks would get a busy instance restarted
+        if request.uri().path().starts_with("/api/health") {
+            return;
+        }
+
+        match slots.clone().try_acquire_owned() {
+            Ok(permit) => {
+                request.local_cache(|| AdmissionSlot { _permit: permit });
+            }
+            Err(_) => {
+                warn!(
+                    "Shedding {} {}, gateway at capacity",
+                    request.method(),
+                    request.uri()
+                );
+                metrics::counter!("api_requests_shed_total").increment(1);
+                reject_with(
+                    request,
+                    ApiError::ServiceUnavailable("Gateway is at capacity".into()),
+                    vec![Header::new("Retry-After", self.retry_after.to_string())],
+                );
+            }
+        }
+    }
+}
+
+/// How request paths ending in `/` are handled. Rocket's router tolerates
+/// the slash either way; stripping also gives logs, metrics and the
+/// idempotency store a single form of the path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailingSlash {
+    /// `/api/users/login/` is rewritten to `/api/users/login`
+    Strip,
+    /// Paths are left as sent
+    Keep,
+}
+
+impl FromStr for TrailingSlash {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "strip" => Ok(Self::Strip),
+            "keep" => Ok(Self::Keep),
+            other => Err(format!(
+                "unknown trailing slash mode '{}', expected strip or keep",
+                other
+            )),
+        }
+    }
+}
+
+/// Bytes of an overlong path kept for the logs
+const LOGGED_PATH_BYTES: usize = 64;
+
+// Client blocking middleware: turns away requests from an IP_DENYLIST range
+// with a 403, before any other fairing spends work on them
+pub struct IpDenylist;
+
+#[rocket::async_trait]
+impl Fairing for IpDenylist {
+    fn info(&self) -> Info {
+        Info {
+            name: "IP Denylist",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        let Some(denylist) = live::current(request)
+            .map(|config| &config.ip_denylist)
+            .filter(|denylist| !denylist.is_empty())
+        else {
+            return;
+        };
+
+        let Some(blocked) = client_addresses(request).find(|ip| denylist.contains(*ip)) else {
+            return;
+        };
+        warn!(
+            "Rejected {} {} from denylisted {}",
+            request.method(),
+            request.uri().path(),
+            blocked
+        );
+        metrics::counter!("api_ip_blocked_total").increment(1);
+        reject(request, ApiError::Forbidden("Access denied".into()));
+    }
+}
+
+/// The caller's address as the gateway can vouch for it: the value of
+/// `TRUSTED_IP_HEADER` when one is configured, otherwise the socket peer.
+/// Unlike `Request::client_ip`, a client-sent `X-Real-IP` is never believed.
+pub fn peer_ip(request: &Request<'_>) -> Option<IpAddr> {
+    match live::current(request).and_then(|config| config.trusted_ip_header.as_deref()) {
+        Some(header) => request
+            .headers()
+            .get_one(header)
+            .and_then(|value| value.trim().parse().ok()),
+        None => request.remote().map(|remote| remote.ip()),
+    }
+}
+
+/// The socket peer, the address Rocket resolved and `peer_ip`, then every
+/// `X-Forwarded-For` hop. Header addresses can be forged, so they may only
+/// be used to refuse requests, never to let them through; the socket peer
+/// is always among them, so a forged header can't hide it.
+pub fn client_addresses<'a>(request: &'a Request<'_>) -> impl Iterator<Item = IpAddr> + 'a {
+    let forwarded = request
+        .headers()
+        .get("X-Forwarded-For")
+        .flat_map(|value| value.split(','))
+        .filter_map(|hop| hop.trim().parse::<IpAddr>().ok());
+    request
+        .remote()
+        .map(|remote| remote.ip())
+        .into_iter()
+        .chain(request.client_ip())
+        .chain(peer_ip(request))
+        .chain(forwarded)
+}
+
+// URI length middleware: turns away requests whose URI (path and query string)
+// is longer than MAX_URI_LENGTH with a 414, logging only the start of the path
+pub struct UriLength;
+
+#[rocket::async_trait]
+impl Fairing for UriLength {
+    fn info(&self) -> Info {
+        Info {
+            name: "URI Length",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        let Some(max) = live::current(request)
+            .map(|config| config.max_uri_length)
+            .filter(|max| *max > 0)
+        else {
+            return;
+        };
         let uri = request.uri();
-        let status = response.status();
+        let length = uri.path().len() + uri.query().map_or(0, |query| query.len() + 1);
+        if length <= max {
+            return;
+        }
 
-        let request_id = request.local_cache(|| RequestIdValue(Uuid::new_v4().to_string()));
+        // Later fairings log the received URI, so keep only its start
+        let logged = truncated(uri);
+        warn!(
+            "Rejected {} {} from {:?}, a {} byte URI",
+            request.method(),
+            logged,
+            request.client_ip(),
+            length
+        );
+        request.local_cache(|| ReceivedUri(logged));
+        metrics::counter!("api_uri_too_long_total").increment(1);
+        reject(
+            request,
+            ApiError::UriTooLong(format!("URI exceeds {} bytes", max)),
+        );
+    }
+}
 
-        info!("[{}] {} {} => {}", request_id, method, uri, status);
+/// The first `LOGGED_PATH_BYTES` of the path, marked as cut
+fn truncated(uri: &Origin<'_>) -> Origin<'static> {
+    let path = uri.path().as_str();
+    let mut end = LOGGED_PATH_BYTES.min(path.len());
+    while !path.is_char_boundary(end) {
+        end -= 1;
+    }
+    Origin::parse_owned(format!("{}...", &path[..end])).unwrap_or(Origin::ROOT)
+}
 
-        // Increment response counter
-        metrics::counter!("api_responses_total").increment(1);
+// Edge filtering middleware: turns away requests matching a WAF_RULES rule with
+// a generic 400, before anything else looks at them
+pub struct Waf;
+
+#[rocket::async_trait]
+impl Fairing for Waf {
+    fn info(&self) -> Info {
+        Info {
+            name: "WAF",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        let Some(config) = live::current(request) else {
+            return;
+        };
+        let blocked = config
+            .waf_rules
+            .iter()
+            .find(|rule| rule.matches(request, config.waf_max_headers))
+            .map(WafRule::label);
+
+        if let Some(rule) = blocked {
+            warn!(
+                "Blocked {} {} from {:?} by WAF rule {}",
+                request.method(),
+                request.uri().path(),
+                request.client_ip(),
+                rule
+            );
+            metrics::counter!("api_waf_blocked_total", "rule" => rule).increment(1);
+            reject(request, ApiError::BadRequest("Request blocked".into()));
+        }
+    }
+}
+
+/// Header through which POST requests ask to be routed as another method
+pub const METHOD_OVERRIDE_HEADER: &str = "X-HTTP-Method-Override";
+
+/// Methods `METHOD_OVERRIDE_METHODS` may allow. Overriding never turns a
+/// POST into a safe method, which would bypass CSRF-style checks.
+pub const OVERRIDABLE_METHODS: &[Method] = &[Method::Put, Method::Patch, Method::Delete];
+
+// Method override middleware: a POST carrying X-HTTP-Method-Override is
+// routed, and so proxied, as the method it names. Runs before routing.
+pub struct MethodOverride;
+
+#[rocket::async_trait]
+impl Fairing for MethodOverride {
+    fn info(&self) -> Info {
+        Info {
+            name: "Method Override",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        if request.method() != Method::Post || is_rejected(request) {
+            return;
+        }
+        let Some(value) = request.headers().get_one(METHOD_OVERRIDE_HEADER) else {
+            return;
+        };
+        let Some(config) = live::current(request) else {
+            return;
+        };
+
+        let allowed = value
+            .trim()
+            .parse::<Method>()
+            .ok()
+            .filter(|method| config.method_overrides.contains(method));
+        match allowed {
+            Some(method) => {
+                debug!("Overriding POST {} as {}", request.uri(), method);
+                request.set_method(method);
+            }
+            None => {
+                let message = format!("Method override '{}' is not allowed", value.trim());
+                reject(request, ApiError::BadRequest(message));
+            }
+        }
+    }
+}
+
+// Trailing slash normalization middleware, runs before routing
+pub struct TrailingSlashes;
+
+#[rocket::async_trait]
+impl Fairing for TrailingSlashes {
+    fn info(&self) -> Info {
+        Info {
+            name: "Trailing Slashes",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        let strip = live::current(request)
+            .is_some_and(|config| config.trailing_slash == TrailingSlash::Strip);
+        let path = request.uri().path().as_str();
+        if !strip || path.len() <= 1 || !path.ends_with('/') {
+            return;
+        }
+
+        let mut normalized = path.trim_end_matches('/').to_string();
+        if normalized.is_empty() {
+            normalized.push('/');
+        }
+        if let Some(query) = request.uri().query() {
+            normalized.push('?');
+            normalized.push_str(query.as_str());
+        }
+
+        match Origin::parse_owned(normalized) {
+            Ok(uri) => {
+                debug!("Normalized {} to {}", request.uri(), uri);
+                request.set_uri(uri);
+            }
+            Err(e) => debug!("Kept {} as is: {}", request.uri(), e),
+        }
+    }
+}
+
+// Authentication middleware: every path outside PUBLIC_PATHS needs a valid
+// bearer token, or a valid API key from an internal caller. Handlers that
+// use the claims still take `JwtGuard`.
+pub struct Authentication;
+
+#[rocket::async_trait]
+impl Fairing for Authentication {
+    fn info(&self) -> Info {
+        Info {
+            name: "Authentication",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        // CORS preflights carry no credentials
+        if request.method() == Method::Options || is_rejected(request) {
+            return;
+        }
+        let Some(config) = live::current(request) else {
+            return;
+        };
+        if is_public(&config.public_paths, request.uri().path().as_str()) {
+            return;
+        }
+
+        let api_key = request.headers().get_one(API_KEY_HEADER);
+        if api_key.is_some_and(|key| is_valid_key(&config.api_keys, key)) {
+            return;
+        }
+
+        if let Err(err) = jwt::authenticate(request) {
+            debug!("Rejected unauthenticated request to {}", request.uri());
+            reject(request, err);
+        }
+    }
+}
+
+/// Whether `path` is one of `public` or below one of them
+pub fn is_public(public: &[String], path: &str) -> bool {
+    public.iter().any(|prefix| is_under(path, prefix))
+}
+
+/// Whether `path` is `prefix` or a path below it
+pub fn is_under(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Response headers through which the global CORS policy grants access
+const CORS_GRANT_HEADERS: &[&str] = &[
+    "Access-Control-Allow-Origin",
+    "Access-Control-Allow-Credentials",
+    "Access-Control-Allow-Methods",
+    "Access-Control-Allow-Headers",
+    "Access-Control-Expose-Headers",
+    "Access-Control-Max-Age",
+];
+
+// Per-route CORS overrides: requests from origins a CORS_ROUTE_ORIGINS rule
+// leaves out are refused with 403, and lose the grants the global policy
+// added. Attach after the global CORS fairing.
+pub struct CorsOverrides;
+
+impl CorsOverrides {
+    /// Whether the request's origin is refused by an override
+    fn refuses(request: &Request<'_>) -> bool {
+        let Some(origin) = request.headers().get_one("Origin") else {
+            return false;
+        };
+        let Some(config) = live::current(request) else {
+            return false;
+        };
+        let path = received_uri(request).path();
+        override_for(&config.cors_overrides, path.as_str()).is_some_and(|rule| !rule.allows(origin))
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for CorsOverrides {
+    fn info(&self) -> Info {
+        Info {
+            name: "CORS Overrides",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        if is_rejected(request) || !Self::refuses(request) {
+            return;
+        }
+        debug!(
+            "Refused origin {:?} for {}",
+            request.headers().get_one("Origin"),
+            request.uri()
+        );
+        reject(
+            request,
+            ApiError::Forbidden("Origin not allowed for this route".into()),
+        );
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        if Self::refuses(request) {
+            for name in CORS_GRANT_HEADERS {
+                response.remove_header(name);
+            }
+        }
+    }
+}
+
+// Tenant resolution middleware: resolves the request's tenant for the
+// upstream X-Tenant-Id header, and requires one under TENANT_SCOPED_PATHS
+pub struct Tenants;
+
+#[rocket::async_trait]
+impl Fairing for Tenants {
+    fn info(&self) -> Info {
+        Info {
+            name: "Tenants",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        if request.method() == Method::Options || is_rejected(request) {
+            return;
+        }
+        let Some(config) = live::current(request) else {
+            return;
+        };
+
+        let resolved = tenant::resolve(request, config);
+        let scoped = config
+            .tenant_scoped_paths
+            .iter()
+            .any(|prefix| is_under(request.uri().path().as_str(), prefix));
+        if resolved.is_none() && scoped {
+            debug!("No tenant for tenant-scoped {}", request.uri());
+            reject(
+                request,
+                ApiError::BadRequest("Tenant could not be resolved".into()),
+            );
+            return;
+        }
+        request.local_cache(|| Tenant(resolved));
+    }
+}
+
+// Client header middleware: requests under a REQUIRED_HEADERS prefix must
+// carry the header, at the minimum version when one is set
+pub struct RequiredHeaders;
+
+#[rocket::async_trait]
+impl Fairing for RequiredHeaders {
+    fn info(&self) -> Info {
+        Info {
+            name: "Required Headers",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        if request.method() == Method::Options || is_rejected(request) {
+            return;
+        }
+        let Some(config) = live::current(request) else {
+            return;
+        };
+
+        let path = request.uri().path();
+        let violation = config
+            .required_headers
+            .iter()
+            .filter(|required| is_under(path.as_str(), &required.prefix))
+            .find_map(|required| required.violation(request.headers().get_one(&required.name)));
+        if let Some(violation) = violation {
+            debug!("{} for {}", violation, request.uri());
+            reject(request, ApiError::BadRequest(violation));
+        }
+    }
+}
+
+// HTTPS enforcement middleware for deployments behind a TLS-terminating proxy
+pub struct HttpsOnly;
+
+#[rocket::async_trait]
+impl Fairing for HttpsOnly {
+    fn info(&self) -> Info {
+        Info {
+            name: "HTTPS Only",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    /// Requests without `X-Forwarded-Proto` reached the gateway directly
+    /// (health probes, sidecars) and are let through
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        if !https_enforced(request) {
+            return;
+        }
+
+        let proto = request
+            .headers()
+            .get_one("X-Forwarded-Proto")
+            .and_then(|value| value.split(',').next())
+            .map(|value| value.trim().to_ascii_lowercase());
+
+        if let Some(proto) = proto.filter(|proto| proto != "https") {
+            warn!("Rejected {} request to {}", proto, request.uri());
+            reject(
+                request,
+                ApiError::UpgradeRequired("HTTPS is required".into()),
+            );
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let max_age = live::current(request).map_or(0, |config| config.hsts_max_age);
+
+        if max_age > 0 && https_enforced(request) {
+            response.set_raw_header(
+                "Strict-Transport-Security",
+                format!("max-age={}; includeSubDomains", max_age),
+            );
+        }
+    }
+}
+
+fn https_enforced(request: &Request<'_>) -> bool {
+    live::current(request).is_some_and(|config| config.enforce_https && !config.is_development())
+}
+
+// Security headers middleware, applied to every response including errors.
+// Runs after Rocket's Shield and overrides its frame policy.
+pub struct SecurityHeaders;
+
+#[rocket::async_trait]
+impl Fairing for SecurityHeaders {
+    fn info(&self) -> Info {
+        Info {
+            name: "Security Headers",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        response.set_raw_header("X-Content-Type-Options", "nosniff");
+        response.set_raw_header("X-Frame-Options", "DENY");
+        response.set_raw_header("Referrer-Policy", "no-referrer");
+
+        if let Some(config) = live::current(request) {
+            response.set_raw_header("Content-Security-Policy", config.csp_header.clone());
+        }
+    }
+}
+
+// Payload size metrics middleware
+pub struct BodySizes;
+
+#[rocket::async_trait]
+impl Fairing for BodySizes {
+    fn info(&self) -> Info {
+        Info {
+            name: "Body Sizes",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let mut labels = request_labels(request);
+        labels.push(Label::new("route", route_label(request)));
+
+        // Bodyless requests have neither and count as 0 bytes
+        let request_bytes = request
+            .headers()
+            .get_one("Content-Length")
+            .and_then(|length| length.parse::<usize>().ok())
+            .unwrap_or_else(|| request.local_cache(|| RequestBodySize(0)).0);
+        metrics::histogram!("api_request_body_bytes", labels.clone()).record(request_bytes as f64);
+
+        if let Some(bytes) = response.body().preset_size() {
+            metrics::histogram!("api_response_body_bytes", labels).record(bytes as f64);
+        }
     }
 }
 
@@ -100,14 +936,135 @@ impl Fairing for ResponseTime {
         let response_time = start_time.elapsed();
 
         let method = request.method();
-        let uri = request.uri();
+        let uri = logged_uri(request);
         let status = response.status();
 
-        // Log response time
-        debug!("{} {} => {} in {:.2?}", method, uri, status, response_time);
+        let config = live::current(request);
+        if config.is_some_and(|config| config.expose_server_timing) {
+            let mut timing = format!("gateway;dur={:.1}", millis(response_time));
+            if let Some(upstream) = request.local_cache(UpstreamCall::default).time {
+                timing.push_str(&format!(", upstream;dur={:.1}", millis(upstream)));
+            }
+            response.set_raw_header("Server-Timing", timing);
+        }
+
+        // Log response time, loudly when it is over the slow-request threshold
+        let slow_after = config
+            .map(|config| config.slow_request_threshold)
+            .filter(|threshold| !threshold.is_zero());
+        if slow_after.is_some_and(|threshold| response_time > threshold) {
+            let request_id = request.local_cache(|| RequestIdValue(Uuid::new_v4().to_string()));
+            warn!(
+                "[{}] Slow request: {} {} ({}) => {} in {:.2?}",
+                request_id,
+                method,
+                uri,
+                route_label(request),
+                status,
+                response_time
+            );
+        } else {
+            debug!("{} {} => {} in {:.2?}", method, uri, status, response_time);
+        }
+
+        metrics::histogram!("api_response_time", request_labels(request))
+            .record(response_time.as_secs_f64());
+
+        if let Some(stats) = request.rocket().state::<RequestStats>() {
+            stats.record(status.code, response_time);
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+// Gateway-wide request deadline middleware
+pub struct RequestTimeout;
+
+#[rocket::async_trait]
+impl Fairing for RequestTimeout {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request Timeout",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        request.local_cache(|| RequestDeadline::start(request));
+    }
+}
+
+/// Time budget for answering the request, covering every upstream attempt
+/// made on its behalf. The timeout is the route's `ROUTE_TIMEOUTS` override
+/// when one matches, otherwise `GATEWAY_REQUEST_TIMEOUT_MS`.
+#[derive(Clone, Copy)]
+pub struct RequestDeadline {
+    pub at: Instant,
+    pub timeout: Duration,
+}
+
+impl RequestDeadline {
+    fn start(request: &Request<'_>) -> Self {
+        let timeout = live::current(request)
+            .map(|config| {
+                route_timeout(
+                    &config.route_timeouts,
+                    request.uri().path().as_str(),
+                    config.gateway_request_timeout,
+                )
+            })
+            .unwrap_or(Duration::from_secs(30));
+
+        RequestDeadline {
+            at: Instant::now() + timeout,
+            timeout,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestDeadline {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(*request.local_cache(|| RequestDeadline::start(request)))
+    }
+}
+
+// In-flight request gauge middleware
+pub struct InFlightRequests;
+
+#[rocket::async_trait]
+impl Fairing for InFlightRequests {
+    fn info(&self) -> Info {
+        Info {
+            name: "In-Flight Requests",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut rocket::Data<'_>) {
+        request.local_cache(InFlightGuard::new);
+    }
+}
+
+/// Keeps `api_requests_in_flight` raised for as long as it lives. It sits in
+/// the request's local cache, so the gauge drops when Rocket drops the
+/// request, whether it completed, was rejected early or its handler panicked.
+struct InFlightGuard;
+
+impl InFlightGuard {
+    fn new() -> Self {
+        metrics::gauge!("api_requests_in_flight").increment(1.0);
+        InFlightGuard
+    }
+}
 
-        let seconds = response_time.as_secs_f64();
-        let labels = [("seconds", format!("{}!", seconds))];
-        let _ = metrics::histogram!("api_response_time", &labels);
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        metrics::gauge!("api_requests_in_flight").decrement(1.0);
     }
 }
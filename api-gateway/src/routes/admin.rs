@@ -0,0 +1,169 @@
+// src/routes/admin.rs
+use crate::config::live::LiveConfig;
+use crate::errors::{ApiError, ErrorResponder, IntoErrorResponse};
+use crate::guards::admin::AdminGuard;
+use crate::guards::json_body::JsonBody;
+use crate::services::stats::{RequestStats, RequestSummary};
+use crate::services::switches::{ROUTE_GROUPS, RouteSwitches};
+use crate::services::upstream::{UpstreamStatus, Upstreams};
+use log::{error, warn};
+use rocket::State;
+use rocket::serde::json::Json;
+use rocket::serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct MaintenanceState {
+    enabled: bool,
+}
+
+#[get("/maintenance")]
+pub fn maintenance(_auth: AdminGuard, switches: &State<RouteSwitches>) -> Json<MaintenanceState> {
+    Json(MaintenanceState {
+        enabled: switches.maintenance(),
+    })
+}
+
+/// Turn maintenance mode on or off without restarting the gateway
+#[put("/maintenance", data = "<state>")]
+pub fn set_maintenance(
+    _auth: AdminGuard,
+    switches: &State<RouteSwitches>,
+    state: JsonBody<MaintenanceState>,
+) -> Json<MaintenanceState> {
+    let enabled = state.into_inner().enabled;
+    switches.set_maintenance(enabled);
+    warn!(
+        "Maintenance mode {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+
+    Json(MaintenanceState { enabled })
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RouteGroupState {
+    group: String,
+    enabled: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RouteToggle {
+    enabled: bool,
+}
+
+#[get("/routes")]
+pub fn routes(_auth: AdminGuard, switches: &State<RouteSwitches>) -> Json<Vec<RouteGroupState>> {
+    Json(
+        ROUTE_GROUPS
+            .iter()
+            .map(|group| RouteGroupState {
+                group: group.to_string(),
+                enabled: switches.is_enabled(group),
+            })
+            .collect(),
+    )
+}
+
+/// Enable or disable one proxied route group; disabled groups answer 503
+#[put("/routes/<group>", data = "<toggle>")]
+pub fn set_route(
+    _auth: AdminGuard,
+    switches: &State<RouteSwitches>,
+    group: &str,
+    toggle: JsonBody<RouteToggle>,
+) -> Result<Json<RouteGroupState>, ErrorResponder> {
+    let enabled = toggle.into_inner().enabled;
+    if !switches.set_enabled(group, enabled) {
+        return Err(
+            ApiError::NotFound(format!("Unknown route group '{}'", group))
+                .into_error_response(None),
+        );
+    }
+    warn!(
+        "Route group {} {}",
+        group,
+        if enabled { "enabled" } else { "disabled" }
+    );
+
+    Ok(Json(RouteGroupState {
+        group: group.to_string(),
+        enabled,
+    }))
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReloadResult {
+    reloaded: bool,
+    environment: String,
+    /// Changed settings that only take effect after a restart
+    restart_required: Vec<&'static str>,
+}
+
+/// Load the configuration again, re-reading `CONFIG_FILE`, and swap it in
+/// for new requests. Upstreams whose settings are unchanged keep their
+/// breakers, retry budgets, stale cache and bulkheads. Some settings are
+/// only read at startup, see the top of `Development.env`; changes to
+/// those are listed in `restart_required` rather than applied.
+#[post("/reload")]
+pub fn reload(
+    _auth: AdminGuard,
+    live: &State<LiveConfig>,
+) -> Result<Json<ReloadResult>, ErrorResponder> {
+    let previous = live.snapshot();
+    match live.reload() {
+        Ok(snapshot) => {
+            let restart_required = previous.config.restart_required(&snapshot.config);
+            if restart_required.is_empty() {
+                warn!("Configuration reloaded");
+            } else {
+                warn!(
+                    "Configuration reloaded; changes to {} need a restart",
+                    restart_required.join(", ")
+                );
+            }
+            Ok(Json(ReloadResult {
+                reloaded: true,
+                environment: snapshot.config.environment.clone(),
+                restart_required,
+            }))
+        }
+        Err(e) => {
+            error!(
+                "Configuration reload failed, keeping the current one: {}",
+                e
+            );
+            Err(ApiError::BadRequest(e.to_string()).into_error_response(None))
+        }
+    }
+}
+
+/// Breaker state, recent failures, last health probe and bulkhead usage of
+/// every upstream and its replicas
+#[get("/upstreams")]
+pub fn upstreams(_auth: AdminGuard, upstreams: &Upstreams) -> Json<Vec<UpstreamStatus>> {
+    Json(
+        upstreams
+            .all()
+            .iter()
+            .map(|upstream| upstream.status())
+            .collect(),
+    )
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AdminHealth {
+    requests: RequestSummary,
+}
+
+/// Traffic over the last minute, kept off the public health check
+#[get("/health")]
+pub fn health(_auth: AdminGuard, stats: &State<RequestStats>) -> Json<AdminHealth> {
+    Json(AdminHealth {
+        requests: stats.summary(),
+    })
+}
@@ -0,0 +1,127 @@
+// src/routes/customer/mod.rs
+use crate::config::app::AppConfig;
+use crate::errors::{ApiError, IntoErrorResponse};
+use crate::guards::available::Available;
+use crate::guards::caller::Caller;
+use crate::guards::json_body::JsonBody;
+use crate::middleware::RequestDeadline;
+use crate::services::headers::ForwardedHeaders;
+use crate::services::idempotency::IdempotencyKey;
+use crate::services::proxy::{ProxyRequest, ProxyResult, path_segment};
+use crate::services::upstream::Upstreams;
+use log::debug;
+use rocket::serde::json::json;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+// Request data models
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateCustomerRequest {
+    pub name: String,
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+}
+
+// Single customer route
+#[get("/<customer_id>")]
+pub async fn get_customer(
+    _available: Available,
+    caller: Caller,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    customer_id: &str,
+) -> ProxyResult {
+    debug!(
+        "Proxying get customer request for {} to customer service",
+        caller
+    );
+
+    ProxyRequest::get(
+        &upstreams.customers,
+        format!("/api/customers/{}", path_segment(customer_id)),
+    )
+    .headers(headers)
+    .canary_key(caller.user_id())
+    .deadline(deadline)
+    .send(config)
+    .await
+}
+
+// Customer activity route, optionally limited to events after `since`
+#[get("/<customer_id>/activity?<since>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_customer_activity(
+    _available: Available,
+    caller: Caller,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    customer_id: &str,
+    since: Option<&str>,
+) -> ProxyResult {
+    if let Some(since) = since
+        && OffsetDateTime::parse(since, &Rfc3339).is_err()
+    {
+        return Err(
+            ApiError::BadRequest("since must be an RFC 3339 timestamp".into())
+                .into_error_response(None),
+        );
+    }
+
+    debug!(
+        "Proxying customer activity request for {} to customer service",
+        caller
+    );
+
+    ProxyRequest::get(
+        &upstreams.customers,
+        format!("/api/customers/{}/activity", path_segment(customer_id)),
+    )
+    .headers(headers)
+    .canary_key(caller.user_id())
+    .deadline(deadline)
+    .query("since", since)
+    .send(config)
+    .await
+}
+
+// Create customer route
+#[post("/", data = "<customer_data>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_customer(
+    _available: Available,
+    caller: Caller,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    idempotency: Option<IdempotencyKey<'_>>,
+    customer_data: JsonBody<CreateCustomerRequest>,
+) -> ProxyResult {
+    let customer = customer_data.into_inner();
+    if customer.name.trim().is_empty() || customer.email.trim().is_empty() {
+        return Err(
+            ApiError::BadRequest("name and email are required".into()).into_error_response(None)
+        );
+    }
+
+    debug!(
+        "Proxying create customer request for {} to customer service",
+        caller
+    );
+
+    ProxyRequest::post(&upstreams.customers, "/api/customers")
+        .headers(headers)
+        .canary_key(caller.user_id())
+        .deadline(deadline)
+        .idempotency(idempotency)
+        .json(json!(customer))
+        .send(config)
+        .await
+}
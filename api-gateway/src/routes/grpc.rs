@@ -0,0 +1,128 @@
+// src/routes/grpc.rs
+use crate::config::app::AppConfig;
+use crate::config::live;
+use crate::middleware::{REQUEST_ID_HEADER, RequestDeadline, RequestIdValue};
+use crate::services::grpcweb::{self, GRPC_WEB, GrpcChannel, Reply};
+use log::debug;
+use rocket::State;
+use rocket::data::{Data, ToByteUnit};
+use rocket::http::{ContentType, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::{self, Responder, Response};
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Largest request message accepted, gRPC's default receive limit
+const MAX_REQUEST_BYTES: usize = 4 * 1024 * 1024;
+
+/// Client headers always sent upstream as call metadata. Others, the
+/// gateway's own credentials and routing headers among them, only go
+/// when listed in `GRPC_WEB_METADATA`.
+const METADATA_HEADERS: &[&str] = &["authorization", "grpc-timeout", "traceparent", "tracestate"];
+
+/// Headers of a binary gRPC-Web call that are sent upstream as metadata.
+/// Text-encoded (`grpc-web-text`) calls aren't supported and get a 415.
+pub struct GrpcWebCall {
+    metadata: Vec<(String, String)>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for GrpcWebCall {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let content_type = request.headers().get_one("Content-Type").unwrap_or("");
+        let is_binary =
+            content_type == GRPC_WEB || content_type.starts_with("application/grpc-web+");
+        if !is_binary {
+            return Outcome::Forward(Status::UnsupportedMediaType);
+        }
+
+        let extra = live::current(request).map_or(&[][..], |config| &config.grpc_web_metadata);
+        let mut metadata: Vec<_> = request
+            .headers()
+            .iter()
+            .filter(|header| is_metadata(header.name.as_str(), extra))
+            .map(|header| {
+                (
+                    header.name.as_str().to_ascii_lowercase(),
+                    header.value.to_string(),
+                )
+            })
+            .collect();
+        let request_id = request.guard::<RequestIdValue>().await;
+        if let Outcome::Success(request_id) = request_id {
+            metadata.push((REQUEST_ID_HEADER.to_ascii_lowercase(), request_id.0));
+        }
+
+        Outcome::Success(GrpcWebCall { metadata })
+    }
+}
+
+/// Whether a client header is sent upstream as call metadata: a built-in
+/// one or one of `extra`, which are lowercase
+fn is_metadata(name: &str, extra: &[String]) -> bool {
+    let name = name.to_ascii_lowercase();
+    METADATA_HEADERS.contains(&name.as_str()) || extra.contains(&name)
+}
+
+/// A gRPC-Web response, always a 200 with the call's status in the trailer
+/// frame
+pub struct GrpcWebResponse(Reply);
+
+impl<'r> Responder<'r, 'static> for GrpcWebResponse {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let body = self.0.into_body();
+        Response::build()
+            .header(ContentType::new("application", "grpc-web+proto"))
+            .sized_body(body.len(), Cursor::new(body))
+            .ok()
+    }
+}
+
+/// Unary gRPC-Web call relayed as gRPC to `GRPC_WEB_UPSTREAM_URL`, e.g.
+/// `POST /api/grpc/profile.v1.Profiles/Get`. Answers 404 when no gRPC
+/// upstream is configured.
+#[post("/<method..>", data = "<body>")]
+pub async fn unary(
+    method: PathBuf,
+    config: &AppConfig,
+    channel: &State<GrpcChannel>,
+    deadline: RequestDeadline,
+    call: GrpcWebCall,
+    body: Data<'_>,
+) -> Option<GrpcWebResponse> {
+    let url = config.grpc_web_url.as_deref()?;
+    let path = format!("/{}", method.display());
+
+    let body = match body.open(MAX_REQUEST_BYTES.bytes()).into_bytes().await {
+        Ok(body) if body.is_complete() => body.into_inner(),
+        Ok(_) => {
+            return Some(GrpcWebResponse(Reply::failed(
+                grpcweb::code::RESOURCE_EXHAUSTED,
+                "Request message exceeds the size limit",
+            )));
+        }
+        Err(_) => {
+            return Some(GrpcWebResponse(Reply::failed(
+                grpcweb::code::INTERNAL,
+                "Could not read the request message",
+            )));
+        }
+    };
+
+    debug!("Relaying gRPC-Web call {} to {}", path, url);
+    let timeout = deadline.at.saturating_duration_since(Instant::now());
+    let reply = grpcweb::call(
+        channel,
+        url,
+        &path,
+        &call.metadata,
+        body,
+        config.max_upstream_response_bytes,
+        timeout,
+    )
+    .await;
+    Some(GrpcWebResponse(reply))
+}
@@ -1,8 +1,19 @@
 // src/routes/health.rs
-use log::info;
+use crate::config::app::AppConfig;
+use crate::services::breaker::BreakerState;
+use crate::services::telemetry;
+use crate::services::upstream::Upstreams;
+use log::{error, info, warn};
+use metrics_exporter_prometheus::PrometheusHandle;
+use rocket::State;
+use rocket::futures::future::join_all;
+use rocket::http::Status;
+use rocket::response::status;
 use rocket::serde::json::Json;
 use rocket::serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
 
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -10,20 +21,234 @@ pub struct HealthStatus {
     status: String,
     timestamp: String,
     version: String,
+    components: Components,
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Components {
+    metrics: Component,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Component {
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+/// Gateway health with its components. A failing component makes the status
+/// `degraded` but keeps the 200, as the gateway still serves traffic.
+/// Traffic figures are in the admin health output.
 #[get("/")]
-pub fn check() -> Json<HealthStatus> {
+pub fn check(prometheus_handle: &State<PrometheusHandle>) -> Json<HealthStatus> {
     info!("Health check endpoint called");
 
+    let metrics = if telemetry::recorder_is_working(prometheus_handle) {
+        Component {
+            status: "ok".into(),
+            detail: None,
+        }
+    } else {
+        error!("Metrics recorder is not recording, /api/metrics is stale");
+        Component {
+            status: "failing".into(),
+            detail: Some("probe metric missing from the rendered metrics".into()),
+        }
+    };
+    let status = if metrics.status == "ok" {
+        "ok"
+    } else {
+        "degraded"
+    };
+
+    Json(HealthStatus {
+        status: status.into(),
+        timestamp: unix_timestamp(),
+        version: env!("CARGO_PKG_VERSION").into(),
+        components: Components { metrics },
+    })
+}
+
+fn unix_timestamp() -> String {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards")
         .as_secs();
+    format!("{}", now)
+}
 
-    Json(HealthStatus {
-        status: "ok".into(),
-        timestamp: format!("{}", now),
-        version: env!("CARGO_PKG_VERSION").into(),
-    })
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReadinessStatus {
+    status: String,
+    /// When the upstreams were probed, which may predate this response
+    timestamp: String,
+    /// `up`, `down` when its probe failed, `open` while the breaker of
+    /// every replica is open, or `half-open` while one is on trial
+    upstreams: BTreeMap<String, String>,
+    /// `CRITICAL_SERVICES` that are down or whose breaker is open, which
+    /// fail readiness
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    critical: Vec<String>,
+}
+
+/// Results of one round of upstream probes, in `Upstreams::all` order
+#[derive(Clone)]
+struct Probed {
+    at: Instant,
+    timestamp: String,
+    up: Vec<bool>,
+}
+
+/// Last probe results, reused for `HEALTH_CACHE_MS`. The lock is held while
+/// probing, so concurrent checks wait for the probes in flight. Breakers
+/// are read on every check, as they cost no backend traffic.
+#[derive(Default)]
+pub struct ReadinessCache(Mutex<Option<Probed>>);
+
+/// Readiness probe checking the upstreams too. A critical service down or
+/// with an open breaker, or no service being up, is a 503; any other
+/// upstream not up makes the status `degraded` with a 200.
+#[get("/ready")]
+pub async fn ready(
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    cache: &State<ReadinessCache>,
+) -> status::Custom<Json<ReadinessStatus>> {
+    let mut last = cache.0.lock().await;
+    let probed = match &*last {
+        Some(probed) if probed.at.elapsed() < config.health_cache_ttl => probed.clone(),
+        _ => {
+            let probed = probe_upstreams(upstreams, config.health_probe_timeout).await;
+            *last = Some(probed.clone());
+            probed
+        }
+    };
+    drop(last);
+
+    let readiness = readiness(config, upstreams, probed);
+    let status = if readiness.status == "unavailable" {
+        Status::ServiceUnavailable
+    } else {
+        Status::Ok
+    };
+    status::Custom(status, Json(readiness))
+}
+
+async fn probe_upstreams(upstreams: &Upstreams, timeout: Duration) -> Probed {
+    let all = upstreams.all();
+    let up = join_all(all.iter().map(|upstream| upstream.probe(timeout))).await;
+
+    let down = up.iter().filter(|up| !**up).count();
+    if down > 0 {
+        warn!(
+            "Readiness check: {} of {} upstreams are down",
+            down,
+            up.len()
+        );
+    }
+
+    Probed {
+        at: Instant::now(),
+        timestamp: unix_timestamp(),
+        up,
+    }
+}
+
+fn readiness(config: &AppConfig, upstreams: &Upstreams, probed: Probed) -> ReadinessStatus {
+    let states: Vec<_> = upstreams
+        .all()
+        .into_iter()
+        .zip(probed.up)
+        .map(|(upstream, up)| {
+            let state = match upstream.breaker_state() {
+                _ if !up => "down",
+                BreakerState::Open => "open",
+                BreakerState::HalfOpen => "half-open",
+                BreakerState::Closed => "up",
+            };
+            (upstream.name, state)
+        })
+        .collect();
+
+    let critical: Vec<String> = states
+        .iter()
+        .filter(|(name, state)| {
+            matches!(*state, "down" | "open")
+                && config
+                    .critical_services
+                    .iter()
+                    .any(|critical| critical == name)
+        })
+        .map(|(name, _)| name.to_string())
+        .collect();
+    let up = states.iter().filter(|(_, state)| *state == "up").count();
+    let status = if up == 0 || !critical.is_empty() {
+        "unavailable"
+    } else if up == states.len() {
+        "ready"
+    } else {
+        "degraded"
+    };
+
+    ReadinessStatus {
+        status: status.into(),
+        timestamp: probed.timestamp,
+        upstreams: states
+            .into_iter()
+            .map(|(name, state)| (name.to_string(), state.to_string()))
+            .collect(),
+        critical,
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LivenessStatus {
+    status: String,
+    scheduling_delay_ms: f64,
+    threshold_ms: u64,
+}
+
+/// Liveness probe that also checks the async runtime is responsive, by
+/// timing how long a no-op task waits before it gets polled
+#[get("/live")]
+pub async fn live(config: &AppConfig) -> status::Custom<Json<LivenessStatus>> {
+    let delay = scheduling_delay().await;
+    let threshold = config.liveness_max_scheduling_delay;
+    let degraded = delay > threshold;
+
+    if degraded {
+        warn!(
+            "Runtime is saturated: scheduling a task took {:.2?} (threshold {:?})",
+            delay, threshold
+        );
+    }
+
+    metrics::gauge!("api_runtime_scheduling_delay_seconds").set(delay.as_secs_f64());
+
+    let (status, label) = if degraded {
+        (Status::ServiceUnavailable, "degraded")
+    } else {
+        (Status::Ok, "ok")
+    };
+
+    status::Custom(
+        status,
+        Json(LivenessStatus {
+            status: label.into(),
+            scheduling_delay_ms: delay.as_secs_f64() * 1000.0,
+            threshold_ms: threshold.as_millis() as u64,
+        }),
+    )
+}
+
+/// Time from spawning a no-op task until the runtime polls it
+async fn scheduling_delay() -> Duration {
+    let spawned = Instant::now();
+    tokio::spawn(async move { spawned.elapsed() })
+        .await
+        .unwrap_or_else(|_| spawned.elapsed())
 }
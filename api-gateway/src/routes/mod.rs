@@ -1,8 +1,14 @@
+pub mod admin;
+pub mod customer;
+pub mod grpc;
 pub mod health;
+pub mod notifications;
+pub mod preflight;
+pub mod proxy;
+pub mod purchasing;
+pub mod sales;
 pub mod users;
+pub mod version;
 // Commented modules for future implementation
-// pub mod customer;
 // pub mod inventory;
 // pub mod payments;
-// pub mod purchasing;
-// pub mod sales;
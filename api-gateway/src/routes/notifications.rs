@@ -0,0 +1,208 @@
+// src/routes/notifications.rs
+use crate::config::app::AppConfig;
+use crate::errors::{ApiError, ErrorResponder};
+use crate::guards::available::Available;
+use crate::middleware::{REQUEST_ID_HEADER, RequestContext, RequestDeadline};
+use crate::services::proxy::error_response;
+use crate::services::rewrite::rewrite_path;
+use crate::services::upstream::Upstreams;
+use log::{debug, warn};
+use rocket::futures::{SinkExt, StreamExt};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket_ws::{Channel, WebSocket};
+use std::convert::Infallible;
+use tokio::time::{Instant, timeout_at};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async_tls_with_config};
+
+type UpstreamSocket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Parts of the client handshake that are replayed against the upstream
+pub struct Handshake {
+    authorization: Option<String>,
+    query: Option<String>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Handshake {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(Handshake {
+            authorization: request
+                .headers()
+                .get_one("Authorization")
+                .map(str::to_string),
+            query: request.uri().query().map(|q| q.as_str().to_string()),
+        })
+    }
+}
+
+#[get("/ws")]
+pub async fn stream(
+    _available: Available,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    deadline: RequestDeadline,
+    ctx: RequestContext,
+    handshake: Handshake,
+    ws: WebSocket,
+) -> Result<Channel<'static>, ErrorResponder> {
+    let upstream = &upstreams.notifications;
+    let path = rewrite_path(&config.path_rewrites, "/api/notifications/ws");
+
+    // Keep a user on the same side of a canary split across reconnects
+    let canary_key = ctx
+        .auth
+        .as_ref()
+        .map_or(&ctx.request_id, |claims| &claims.user_id);
+    let primary = upstream
+        .canary_for(canary_key)
+        .unwrap_or_else(|| upstream.next_target());
+    let mut targets = vec![primary.to_string()];
+    targets.extend(upstream.fallback().map(str::to_string));
+
+    let request_id = &ctx.request_id;
+    let mut last_error = String::new();
+    for base in targets {
+        let mut url = format!("{}{}", base, path);
+        if let Some(query) = &handshake.query {
+            url.push('?');
+            url.push_str(query);
+        }
+
+        debug!("[{}] Dialing {} at {}", request_id, upstream.label, url);
+        match timeout_at(
+            Instant::from_std(deadline.at),
+            dial(config, &url, &handshake, request_id),
+        )
+        .await
+        {
+            Ok(Ok(socket)) => {
+                upstream.record(&url, true);
+                let ctx = ctx.clone();
+                return Ok(ws.channel(move |client| {
+                    Box::pin(async move {
+                        pipe(client, socket, ctx).await;
+                        Ok(())
+                    })
+                }));
+            }
+            Ok(Err(e)) => {
+                upstream.record(&url, false);
+                last_error = e;
+            }
+            Err(_) => {
+                return Err(error_response(
+                    config,
+                    ApiError::RequestTimeout(format!("{} handshake timed out", upstream.label)),
+                    "gateway request timeout elapsed",
+                ));
+            }
+        }
+        warn!(
+            "[{}] {} handshake failed: {}",
+            request_id, upstream.label, last_error
+        );
+    }
+
+    Err(error_response(
+        config,
+        ApiError::ServiceUnavailable(format!("{} unavailable", upstream.label)),
+        last_error,
+    ))
+}
+
+/// Open the upstream socket, forwarding the caller's token and request ID.
+/// It connects with the upstream TLS settings and within the connect
+/// timeout, like proxied calls do.
+async fn dial(
+    config: &AppConfig,
+    url: &str,
+    handshake: &Handshake,
+    request_id: &str,
+) -> Result<UpstreamSocket, String> {
+    let mut request = url.into_client_request().map_err(|e| e.to_string())?;
+    let headers = request.headers_mut();
+
+    if let Some(token) = &handshake.authorization {
+        let value = HeaderValue::from_str(token).map_err(|e| e.to_string())?;
+        headers.insert("Authorization", value);
+    }
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        headers.insert(REQUEST_ID_HEADER, value);
+    }
+
+    let connecting = connect_async_tls_with_config(
+        request,
+        None,
+        false,
+        config.upstream_tls.websocket_connector(),
+    );
+    let connected = match config.upstream_connect_timeout {
+        timeout if timeout.is_zero() => connecting.await,
+        timeout => tokio::time::timeout(timeout, connecting)
+            .await
+            .map_err(|_| format!("connect timed out after {:?}", timeout))?,
+    };
+    let (socket, _) = connected.map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+/// Relay frames in both directions until either side closes
+async fn pipe(
+    client: rocket_ws::stream::DuplexStream,
+    upstream: UpstreamSocket,
+    ctx: RequestContext,
+) {
+    let (mut client_tx, mut client_rx) = client.split();
+    let (mut upstream_tx, mut upstream_rx) = upstream.split();
+
+    let inbound = async {
+        while let Some(message) = client_rx.next().await {
+            upstream_tx.send(message?).await?;
+        }
+        upstream_tx.close().await
+    };
+
+    let outbound = async {
+        while let Some(message) = upstream_rx.next().await {
+            client_tx.send(message?).await?;
+        }
+        client_tx.close().await
+    };
+
+    let result = tokio::select! {
+        result = inbound => result,
+        result = outbound => result,
+    };
+
+    match result {
+        Ok(()) => debug!(
+            "[{}] Notifications socket for {} closed after {:?}",
+            ctx.request_id,
+            client_label(&ctx),
+            ctx.elapsed()
+        ),
+        Err(e) => debug!(
+            "[{}] Notifications socket for {} ended after {:?}: {}",
+            ctx.request_id,
+            client_label(&ctx),
+            ctx.elapsed(),
+            e
+        ),
+    }
+}
+
+fn client_label(ctx: &RequestContext) -> String {
+    let client = match (&ctx.auth, ctx.client_ip) {
+        (Some(claims), _) => format!("user {}", claims.user_id),
+        (None, Some(ip)) => ip.to_string(),
+        (None, None) => "unknown client".into(),
+    };
+    match &ctx.tenant {
+        Some(tenant) => format!("{} of tenant {}", client, tenant),
+        None => client,
+    }
+}
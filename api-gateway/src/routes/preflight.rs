@@ -0,0 +1,35 @@
+// src/routes/preflight.rs
+use crate::middleware::is_under;
+use rocket::http::{Method, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use std::convert::Infallible;
+
+/// Whether the request path is under a mounted route group
+pub struct Mounted(bool);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Mounted {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let path = request.uri().path().as_str();
+        let mounted = request
+            .rocket()
+            .routes()
+            .filter(|route| route.method != Method::Options)
+            .any(|route| is_under(path, route.uri.base()));
+        Outcome::Success(Mounted(mounted))
+    }
+}
+
+/// CORS preflight for any path under a mounted route group. The CORS
+/// fairing adds the `Access-Control-Allow-*` headers; without this route it
+/// would also answer 204 for paths nothing is mounted at.
+#[options("/<_..>")]
+pub fn preflight(mounted: Mounted) -> Status {
+    if mounted.0 {
+        Status::NoContent
+    } else {
+        Status::NotFound
+    }
+}
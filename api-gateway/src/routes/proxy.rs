@@ -0,0 +1,270 @@
+// src/routes/proxy.rs
+use crate::config::app::AppConfig;
+use crate::errors::{ApiError, IntoErrorResponse};
+use crate::guards::available::Available;
+use crate::guards::caller::Caller;
+use crate::guards::upload::ProxyBody;
+use crate::middleware::{RequestDeadline, is_under};
+use crate::services::headers::ForwardedHeaders;
+use crate::services::idempotency::IdempotencyKey;
+use crate::services::proxy::{ProxyRequest, StreamingResult, path_segment};
+use crate::services::switches::RouteSwitches;
+use crate::services::upstream::Upstreams;
+use log::debug;
+use reqwest::Method;
+use rocket::http::MediaType;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::{Either, State};
+use std::path::PathBuf;
+
+/// Request-level inputs shared by every catch-all route
+pub struct ProxyContext<'r> {
+    caller: Result<Caller, ApiError>,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    idempotency: Option<IdempotencyKey<'r>>,
+    query: Option<String>,
+    accepts_events: bool,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ProxyContext<'r> {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let headers = match request.guard::<ForwardedHeaders>().await {
+            Outcome::Success(headers) => headers,
+            Outcome::Error(e) => return Outcome::Error(e),
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+
+        // Kept as a result: whether credentials are required depends on the target
+        let caller = match request.guard::<Caller>().await {
+            Outcome::Success(caller) => Ok(caller),
+            Outcome::Error((_, err)) => Err(err),
+            Outcome::Forward(_) => Err(ApiError::Unauthorized("Missing bearer token".into())),
+        };
+
+        Outcome::Success(ProxyContext {
+            caller,
+            headers,
+            deadline: request
+                .guard::<RequestDeadline>()
+                .await
+                .expect("deadline guard is infallible"),
+            idempotency: request.guard::<IdempotencyKey<'r>>().await.succeeded(),
+            query: request.uri().query().map(|q| q.as_str().to_string()),
+            accepts_events: request.accept().is_some_and(|accept| {
+                accept
+                    .media_types()
+                    .any(|media| *media == MediaType::EventStream)
+            }),
+        })
+    }
+}
+
+#[get("/<service>/<path..>")]
+pub async fn get(
+    _available: Available,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    switches: &State<RouteSwitches>,
+    context: ProxyContext<'_>,
+    service: &str,
+    path: PathBuf,
+) -> StreamingResult {
+    forward(
+        config,
+        upstreams,
+        switches,
+        context,
+        Method::GET,
+        service,
+        path,
+        None,
+    )
+    .await
+}
+
+#[delete("/<service>/<path..>")]
+pub async fn delete(
+    _available: Available,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    switches: &State<RouteSwitches>,
+    context: ProxyContext<'_>,
+    service: &str,
+    path: PathBuf,
+) -> StreamingResult {
+    forward(
+        config,
+        upstreams,
+        switches,
+        context,
+        Method::DELETE,
+        service,
+        path,
+        None,
+    )
+    .await
+}
+
+#[post("/<service>/<path..>", data = "<body>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn post(
+    _available: Available,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    switches: &State<RouteSwitches>,
+    context: ProxyContext<'_>,
+    service: &str,
+    path: PathBuf,
+    body: ProxyBody<'_>,
+) -> StreamingResult {
+    forward(
+        config,
+        upstreams,
+        switches,
+        context,
+        Method::POST,
+        service,
+        path,
+        Some(body),
+    )
+    .await
+}
+
+#[put("/<service>/<path..>", data = "<body>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn put(
+    _available: Available,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    switches: &State<RouteSwitches>,
+    context: ProxyContext<'_>,
+    service: &str,
+    path: PathBuf,
+    body: ProxyBody<'_>,
+) -> StreamingResult {
+    forward(
+        config,
+        upstreams,
+        switches,
+        context,
+        Method::PUT,
+        service,
+        path,
+        Some(body),
+    )
+    .await
+}
+
+#[patch("/<service>/<path..>", data = "<body>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn patch(
+    _available: Available,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    switches: &State<RouteSwitches>,
+    context: ProxyContext<'_>,
+    service: &str,
+    path: PathBuf,
+    body: ProxyBody<'_>,
+) -> StreamingResult {
+    forward(
+        config,
+        upstreams,
+        switches,
+        context,
+        Method::PATCH,
+        service,
+        path,
+        Some(body),
+    )
+    .await
+}
+
+/// Forward `/<service>/<path..>` to `<path..>` on the named upstream,
+/// requiring a bearer token or API key unless the route is listed as
+/// public. GETs asking for an event stream, or listed as streaming routes,
+/// are relayed as a stream when the upstream answers with one.
+#[allow(clippy::too_many_arguments)]
+async fn forward(
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    switches: &RouteSwitches,
+    context: ProxyContext<'_>,
+    method: Method,
+    service: &str,
+    path: PathBuf,
+    body: Option<ProxyBody<'_>>,
+) -> StreamingResult {
+    let Some(upstream) = upstreams.by_name(service) else {
+        return Err(
+            ApiError::NotFound(format!("Unknown service '{}'", service)).into_error_response(None)
+        );
+    };
+
+    if !switches.is_enabled(service) {
+        return Err(
+            ApiError::ServiceUnavailable(format!("{} routes are disabled", service))
+                .into_error_response(None),
+        );
+    }
+
+    let segments: Vec<String> = path
+        .iter()
+        .map(|segment| path_segment(&segment.to_string_lossy()))
+        .collect();
+    let upstream_path = format!("/{}", segments.join("/"));
+
+    let user_id = match context.caller {
+        Ok(caller) => caller.user_id().map(str::to_string),
+        Err(err) if !is_listed(&config.proxy_public_routes, service, &upstream_path) => {
+            return Err(err.into_error_response(None));
+        }
+        Err(_) => None,
+    };
+
+    let streaming = method == Method::GET
+        && (context.accepts_events
+            || is_listed(&config.proxy_stream_routes, service, &upstream_path));
+
+    let mut target = upstream_path;
+    if let Some(query) = &context.query {
+        target.push('?');
+        target.push_str(query);
+    }
+
+    debug!(
+        "Proxying {} {} to {} via catch-all",
+        method, target, upstream.label
+    );
+
+    let mut request = ProxyRequest::new(upstream, method, target)
+        .headers(context.headers)
+        .deadline(context.deadline)
+        .idempotency(context.idempotency)
+        .canary_key(user_id);
+    match body {
+        Some(ProxyBody::Multipart(upload)) => {
+            return request.upload(config, *upload).await.map(Either::Left);
+        }
+        Some(ProxyBody::Json(Some(body))) => request = request.json(body),
+        Some(ProxyBody::Json(None)) | None => {}
+    }
+
+    if streaming {
+        return request.stream(config).await;
+    }
+    request.send(config).await.map(Either::Left)
+}
+
+/// Whether `<service><path>` matches a `<service>` or `<service>/<prefix>`
+/// entry of a route list, the prefix covering whole path segments only
+fn is_listed(routes: &[String], service: &str, path: &str) -> bool {
+    routes.iter().any(|entry| match entry.split_once('/') {
+        Some((name, prefix)) => name == service && is_under(path, &format!("/{}", prefix)),
+        None => entry == service,
+    })
+}
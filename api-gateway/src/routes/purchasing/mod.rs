@@ -0,0 +1,135 @@
+// src/routes/purchasing/mod.rs
+use crate::config::app::AppConfig;
+use crate::errors::{ApiError, IntoErrorResponse};
+use crate::guards::available::Available;
+use crate::guards::caller::Caller;
+use crate::guards::json_body::JsonBody;
+use crate::middleware::RequestDeadline;
+use crate::services::headers::ForwardedHeaders;
+use crate::services::idempotency::IdempotencyKey;
+use crate::services::proxy::{ProxyRequest, ProxyResult, path_segment};
+use crate::services::upstream::Upstreams;
+use log::debug;
+use rocket::serde::json::json;
+use serde::{Deserialize, Serialize};
+
+// Request data models
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PurchaseOrderLineItem {
+    pub product_id: String,
+    pub quantity: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit_cost: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreatePurchaseOrderRequest {
+    pub supplier_id: String,
+    pub items: Vec<PurchaseOrderLineItem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_delivery: Option<String>,
+}
+
+// Create purchase order route
+#[post("/orders", data = "<order_data>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_purchase_order(
+    _available: Available,
+    caller: Caller,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    idempotency: Option<IdempotencyKey<'_>>,
+    order_data: JsonBody<CreatePurchaseOrderRequest>,
+) -> ProxyResult {
+    let order = order_data.into_inner();
+    if order.supplier_id.trim().is_empty() {
+        return Err(
+            ApiError::BadRequest("supplier_id is required".into()).into_error_response(None)
+        );
+    }
+    if order.items.is_empty() {
+        return Err(ApiError::BadRequest(
+            "Purchase order must contain at least one line item".into(),
+        )
+        .into_error_response(None));
+    }
+    if order.items.iter().any(|item| item.quantity == 0) {
+        return Err(
+            ApiError::BadRequest("Line item quantity must be positive".into())
+                .into_error_response(None),
+        );
+    }
+
+    debug!(
+        "Proxying create purchase order request for {} to purchasing service",
+        caller
+    );
+
+    ProxyRequest::post(&upstreams.purchasing, "/api/purchasing/orders")
+        .headers(headers)
+        .canary_key(caller.user_id())
+        .deadline(deadline)
+        .idempotency(idempotency)
+        .json(json!(order))
+        .send(config)
+        .await
+}
+
+// Single purchase order route
+#[get("/orders/<order_id>")]
+pub async fn get_purchase_order(
+    _available: Available,
+    caller: Caller,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    order_id: &str,
+) -> ProxyResult {
+    debug!(
+        "Proxying get purchase order request for {} to purchasing service",
+        caller
+    );
+
+    ProxyRequest::get(
+        &upstreams.purchasing,
+        format!("/api/purchasing/orders/{}", path_segment(order_id)),
+    )
+    .headers(headers)
+    .canary_key(caller.user_id())
+    .deadline(deadline)
+    .send(config)
+    .await
+}
+
+// Purchase order listing route, optionally filtered by status and supplier
+#[get("/orders?<status>&<supplier_id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_purchase_orders(
+    _available: Available,
+    caller: Caller,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    status: Option<&str>,
+    supplier_id: Option<&str>,
+) -> ProxyResult {
+    debug!(
+        "Proxying list purchase orders request for {} to purchasing service",
+        caller
+    );
+
+    ProxyRequest::get(&upstreams.purchasing, "/api/purchasing/orders")
+        .headers(headers)
+        .canary_key(caller.user_id())
+        .deadline(deadline)
+        .query("status", status)
+        .query("supplier_id", supplier_id)
+        .send(config)
+        .await
+}
@@ -0,0 +1,127 @@
+// src/routes/sales/mod.rs
+use crate::config::app::AppConfig;
+use crate::errors::{ApiError, IntoErrorResponse};
+use crate::guards::available::Available;
+use crate::guards::caller::Caller;
+use crate::guards::json_body::JsonBody;
+use crate::middleware::RequestDeadline;
+use crate::services::headers::ForwardedHeaders;
+use crate::services::idempotency::IdempotencyKey;
+use crate::services::proxy::{ProxyRequest, ProxyResult, path_segment};
+use crate::services::upstream::Upstreams;
+use log::debug;
+use rocket::serde::json::json;
+use serde::{Deserialize, Serialize};
+
+// Request data models
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct OrderLineItem {
+    pub product_id: String,
+    pub quantity: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit_price: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateOrderRequest {
+    pub customer_id: String,
+    pub items: Vec<OrderLineItem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+// Create order route
+#[post("/orders", data = "<order_data>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_order(
+    _available: Available,
+    caller: Caller,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    idempotency: Option<IdempotencyKey<'_>>,
+    order_data: JsonBody<CreateOrderRequest>,
+) -> ProxyResult {
+    let order = order_data.into_inner();
+    if order.items.is_empty() {
+        return Err(
+            ApiError::BadRequest("Order must contain at least one line item".into())
+                .into_error_response(None),
+        );
+    }
+    if order.items.iter().any(|item| item.quantity == 0) {
+        return Err(
+            ApiError::BadRequest("Line item quantity must be positive".into())
+                .into_error_response(None),
+        );
+    }
+
+    debug!(
+        "Proxying create order request for {} to sales service",
+        caller
+    );
+
+    ProxyRequest::post(&upstreams.sales, "/api/sales/orders")
+        .headers(headers)
+        .canary_key(caller.user_id())
+        .deadline(deadline)
+        .idempotency(idempotency)
+        .json(json!(order))
+        .send(config)
+        .await
+}
+
+// Single order route
+#[get("/orders/<order_id>")]
+pub async fn get_order(
+    _available: Available,
+    caller: Caller,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    order_id: &str,
+) -> ProxyResult {
+    debug!("Proxying get order request for {} to sales service", caller);
+
+    ProxyRequest::get(
+        &upstreams.sales,
+        format!("/api/sales/orders/{}", path_segment(order_id)),
+    )
+    .headers(headers)
+    .canary_key(caller.user_id())
+    .deadline(deadline)
+    .send(config)
+    .await
+}
+
+// Order listing route, optionally filtered by status and customer
+#[get("/orders?<status>&<customer_id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_orders(
+    _available: Available,
+    caller: Caller,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    status: Option<&str>,
+    customer_id: Option<&str>,
+) -> ProxyResult {
+    debug!(
+        "Proxying list orders request for {} to sales service",
+        caller
+    );
+
+    ProxyRequest::get(&upstreams.sales, "/api/sales/orders")
+        .headers(headers)
+        .canary_key(caller.user_id())
+        .deadline(deadline)
+        .query("status", status)
+        .query("customer_id", customer_id)
+        .send(config)
+        .await
+}
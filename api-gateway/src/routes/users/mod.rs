@@ -1,11 +1,18 @@
 // src/routes/auth.rs
+mod validation;
+
 use crate::config::app::AppConfig;
-use crate::errors::ApiError;
-use log::{debug, error};
-use rocket::State;
-use rocket::http::Status;
-use rocket::response::status;
-use rocket::serde::json::{Json, Value, json};
+use crate::guards::available::Available;
+use crate::guards::json_body::JsonBody;
+use crate::guards::jwt::JwtGuard;
+use crate::guards::rate_limit::PasswordResetLimit;
+use crate::middleware::RequestDeadline;
+use crate::services::headers::ForwardedHeaders;
+use crate::services::idempotency::IdempotencyKey;
+use crate::services::proxy::{ProxyRequest, ProxyResult};
+use crate::services::upstream::Upstreams;
+use log::debug;
+use rocket::serde::json::json;
 use serde::{Deserialize, Serialize};
 
 // Request data models
@@ -30,226 +37,184 @@ pub struct RefreshTokenRequest {
     pub refresh_token: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
 // Login route
 #[post("/login", data = "<login_data>")]
 pub async fn login(
-    config: &State<AppConfig>,
-    login_data: Json<LoginRequest>,
-) -> Result<Value, status::Custom<Json<Value>>> {
+    _available: Available,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    idempotency: Option<IdempotencyKey<'_>>,
+    login_data: JsonBody<LoginRequest>,
+) -> ProxyResult {
+    let login = login_data.into_inner();
+    validation::login(&login)?;
+
     debug!("Proxying login request to user service");
 
-    let client = reqwest::Client::new();
-    let response = match client
-        .post(format!("{}/api/users/login", config.user_service_url))
-        .json(&login_data.into_inner())
-        .send()
+    ProxyRequest::post(&upstreams.users, "/api/users/login")
+        .headers(headers)
+        .deadline(deadline)
+        .idempotency(idempotency)
+        .json(json!(login))
+        .send(config)
         .await
-    {
-        Ok(response) => response,
-        Err(e) => {
-            error!("Error proxying login request: {:?}", e);
-            let err = ApiError::ServiceUnavailable("User Service unavailable".into());
-            return Err(status::Custom(
-                err.status_code(),
-                Json(json!({
-                    "status": err.status_code().code,
-                    "message": err.to_string(),
-                    "details": if config.is_development() { e.to_string() } else { String::new() }
-                })),
-            ));
-        }
-    };
-
-    let status = response.status();
-    let response_body = match response.json::<Value>().await {
-        Ok(body) => body,
-        Err(e) => {
-            error!("Error parsing login response: {:?}", e);
-            let err = ApiError::InternalServerError("Error parsing response".into());
-            return Err(status::Custom(
-                err.status_code(),
-                Json(json!({
-                    "status": err.status_code().code,
-                    "message": err.to_string(),
-                    "details": if config.is_development() { e.to_string() } else { String::new() }
-                })),
-            ));
-        }
-    };
-
-    if status.is_success() {
-        Ok(response_body)
-    } else {
-        Err(status::Custom(
-            Status::from_code(status.as_u16()).unwrap_or(Status::InternalServerError),
-            Json(response_body),
-        ))
-    }
 }
 
 // Register route
 #[post("/register", data = "<register_data>")]
 pub async fn register(
-    config: &State<AppConfig>,
-    register_data: Json<RegisterRequest>,
-) -> Result<Value, status::Custom<Json<Value>>> {
+    _available: Available,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    idempotency: Option<IdempotencyKey<'_>>,
+    register_data: JsonBody<RegisterRequest>,
+) -> ProxyResult {
+    let registration = register_data.into_inner();
+    validation::register(&registration, config.min_password_length)?;
+
     debug!("Proxying register request to user service");
 
-    let client = reqwest::Client::new();
-    let response = match client
-        .post(format!("{}/api/users/register", config.user_service_url))
-        .json(&register_data.into_inner())
-        .send()
+    ProxyRequest::post(&upstreams.users, "/api/users/register")
+        .headers(headers)
+        .deadline(deadline)
+        .idempotency(idempotency)
+        .json(json!(registration))
+        .send(config)
         .await
-    {
-        Ok(response) => response,
-        Err(e) => {
-            error!("Error proxying register request: {:?}", e);
-            let err = ApiError::ServiceUnavailable("User Service unavailable".into());
-            return Err(status::Custom(
-                err.status_code(),
-                Json(serde_json::json!({
-                    "status": err.status_code().code,
-                    "message": err.to_string(),
-                    "details": config.is_development().then(|| e.to_string())
-                })),
-            ));
-        }
-    };
-
-    let status = response.status();
-    let response_body = match response.json::<Value>().await {
-        Ok(body) => body,
-        Err(e) => {
-            error!("Error parsing register response: {:?}", e);
-            let err = ApiError::InternalServerError("Error parsing response".into());
-            return Err(status::Custom(
-                err.status_code(),
-                Json(serde_json::json!({
-                    "status": err.status_code().code,
-                    "message": err.to_string(),
-                    "details": config.is_development().then(|| e.to_string())
-                })),
-            ));
-        }
-    };
-
-    if status.is_success() {
-        Ok(response_body)
-    } else {
-        Err(status::Custom(
-            Status::from_code(status.as_u16()).unwrap_or(Status::InternalServerError),
-            Json(response_body),
-        ))
-    }
 }
 
 // Token refresh route
 #[post("/refresh", data = "<refresh_data>")]
 pub async fn refresh(
-    config: &State<AppConfig>,
-    refresh_data: Json<RefreshTokenRequest>,
-) -> Result<Value, status::Custom<Json<Value>>> {
+    _available: Available,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    idempotency: Option<IdempotencyKey<'_>>,
+    refresh_data: JsonBody<RefreshTokenRequest>,
+) -> ProxyResult {
     debug!("Proxying token refresh request to user service");
 
-    let client = reqwest::Client::new();
-    let response = match client
-        .post(format!("{}/api/users/refresh", config.user_service_url))
-        .json(&refresh_data.into_inner())
-        .send()
+    ProxyRequest::post(&upstreams.users, "/api/users/refresh")
+        .headers(headers)
+        .deadline(deadline)
+        .idempotency(idempotency)
+        .json(json!(refresh_data.into_inner()))
+        .send(config)
         .await
-    {
-        Ok(response) => response,
-        Err(e) => {
-            error!("Error proxying refresh request: {:?}", e);
-            let err = ApiError::ServiceUnavailable("User Service unavailable".into());
-            return Err(status::Custom(
-                err.status_code(),
-                Json(serde_json::json!({
-                    "status": err.status_code().code,
-                    "message": err.to_string(),
-                    "details": config.is_development().then(|| e.to_string())
-                })),
-            ));
-        }
-    };
-
-    let status = response.status();
-    let response_body = match response.json::<Value>().await {
-        Ok(body) => body,
-        Err(e) => {
-            error!("Error parsing refresh response: {:?}", e);
-            let err = ApiError::InternalServerError("Error parsing response".into());
-            return Err(status::Custom(
-                err.status_code(),
-                Json(serde_json::json!({
-                    "status": err.status_code().code,
-                    "message": err.to_string(),
-                    "details": config.is_development().then(|| e.to_string())
-                })),
-            ));
-        }
-    };
-
-    if status.is_success() {
-        Ok(response_body)
-    } else {
-        Err(status::Custom(
-            Status::from_code(status.as_u16()).unwrap_or(Status::InternalServerError),
-            Json(response_body),
-        ))
-    }
 }
 
 // Logout route
 #[post("/logout")]
-pub async fn logout(config: &State<AppConfig>) -> Result<Value, status::Custom<Json<Value>>> {
+pub async fn logout(
+    _available: Available,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    idempotency: Option<IdempotencyKey<'_>>,
+) -> ProxyResult {
     debug!("Proxying logout request to user service");
 
-    let client = reqwest::Client::new();
-    let response = match client
-        .post(format!("{}/api/users/logout", config.user_service_url))
-        .send()
+    ProxyRequest::post(&upstreams.users, "/api/users/logout")
+        .headers(headers)
+        .deadline(deadline)
+        .idempotency(idempotency)
+        .send(config)
+        .await
+}
+
+// Forgot password route, public and throttled per client
+#[post("/forgot-password", data = "<forgot_data>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn forgot_password(
+    _available: Available,
+    _limit: PasswordResetLimit,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    idempotency: Option<IdempotencyKey<'_>>,
+    forgot_data: JsonBody<ForgotPasswordRequest>,
+) -> ProxyResult {
+    let forgot = forgot_data.into_inner();
+    validation::forgot_password(&forgot)?;
+
+    debug!("Proxying forgot password request to user service");
+
+    ProxyRequest::post(&upstreams.users, "/api/users/forgot-password")
+        .headers(headers)
+        .deadline(deadline)
+        .idempotency(idempotency)
+        .json(json!(forgot))
+        .send(config)
+        .await
+}
+
+// Reset password route, public and throttled per client
+#[post("/reset-password", data = "<reset_data>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn reset_password(
+    _available: Available,
+    _limit: PasswordResetLimit,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+    idempotency: Option<IdempotencyKey<'_>>,
+    reset_data: JsonBody<ResetPasswordRequest>,
+) -> ProxyResult {
+    let reset = reset_data.into_inner();
+    validation::reset_password(&reset, config.min_password_length)?;
+
+    debug!("Proxying reset password request to user service");
+
+    ProxyRequest::post(&upstreams.users, "/api/users/reset-password")
+        .headers(headers)
+        .deadline(deadline)
+        .idempotency(idempotency)
+        .json(json!(reset))
+        .send(config)
+        .await
+}
+
+// Current user's profile route
+#[get("/me")]
+pub async fn me(
+    _available: Available,
+    auth: JwtGuard,
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    headers: ForwardedHeaders,
+    deadline: RequestDeadline,
+) -> ProxyResult {
+    debug!(
+        "Proxying profile request for user {} to user service",
+        auth.0.user_id
+    );
+
+    ProxyRequest::get(&upstreams.users, "/api/users/me")
+        .headers(headers)
+        .canary_key(Some(&auth.0.user_id))
+        .deadline(deadline)
+        .send(config)
         .await
-    {
-        Ok(response) => response,
-        Err(e) => {
-            error!("Error proxying logout request: {:?}", e);
-            let err = ApiError::ServiceUnavailable("User Service unavailable".into());
-            return Err(status::Custom(
-                err.status_code(),
-                Json(serde_json::json!({
-                    "status": err.status_code().code,
-                    "message": err.to_string(),
-                    "details": config.is_development().then(|| e.to_string())
-                })),
-            ));
-        }
-    };
-
-    let status = response.status();
-    let response_body = match response.json::<Value>().await {
-        Ok(body) => body,
-        Err(e) => {
-            error!("Error parsing logout response: {:?}", e);
-            let err = ApiError::InternalServerError("Error parsing response".into());
-            return Err(status::Custom(
-                err.status_code(),
-                Json(serde_json::json!({
-                    "status": err.status_code().code,
-                    "message": err.to_string(),
-                    "details": config.is_development().then(|| e.to_string())
-                })),
-            ));
-        }
-    };
-
-    if status.is_success() {
-        Ok(response_body)
-    } else {
-        Err(status::Custom(
-            Status::from_code(status.as_u16()).unwrap_or(Status::InternalServerError),
-            Json(response_body),
-        ))
-    }
 }
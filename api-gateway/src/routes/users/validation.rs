@@ -0,0 +1,81 @@
+// src/routes/users/validation.rs
+use super::{ForgotPasswordRequest, LoginRequest, RegisterRequest, ResetPasswordRequest};
+use crate::errors::{ApiError, ErrorResponder, IntoErrorResponse};
+
+/// Field-level problems found in a request body
+#[derive(Default)]
+struct Problems(Vec<String>);
+
+impl Problems {
+    fn check(&mut self, ok: bool, problem: impl Into<String>) {
+        if !ok {
+            self.0.push(problem.into());
+        }
+    }
+
+    fn email(&mut self, email: &str) {
+        self.check(is_email(email), "email must be a valid email address");
+    }
+
+    fn new_password(&mut self, field: &str, password: &str, min_length: usize) {
+        self.check(
+            password.chars().count() >= min_length,
+            format!("{} must be at least {} characters", field, min_length),
+        );
+    }
+
+    fn into_result(self) -> Result<(), ErrorResponder> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        Err(ApiError::BadRequest(self.0.join("; ")).into_error_response(None))
+    }
+}
+
+/// A single `@` with a non-empty local part and a dotted domain. Anything
+/// stricter is left to the user service.
+fn is_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain.split('.').count() >= 2
+        && domain.split('.').all(|label| !label.is_empty())
+        && !email.chars().any(char::is_whitespace)
+}
+
+pub fn login(request: &LoginRequest) -> Result<(), ErrorResponder> {
+    let mut problems = Problems::default();
+    problems.email(&request.email);
+    problems.check(!request.password.is_empty(), "password must not be empty");
+    problems.into_result()
+}
+
+pub fn register(
+    request: &RegisterRequest,
+    min_password_length: usize,
+) -> Result<(), ErrorResponder> {
+    let mut problems = Problems::default();
+    problems.check(!request.name.trim().is_empty(), "name must not be empty");
+    problems.email(&request.email);
+    problems.new_password("password", &request.password, min_password_length);
+    problems.into_result()
+}
+
+pub fn forgot_password(request: &ForgotPasswordRequest) -> Result<(), ErrorResponder> {
+    let mut problems = Problems::default();
+    problems.email(&request.email);
+    problems.into_result()
+}
+
+pub fn reset_password(
+    request: &ResetPasswordRequest,
+    min_password_length: usize,
+) -> Result<(), ErrorResponder> {
+    let mut problems = Problems::default();
+    problems.check(!request.token.is_empty(), "token must not be empty");
+    problems.new_password("new_password", &request.new_password, min_password_length);
+    problems.into_result()
+}
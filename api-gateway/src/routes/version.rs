@@ -0,0 +1,28 @@
+// src/routes/version.rs
+use crate::config::app::AppConfig;
+use rocket::serde::json::Json;
+use rocket::serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct VersionInfo {
+    version: String,
+    git_sha: String,
+    build_timestamp: String,
+    rust_version: String,
+}
+
+/// Build metadata for the running binary, hidden when `EXPOSE_VERSION=false`
+#[get("/")]
+pub fn info(config: &AppConfig) -> Option<Json<VersionInfo>> {
+    if !config.expose_version {
+        return None;
+    }
+
+    Some(Json(VersionInfo {
+        version: env!("CARGO_PKG_VERSION").into(),
+        git_sha: env!("GIT_SHA").into(),
+        build_timestamp: env!("BUILD_TIMESTAMP").into(),
+        rust_version: env!("RUST_VERSION").into(),
+    }))
+}
@@ -0,0 +1,128 @@
+// src/services/breaker.rs
+use crate::config::app::AppConfig;
+use rocket::serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// When a replica's breaker trips and how long it stays open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerPolicy {
+    /// Failed calls in a row that open the breaker, 0 to never open it
+    pub threshold: u64,
+    pub cooldown: Duration,
+}
+
+impl BreakerPolicy {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            threshold: config.breaker_failure_threshold,
+            cooldown: config.breaker_cooldown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "kebab-case")]
+pub enum BreakerState {
+    /// Calls go through
+    Closed,
+    /// Calls are refused until the cooldown runs out
+    Open,
+    /// The cooldown ran out; one trial call decides whether to close again
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    consecutive_failures: u64,
+    total_failures: u64,
+    opened_at: Option<Instant>,
+    /// When the half-open trial call was let through
+    trial_at: Option<Instant>,
+}
+
+/// Circuit breaker guarding one upstream replica. It opens after
+/// `threshold` failed calls in a row, refuses calls for `cooldown`, then
+/// lets a single trial call through: a success closes it, a failure opens
+/// it for another cooldown.
+#[derive(Debug)]
+pub struct Breaker {
+    policy: BreakerPolicy,
+    counts: Mutex<Counts>,
+}
+
+impl Breaker {
+    pub fn new(policy: BreakerPolicy) -> Self {
+        Self {
+            policy,
+            counts: Mutex::new(Counts::default()),
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        let counts = self.counts.lock().unwrap();
+        match counts.opened_at {
+            None => BreakerState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.policy.cooldown => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Whether a call may go to the replica. A half-open breaker admits
+    /// one trial call at a time; a trial that never reports back is given
+    /// up on after a cooldown.
+    pub fn admit(&self) -> bool {
+        let mut counts = self.counts.lock().unwrap();
+        let Some(opened_at) = counts.opened_at else {
+            return true;
+        };
+        if opened_at.elapsed() < self.policy.cooldown {
+            return false;
+        }
+        if counts
+            .trial_at
+            .is_some_and(|trial_at| trial_at.elapsed() < self.policy.cooldown)
+        {
+            return false;
+        }
+        counts.trial_at = Some(Instant::now());
+        true
+    }
+
+    /// Time until the breaker lets a trial call through, `None` unless open
+    pub fn retry_in(&self) -> Option<Duration> {
+        let counts = self.counts.lock().unwrap();
+        let remaining = self
+            .policy
+            .cooldown
+            .checked_sub(counts.opened_at?.elapsed())?;
+        (!remaining.is_zero()).then_some(remaining)
+    }
+
+    /// Count the outcome of one call
+    pub fn record(&self, succeeded: bool) {
+        let mut counts = self.counts.lock().unwrap();
+        if succeeded {
+            counts.consecutive_failures = 0;
+            counts.opened_at = None;
+            counts.trial_at = None;
+            return;
+        }
+
+        counts.consecutive_failures += 1;
+        counts.total_failures += 1;
+        let failed_trial = counts.trial_at.take().is_some();
+        let tripped = self.policy.threshold > 0
+            && counts.opened_at.is_none()
+            && counts.consecutive_failures >= self.policy.threshold;
+        if failed_trial || tripped {
+            counts.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Failed calls since the last successful one, and in total
+    pub fn failures(&self) -> (u64, u64) {
+        let counts = self.counts.lock().unwrap();
+        (counts.consecutive_failures, counts.total_failures)
+    }
+}
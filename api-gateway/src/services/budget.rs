@@ -0,0 +1,54 @@
+// src/services/budget.rs
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Credits are kept in thousandths of a retry, so small ratios still add up
+const MILLI: u64 = 1000;
+
+/// Retries a budget starts with and can bank at most, so a few failures
+/// after a quiet spell are still retried before the ratio takes over
+pub const MAX_BANKED_RETRIES: u64 = 10;
+
+/// Token bucket of retry credits for one upstream. Every request deposits
+/// `ratio` of a retry and every retry spends a whole one, so under
+/// widespread failure retries level off at `ratio` of the traffic instead
+/// of multiplying it.
+#[derive(Debug)]
+pub struct RetryBudget {
+    /// Credit each request deposits, 0 when the budget is unlimited
+    deposit: u64,
+    balance: AtomicU64,
+}
+
+impl RetryBudget {
+    /// A budget earning `ratio` retries per request; 0 leaves retries unlimited
+    pub fn new(ratio: f64) -> Self {
+        Self {
+            deposit: (ratio * MILLI as f64).round() as u64,
+            balance: AtomicU64::new(MAX_BANKED_RETRIES * MILLI),
+        }
+    }
+
+    /// Credit the budget for one request
+    pub fn deposit(&self) {
+        if self.deposit == 0 {
+            return;
+        }
+        let _ = self
+            .balance
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |balance| {
+                Some((balance + self.deposit).min(MAX_BANKED_RETRIES * MILLI))
+            });
+    }
+
+    /// Spend a retry, returning false when the budget can't cover one
+    pub fn withdraw(&self) -> bool {
+        if self.deposit == 0 {
+            return true;
+        }
+        self.balance
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |balance| {
+                balance.checked_sub(MILLI)
+            })
+            .is_ok()
+    }
+}
@@ -0,0 +1,51 @@
+// src/services/chaos.rs
+use crate::config::app::AppConfig;
+use crate::errors::{ErrorResponder, ErrorResponse};
+use log::debug;
+use rand::Rng;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use std::time::Duration;
+
+/// Faults injected into a share of proxied requests for resilience testing.
+/// Only honoured when running in development.
+#[derive(Debug, Clone)]
+pub struct ChaosConfig {
+    pub latency: Duration,
+    pub latency_percent: u8,
+    pub error_percent: u8,
+    pub error_status: Status,
+}
+
+impl ChaosConfig {
+    pub fn is_enabled(&self) -> bool {
+        (self.latency_percent > 0 && !self.latency.is_zero()) || self.error_percent > 0
+    }
+}
+
+/// Run before contacting the upstream: sleeps for the configured latency
+/// and/or fails with the configured status on the sampled requests
+pub async fn inject(config: &AppConfig) -> Result<(), ErrorResponder> {
+    let chaos = &config.chaos;
+    if !config.is_development() || !chaos.is_enabled() {
+        return Ok(());
+    }
+
+    if sampled(chaos.latency_percent) {
+        debug!("Chaos: delaying proxied request by {:?}", chaos.latency);
+        tokio::time::sleep(chaos.latency).await;
+    }
+
+    if sampled(chaos.error_percent) {
+        debug!("Chaos: failing proxied request with {}", chaos.error_status);
+        let status = chaos.error_status;
+        let response = ErrorResponse::new(status, "Injected fault");
+        return Err(ErrorResponder(status, Json(Box::new(response)), Vec::new()));
+    }
+
+    Ok(())
+}
+
+fn sampled(percent: u8) -> bool {
+    percent > 0 && rand::thread_rng().gen_range(0..100) < percent
+}
@@ -0,0 +1,204 @@
+// src/services/client.rs
+use crate::config::app::AppConfig;
+use crate::services::digest::sha256_hex;
+use reqwest::{Certificate, Identity};
+use rustls::RootCertStore;
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio_tungstenite::Connector;
+
+/// Which HTTP versions upstream connections may use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Http2Mode {
+    /// HTTP/1.1 only
+    Off,
+    /// Offer h2 over TLS through ALPN, keeping HTTP/1.1 for backends that
+    /// don't accept it and for plain `http://` targets
+    Negotiate,
+    /// Speak h2 from the first byte, also over plain `http://`. Only for
+    /// backends known to support it, there is no fallback.
+    PriorKnowledge,
+}
+
+impl FromStr for Http2Mode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "false" => Ok(Self::Off),
+            "true" => Ok(Self::Negotiate),
+            "prior-knowledge" => Ok(Self::PriorKnowledge),
+            other => Err(format!(
+                "unknown HTTP/2 mode '{}', expected true, false or prior-knowledge",
+                other
+            )),
+        }
+    }
+}
+
+/// Client certificate and extra trusted CAs for upstreams behind mutual TLS
+#[derive(Clone, Default)]
+pub struct UpstreamTls {
+    identity: Option<Identity>,
+    roots: Vec<Certificate>,
+    /// The same settings for WebSocket upstreams, which don't go through reqwest
+    websocket: Option<Arc<rustls::ClientConfig>>,
+    /// Hash of the PEM files, to tell whether a reload changes them
+    fingerprint: String,
+}
+
+impl PartialEq for UpstreamTls {
+    fn eq(&self, other: &Self) -> bool {
+        self.fingerprint == other.fingerprint
+    }
+}
+
+impl UpstreamTls {
+    /// Load the PEM files named by the config. The certificate and key
+    /// come as a pair; the CA bundle is independent of them.
+    pub fn from_files(
+        cert: Option<&str>,
+        key: Option<&str>,
+        ca: Option<&str>,
+    ) -> Result<Self, String> {
+        let pair = match (cert, key) {
+            (Some(cert), Some(key)) => Some((read(cert)?, read(key)?)),
+            (None, None) => None,
+            _ => return Err("client certificate and key must be set together".into()),
+        };
+        let bundle = ca.map(read).transpose()?;
+
+        let identity = match &pair {
+            Some((cert, key)) => {
+                let mut pem = cert.clone();
+                pem.push(b'\n');
+                pem.extend(key);
+                let identity = Identity::from_pem(&pem)
+                    .map_err(|e| format!("invalid client certificate or key: {}", e))?;
+                Some(identity)
+            }
+            None => None,
+        };
+
+        let roots = match (ca, &bundle) {
+            (Some(ca), Some(bundle)) => Certificate::from_pem_bundle(bundle)
+                .map_err(|e| format!("invalid CA bundle {}: {}", ca, e))?,
+            _ => Vec::new(),
+        };
+
+        let (cert_pem, key_pem) = pair
+            .as_ref()
+            .map_or((&[][..], &[][..]), |(cert, key)| (cert, key));
+        let pem = [cert_pem, key_pem, bundle.as_deref().unwrap_or_default()];
+        let fingerprint = sha256_hex(&pem.join(&0));
+
+        let websocket = match pair.is_some() || bundle.is_some() {
+            true => Some(Arc::new(websocket_config(pair, bundle)?)),
+            false => None,
+        };
+
+        Ok(Self {
+            identity,
+            roots,
+            websocket,
+            fingerprint,
+        })
+    }
+
+    /// TLS connector for WebSocket upstreams, `None` for the default one
+    pub fn websocket_connector(&self) -> Option<Connector> {
+        self.websocket.clone().map(Connector::Rustls)
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.identity.is_some() || !self.roots.is_empty()
+    }
+}
+
+impl fmt::Debug for UpstreamTls {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpstreamTls")
+            .field("client_certificate", &self.identity.is_some())
+            .field("extra_roots", &self.roots.len())
+            .finish()
+    }
+}
+
+/// rustls settings matching what `builder` gives reqwest: the bundled web
+/// roots plus the extra CAs, and the client certificate when there is one
+fn websocket_config(
+    pair: Option<(Vec<u8>, Vec<u8>)>,
+    bundle: Option<Vec<u8>>,
+) -> Result<rustls::ClientConfig, String> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    for cert in rustls_pemfile::certs(&mut bundle.as_deref().unwrap_or_default()) {
+        let cert = cert.map_err(|e| format!("invalid CA bundle: {}", e))?;
+        roots
+            .add(cert)
+            .map_err(|e| format!("invalid CA certificate: {}", e))?;
+    }
+
+    let builder = rustls::ClientConfig::builder().with_root_certificates(roots);
+    let Some((cert, key)) = pair else {
+        return Ok(builder.with_no_client_auth());
+    };
+    let chain = rustls_pemfile::certs(&mut cert.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("invalid client certificate: {}", e))?;
+    let key = rustls_pemfile::private_key(&mut key.as_slice())
+        .map_err(|e| format!("invalid client key: {}", e))?
+        .ok_or("no private key in the client key file")?;
+    builder
+        .with_client_auth_cert(chain, key)
+        .map_err(|e| format!("invalid client certificate or key: {}", e))
+}
+
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Whether `builder` would build the same client for both configurations
+pub fn same_settings(previous: &AppConfig, config: &AppConfig) -> bool {
+    previous.upstream_http2 == config.upstream_http2
+        && previous.upstream_connect_timeout == config.upstream_connect_timeout
+        && previous.upstream_accept_invalid_certs == config.upstream_accept_invalid_certs
+        && previous.environment == config.environment
+        && previous.upstream_tls == config.upstream_tls
+        && previous.log_upstream_timings == config.log_upstream_timings
+}
+
+/// Builder for clients talking to upstreams, with the configured protocol
+/// and TLS settings applied
+pub fn builder(config: &AppConfig) -> reqwest::ClientBuilder {
+    let mut builder = reqwest::Client::builder();
+    match config.upstream_http2 {
+        Http2Mode::Off => builder = builder.http1_only(),
+        Http2Mode::Negotiate => {}
+        Http2Mode::PriorKnowledge => builder = builder.http2_prior_knowledge(),
+    }
+
+    if !config.upstream_connect_timeout.is_zero() {
+        builder = builder.connect_timeout(config.upstream_connect_timeout);
+    }
+
+    // Never outside development, whatever the flag says
+    if config.upstream_accept_invalid_certs && config.is_development() {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    let tls = &config.upstream_tls;
+    if tls.is_enabled() {
+        // PEM identities are only understood by the rustls backend
+        builder = builder.use_rustls_tls();
+        if let Some(identity) = &tls.identity {
+            builder = builder.identity(identity.clone());
+        }
+        for root in &tls.roots {
+            builder = builder.add_root_certificate(root.clone());
+        }
+    }
+    builder
+}
@@ -0,0 +1,108 @@
+// src/services/coalesce.rs
+use crate::services::proxy::ProxyResult;
+use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use reqwest::header::HeaderMap;
+use std::fmt;
+use tokio::sync::watch;
+
+/// Headers that differ between otherwise identical requests without
+/// changing the response, left out of the coalescing key
+const PER_REQUEST_HEADERS: &[&str] = &[
+    "x-request-id",
+    "traceparent",
+    "tracestate",
+    "via",
+    "forwarded",
+    "x-forwarded-for",
+    "x-real-ip",
+];
+
+type Landing = watch::Receiver<Option<ProxyResult>>;
+
+/// Identical GETs in flight to one upstream. The first caller (the leader)
+/// makes the upstream call; callers arriving before it lands wait for its
+/// result instead of sending their own.
+#[derive(Default)]
+pub struct Coalescer {
+    flights: DashMap<String, Landing>,
+}
+
+impl fmt::Debug for Coalescer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Coalescer")
+            .field("in_flight", &self.flights.len())
+            .finish()
+    }
+}
+
+/// A caller's part in a coalesced request
+pub enum Flight<'a> {
+    /// Make the call and hand the result to `Leader::land`
+    Leader(Leader<'a>),
+    /// Wait for the leader's result with `follow`
+    Follower(Landing),
+}
+
+impl Coalescer {
+    pub fn join(&self, key: String) -> Flight<'_> {
+        match self.flights.entry(key.clone()) {
+            Entry::Occupied(entry) => Flight::Follower(entry.get().clone()),
+            Entry::Vacant(entry) => {
+                let (sender, receiver) = watch::channel(None);
+                entry.insert(receiver);
+                Flight::Leader(Leader {
+                    coalescer: self,
+                    key,
+                    sender,
+                })
+            }
+        }
+    }
+}
+
+/// The caller making the shared upstream call. Dropping it without landing,
+/// as when its request is cancelled, lets the followers make their own.
+pub struct Leader<'a> {
+    coalescer: &'a Coalescer,
+    key: String,
+    sender: watch::Sender<Option<ProxyResult>>,
+}
+
+impl Leader<'_> {
+    pub fn land(self, result: &ProxyResult) {
+        self.sender.send_replace(Some(result.clone()));
+    }
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        self.coalescer.flights.remove(&self.key);
+    }
+}
+
+/// The leader's result, or `None` when it gave up without one
+pub async fn follow(mut landing: Landing) -> Option<ProxyResult> {
+    let landed = landing.wait_for(Option::is_some).await.ok()?;
+    landed.clone()
+}
+
+/// Key identifying requests that would get the same response
+pub fn key(path: &str, query: &[(&str, String)], headers: &HeaderMap) -> String {
+    let mut key = path.to_string();
+    for (name, value) in query {
+        key.push_str(&format!("&{}={}", name, value));
+    }
+
+    let mut headers: Vec<_> = headers
+        .iter()
+        .filter(|(name, _)| !PER_REQUEST_HEADERS.contains(&name.as_str()))
+        .map(|(name, value)| format!("{}:{}", name, String::from_utf8_lossy(value.as_bytes())))
+        .collect();
+    headers.sort();
+    for header in headers {
+        key.push('\n');
+        key.push_str(&header);
+    }
+    key
+}
@@ -0,0 +1,55 @@
+// src/services/cors.rs
+use std::str::FromStr;
+
+/// Origins allowed on the routes under a path prefix, narrowing the global
+/// CORS policy, e.g. `/api/payments=https://app.example.com|https://admin.example.com`.
+/// An origin of `*` allows any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsOverride {
+    pub prefix: String,
+    pub origins: Vec<String>,
+}
+
+impl FromStr for CorsOverride {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid CORS override '{}', expected <path>=<origin>|...",
+                rule
+            )
+        };
+        let (prefix, origins) = rule.split_once('=').ok_or_else(invalid)?;
+        let prefix = prefix.trim().trim_end_matches('/');
+        let origins: Vec<String> = origins
+            .split('|')
+            .map(|origin| origin.trim().trim_end_matches('/').to_string())
+            .filter(|origin| !origin.is_empty())
+            .collect();
+        if !prefix.starts_with('/') || origins.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            prefix: prefix.to_string(),
+            origins,
+        })
+    }
+}
+
+impl CorsOverride {
+    pub fn allows(&self, origin: &str) -> bool {
+        self.origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+    }
+}
+
+/// The most specific override covering `path`, if any
+pub fn override_for<'a>(rules: &'a [CorsOverride], path: &str) -> Option<&'a CorsOverride> {
+    rules
+        .iter()
+        .filter(|rule| crate::middleware::is_under(path, &rule.prefix))
+        .max_by_key(|rule| rule.prefix.len())
+}
@@ -0,0 +1,15 @@
+// src/services/digest.rs
+use ring::digest::{SHA256, digest};
+use std::fmt::Write;
+
+/// Lowercase hex SHA-256 of `bytes`, for keying shared state on secrets or
+/// payloads without storing them
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    digest(&SHA256, bytes)
+        .as_ref()
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
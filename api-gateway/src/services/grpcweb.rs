@@ -0,0 +1,227 @@
+// src/services/grpcweb.rs
+use std::time::Duration;
+
+/// Content type of binary gRPC-Web requests and responses
+pub const GRPC_WEB: &str = "application/grpc-web";
+
+/// Flag byte marking a gRPC-Web frame as trailers rather than a message
+const TRAILER_FLAG: u8 = 0x80;
+
+/// gRPC status codes the gateway answers with itself
+#[cfg_attr(not(feature = "grpc-web"), allow(dead_code))]
+pub mod code {
+    pub const UNKNOWN: u32 = 2;
+    pub const DEADLINE_EXCEEDED: u32 = 4;
+    pub const RESOURCE_EXHAUSTED: u32 = 8;
+    pub const UNIMPLEMENTED: u32 = 12;
+    pub const INTERNAL: u32 = 13;
+    pub const UNAVAILABLE: u32 = 14;
+}
+
+/// Outcome of a unary call: the response messages, still length-prefixed
+/// as the upstream framed them, and the trailers carrying its status
+#[derive(Debug, Default)]
+pub struct Reply {
+    pub messages: Vec<u8>,
+    pub trailers: Vec<(String, String)>,
+}
+
+impl Reply {
+    /// A call that failed before the upstream gave a status of its own
+    pub fn failed(code: u32, message: &str) -> Self {
+        Self {
+            messages: Vec::new(),
+            trailers: vec![
+                ("grpc-status".into(), code.to_string()),
+                ("grpc-message".into(), message.into()),
+            ],
+        }
+    }
+
+    /// gRPC-Web response body: the messages, then the trailers in a frame
+    /// of their own, since browsers can't read HTTP/2 trailers
+    pub fn into_body(self) -> Vec<u8> {
+        let mut block = String::new();
+        for (name, value) in &self.trailers {
+            block.push_str(&format!("{}: {}\r\n", name, value));
+        }
+
+        let mut body = self.messages;
+        body.push(TRAILER_FLAG);
+        body.extend((block.len() as u32).to_be_bytes());
+        body.extend(block.as_bytes());
+        body
+    }
+}
+
+/// HTTP/2 connection to the gRPC upstream, shared by every call. It is
+/// opened on the first call, and again after it breaks or the upstream URL
+/// changes on a reload.
+#[derive(Default)]
+pub struct GrpcChannel {
+    #[cfg(feature = "grpc-web")]
+    connection: std::sync::Mutex<Option<(String, h2::client::SendRequest<bytes::Bytes>)>>,
+}
+
+#[cfg(feature = "grpc-web")]
+impl GrpcChannel {
+    /// A sender ready for another call to `url`, reusing the open connection
+    async fn ready(&self, url: &str) -> Result<h2::client::SendRequest<bytes::Bytes>, String> {
+        let open = self
+            .connection
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|(connected, _)| connected == url)
+            .map(|(_, sender)| sender.clone());
+        if let Some(sender) = open
+            && let Ok(sender) = sender.ready().await
+        {
+            return Ok(sender);
+        }
+
+        let sender = connect(url).await?;
+        *self.connection.lock().unwrap() = Some((url.to_string(), sender.clone()));
+        sender.ready().await.map_err(|e| e.to_string())
+    }
+}
+
+/// Open a cleartext HTTP/2 connection to the server at `url`
+#[cfg(feature = "grpc-web")]
+async fn connect(url: &str) -> Result<h2::client::SendRequest<bytes::Bytes>, String> {
+    let uri = url.parse::<http::Uri>().map_err(|e| e.to_string())?;
+    // IPv6 hosts keep their brackets in the URI
+    let host = uri
+        .host()
+        .ok_or("URL has no host")?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = uri.port_u16().unwrap_or(80);
+    let stream = tokio::net::TcpStream::connect((host, port))
+        .await
+        .map_err(|e| e.to_string())?;
+    let (sender, connection) = h2::client::handshake(stream)
+        .await
+        .map_err(|e| e.to_string())?;
+    tokio::spawn(connection);
+    Ok(sender)
+}
+
+/// Make a unary call to the gRPC server at `url` (cleartext HTTP/2) over
+/// the shared `channel`. The request body is passed on as is: gRPC-Web
+/// frames request messages the way gRPC does.
+#[cfg(feature = "grpc-web")]
+pub async fn call(
+    channel: &GrpcChannel,
+    url: &str,
+    path: &str,
+    metadata: &[(String, String)],
+    body: Vec<u8>,
+    limit: usize,
+    timeout: Duration,
+) -> Reply {
+    let exchange = exchange(channel, url, path, metadata, body, limit);
+    match tokio::time::timeout(timeout, exchange).await {
+        Ok(Ok(reply)) => reply,
+        Ok(Err(reply)) => reply,
+        Err(_) => Reply::failed(code::DEADLINE_EXCEEDED, "Upstream gRPC call timed out"),
+    }
+}
+
+#[cfg(feature = "grpc-web")]
+async fn exchange(
+    channel: &GrpcChannel,
+    url: &str,
+    path: &str,
+    metadata: &[(String, String)],
+    body: Vec<u8>,
+    limit: usize,
+) -> Result<Reply, Reply> {
+    use log::warn;
+
+    let unavailable = |e: &dyn std::fmt::Display| {
+        warn!("gRPC call {} to {} failed: {}", path, url, e);
+        Reply::failed(code::UNAVAILABLE, "Upstream gRPC service is unavailable")
+    };
+
+    let mut client = channel.ready(url).await.map_err(|e| unavailable(&e))?;
+
+    let mut request = http::Request::post(format!("{}{}", url, path))
+        .header("content-type", "application/grpc+proto")
+        .header("te", "trailers");
+    for (name, value) in metadata {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    let request = request.body(()).map_err(|e| {
+        warn!("Invalid gRPC metadata for {}: {}", path, e);
+        Reply::failed(code::INTERNAL, "Invalid request metadata")
+    })?;
+
+    let (response, mut send) = client
+        .send_request(request, false)
+        .map_err(|e| unavailable(&e))?;
+    send.send_data(bytes::Bytes::from(body), true)
+        .map_err(|e| unavailable(&e))?;
+    let response = response.await.map_err(|e| unavailable(&e))?;
+
+    let (head, mut received) = response.into_parts();
+    if head.status != http::StatusCode::OK {
+        let code = match head.status.as_u16() {
+            404 => code::UNIMPLEMENTED,
+            502..=504 => code::UNAVAILABLE,
+            _ => code::UNKNOWN,
+        };
+        return Err(Reply::failed(
+            code,
+            &format!("Upstream answered HTTP {}", head.status),
+        ));
+    }
+
+    let mut messages = Vec::new();
+    while let Some(chunk) = received.data().await {
+        let chunk = chunk.map_err(|e| unavailable(&e))?;
+        let _ = received.flow_control().release_capacity(chunk.len());
+        messages.extend_from_slice(&chunk);
+        if messages.len() > limit {
+            return Err(Reply::failed(
+                code::RESOURCE_EXHAUSTED,
+                "Upstream response exceeds the size limit",
+            ));
+        }
+    }
+
+    // A trailers-only response carries its status in the headers
+    let trailers = match received.trailers().await.map_err(|e| unavailable(&e))? {
+        Some(trailers) => trailers,
+        None => head.headers,
+    };
+    let trailers = trailers
+        .iter()
+        .filter(|(name, _)| !is_http_header(name.as_str()))
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+
+    Ok(Reply { messages, trailers })
+}
+
+/// Response headers that describe the HTTP/2 response rather than the call
+#[cfg(feature = "grpc-web")]
+fn is_http_header(name: &str) -> bool {
+    matches!(name, "content-type" | "content-length" | "date" | "server")
+}
+
+#[cfg(not(feature = "grpc-web"))]
+pub async fn call(
+    _channel: &GrpcChannel,
+    _url: &str,
+    _path: &str,
+    _metadata: &[(String, String)],
+    _body: Vec<u8>,
+    _limit: usize,
+    _timeout: Duration,
+) -> Reply {
+    Reply::failed(
+        code::UNIMPLEMENTED,
+        "gRPC-Web requires building with the `grpc-web` feature",
+    )
+}
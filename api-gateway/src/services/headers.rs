@@ -0,0 +1,255 @@
+// src/services/headers.rs
+use crate::config::app::AppConfig;
+use crate::config::live;
+use crate::errors::ApiError;
+use crate::guards::api_key::API_KEY_HEADER;
+use crate::middleware::{REQUEST_ID_HEADER, RequestIdValue};
+use crate::services::tenant::{self, TENANT_HEADER};
+use log::warn;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ORIGIN, VIA};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use std::str::FromStr;
+
+/// Headers that are never forwarded upstream: hop-by-hop headers, plus the
+/// ones reqwest recomputes for the outbound request
+const SKIPPED_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "host",
+    "content-length",
+    "content-type",
+    "accept-encoding",
+    // Already applied by the `MethodOverride` fairing
+    "x-http-method-override",
+    // Rebuilt with the gateway's own entry appended, see `via_chain`
+    "via",
+];
+
+/// How repeated inbound headers are collapsed before forwarding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateHeaderMode {
+    /// Keep only the first occurrence
+    FirstWins,
+    /// Keep only the last occurrence
+    LastWins,
+    /// Join all occurrences into a single comma-separated value
+    Combine,
+}
+
+impl FromStr for DuplicateHeaderMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "first" => Ok(Self::FirstWins),
+            "last" => Ok(Self::LastWins),
+            "combine" => Ok(Self::Combine),
+            other => Err(format!(
+                "unknown duplicate header mode '{}', expected first, last or combine",
+                other
+            )),
+        }
+    }
+}
+
+impl DuplicateHeaderMode {
+    /// Collapse all values of a header into the single value to forward
+    pub fn collapse(&self, name: &str, values: &[&str]) -> Option<String> {
+        match self {
+            Self::FirstWins => values.first().map(|v| v.to_string()),
+            Self::LastWins => values.last().map(|v| v.to_string()),
+            Self::Combine if values.is_empty() => None,
+            // Cookie pairs are separated by "; " rather than ", " (RFC 6265)
+            Self::Combine if name.eq_ignore_ascii_case("cookie") => Some(values.join("; ")),
+            Self::Combine => Some(values.join(", ")),
+        }
+    }
+}
+
+/// What to send upstream in place of the client's `Origin` header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OriginPolicy {
+    /// Drop the header
+    Strip,
+    /// Pass the client's value through unchanged
+    Forward,
+    /// Replace it with a fixed value, sent even when the client had none
+    Rewrite(String),
+}
+
+impl FromStr for OriginPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(':') {
+            Some((mode, origin)) if mode.eq_ignore_ascii_case("rewrite") => {
+                HeaderValue::from_str(origin)
+                    .map(|_| Self::Rewrite(origin.to_string()))
+                    .map_err(|_| format!("invalid rewritten origin '{}'", origin))
+            }
+            _ => match value.to_ascii_lowercase().as_str() {
+                "strip" => Ok(Self::Strip),
+                "forward" => Ok(Self::Forward),
+                other => Err(format!(
+                    "unknown origin policy '{}', expected strip, forward or rewrite:<origin>",
+                    other
+                )),
+            },
+        }
+    }
+}
+
+impl OriginPolicy {
+    fn apply(&self, headers: &mut HeaderMap) {
+        match self {
+            Self::Strip => {
+                headers.remove(ORIGIN);
+            }
+            Self::Forward => {}
+            Self::Rewrite(origin) => {
+                if let Ok(value) = HeaderValue::from_str(origin) {
+                    headers.insert(ORIGIN, value);
+                }
+            }
+        }
+    }
+}
+
+/// Inbound headers normalized for forwarding to an upstream service
+pub struct ForwardedHeaders(pub HeaderMap);
+
+impl ForwardedHeaders {
+    pub fn into_inner(self) -> HeaderMap {
+        self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ForwardedHeaders {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let config = live::current(request);
+
+        let via = match config {
+            Some(config) => match via_chain(request, config) {
+                Ok(via) => Some(via),
+                Err(err) => {
+                    warn!("Rejecting {}: {}", request.uri(), err);
+                    err.stash(request);
+                    return Outcome::Error((Status::LoopDetected, err));
+                }
+            },
+            None => None,
+        };
+        let mode = config
+            .map(|config| config.duplicate_header_mode)
+            .unwrap_or(DuplicateHeaderMode::Combine);
+
+        let headers = request.headers();
+        let mut forwarded = HeaderMap::new();
+
+        for header in headers.iter() {
+            let name = header.name().as_str();
+            let lowercase = name.to_ascii_lowercase();
+
+            // The client's key for this gateway is stripped so it never
+            // leaks to a backend
+            if SKIPPED_HEADERS.contains(&lowercase.as_str())
+                || name.eq_ignore_ascii_case(API_KEY_HEADER)
+            {
+                continue;
+            }
+
+            let Ok(header_name) = HeaderName::from_bytes(lowercase.as_bytes()) else {
+                continue;
+            };
+
+            // Each name is handled once, on its first occurrence
+            if forwarded.contains_key(&header_name) {
+                continue;
+            }
+
+            let values: Vec<&str> = headers.get(name).collect();
+            let collapsed = mode
+                .collapse(name, &values)
+                .and_then(|value| HeaderValue::from_str(&value).ok());
+
+            if let Some(value) = collapsed {
+                forwarded.insert(header_name, value);
+            }
+        }
+
+        match config {
+            Some(config) => config.origin_policy.apply(&mut forwarded),
+            None => OriginPolicy::Strip.apply(&mut forwarded),
+        }
+
+        if let Some(value) = via.and_then(|via| HeaderValue::from_str(&via).ok()) {
+            forwarded.insert(VIA, value);
+        }
+
+        let request_id = request.guard::<RequestIdValue>().await;
+        if let Some(value) = request_id
+            .succeeded()
+            .and_then(|id| HeaderValue::from_str(&id.0).ok())
+        {
+            forwarded.insert(REQUEST_ID_HEADER, value);
+        }
+
+        // Upstreams only see the tenant the gateway resolved
+        forwarded.remove(TENANT_HEADER);
+        if let Some(value) = tenant::current(request).and_then(|id| HeaderValue::from_str(id).ok())
+        {
+            forwarded.insert(TENANT_HEADER, value);
+        }
+
+        Outcome::Success(ForwardedHeaders(forwarded))
+    }
+}
+
+/// Check the inbound `Via` chain for loops and excessive hops, returning
+/// the chain to send upstream with this gateway appended
+fn via_chain(request: &Request<'_>, config: &AppConfig) -> Result<String, ApiError> {
+    let hops: Vec<&str> = request
+        .headers()
+        .get("Via")
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .collect();
+
+    // Each hop is `<protocol> <received-by> [comment]`
+    let already_traversed = hops
+        .iter()
+        .filter_map(|hop| hop.split_whitespace().nth(1))
+        .any(|received_by| received_by.eq_ignore_ascii_case(&config.gateway_id));
+
+    if already_traversed {
+        return Err(ApiError::LoopDetected(format!(
+            "Request already passed through {}",
+            config.gateway_id
+        )));
+    }
+
+    if hops.len() >= config.max_proxy_hops {
+        return Err(ApiError::LoopDetected(format!(
+            "Request exceeded the maximum of {} proxy hops",
+            config.max_proxy_hops
+        )));
+    }
+
+    let own = format!("1.1 {}", config.gateway_id);
+    Ok(hops
+        .into_iter()
+        .chain(std::iter::once(own.as_str()))
+        .collect::<Vec<_>>()
+        .join(", "))
+}
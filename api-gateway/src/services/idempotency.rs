@@ -0,0 +1,218 @@
+// src/services/idempotency.rs
+use crate::guards::api_key::API_KEY_HEADER;
+use crate::guards::jwt;
+use crate::services::digest::sha256_hex;
+use crate::services::proxy::ProxyResponse;
+use crate::services::store::Store;
+use crate::services::tenant;
+use log::{debug, warn};
+use rocket::http::{Header, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::serde::json::Value;
+use rocket::serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Header clients use to make a POST safe to retry
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Header marking a response replayed from the idempotency store
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// Stored while a request with the key is being proxied
+const IN_FLIGHT: &[u8] = b"in-flight";
+
+/// A relayed response as recorded in the store, with the fingerprint of
+/// the request body it answered
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Recorded {
+    status: u16,
+    body: Value,
+    headers: Vec<(String, String)>,
+    fingerprint: String,
+}
+
+impl Recorded {
+    fn new(response: &ProxyResponse, fingerprint: &str) -> Self {
+        Self {
+            fingerprint: fingerprint.to_string(),
+            status: response.status.code,
+            body: response.body.clone(),
+            headers: response
+                .headers
+                .iter()
+                .map(|header| (header.name().to_string(), header.value().to_string()))
+                .collect(),
+        }
+    }
+
+    fn replay(self) -> ProxyResponse {
+        let mut headers: Vec<Header<'static>> = self
+            .headers
+            .into_iter()
+            .map(|(name, value)| Header::new(name, value))
+            .collect();
+        headers.push(Header::new(IDEMPOTENT_REPLAYED_HEADER, "true"));
+
+        ProxyResponse {
+            status: Status::from_code(self.status).unwrap_or(Status::InternalServerError),
+            body: self.body,
+            headers,
+            upstream_time: None,
+            retries: 0,
+            cache_status: Some("replayed"),
+        }
+    }
+}
+
+/// Outcome of claiming an idempotency key before proxying
+pub enum Claim {
+    /// First use of the key: proxy the request and record the result
+    Proceed,
+    /// A response was already recorded for the key
+    Replay(ProxyResponse),
+    /// Another request with the same key hasn't finished yet
+    InFlight,
+    /// The key was already used with a different request body
+    Mismatch,
+}
+
+/// Responses recorded per route and idempotency key, in the configured
+/// store so a retry that lands on another replica is still replayed.
+/// While the store is unavailable requests proceed unprotected.
+pub struct IdempotencyStore {
+    store: Arc<dyn Store>,
+    ttl: Duration,
+    in_flight_timeout: Duration,
+}
+
+impl IdempotencyStore {
+    /// `in_flight_timeout` bounds how long an unfinished claim blocks the
+    /// key, so a request cancelled mid-flight doesn't lock it forever
+    pub fn new(store: Arc<dyn Store>, ttl: Duration, in_flight_timeout: Duration) -> Self {
+        Self {
+            store,
+            ttl,
+            in_flight_timeout,
+        }
+    }
+
+    /// Claim `key` for a request whose body has `fingerprint`
+    pub async fn claim(&self, key: &str, fingerprint: &str) -> Claim {
+        let stored = format!("idempotency:{}", key);
+        // A record expiring between the two calls gets one more attempt
+        for _ in 0..2 {
+            match self
+                .store
+                .set_nx(&stored, IN_FLIGHT, self.in_flight_timeout)
+                .await
+            {
+                Ok(true) => return Claim::Proceed,
+                Ok(false) => {}
+                Err(e) => {
+                    warn!("Idempotency key {} not checked: {}", key, e);
+                    return Claim::Proceed;
+                }
+            }
+
+            match self.store.get(&stored).await {
+                Ok(Some(record)) if record == IN_FLIGHT => return Claim::InFlight,
+                Ok(Some(record)) => match serde_json::from_slice::<Recorded>(&record) {
+                    Ok(recorded) if recorded.fingerprint != fingerprint => return Claim::Mismatch,
+                    Ok(recorded) => return Claim::Replay(recorded.replay()),
+                    Err(e) => {
+                        warn!("Discarding unreadable record for {}: {}", key, e);
+                        self.release(key).await;
+                    }
+                },
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("Idempotency key {} not checked: {}", key, e);
+                    return Claim::Proceed;
+                }
+            }
+        }
+        Claim::InFlight
+    }
+
+    pub async fn complete(&self, key: &str, fingerprint: &str, response: &ProxyResponse) {
+        let record = match serde_json::to_vec(&Recorded::new(response, fingerprint)) {
+            Ok(record) => record,
+            Err(e) => {
+                warn!("Response for {} not recorded: {}", key, e);
+                return self.release(key).await;
+            }
+        };
+
+        let stored = format!("idempotency:{}", key);
+        if let Err(e) = self.store.set(&stored, &record, self.ttl).await {
+            warn!("Response for {} not recorded: {}", key, e);
+        }
+    }
+
+    /// Release a claim without recording, so the client may retry
+    pub async fn release(&self, key: &str) {
+        if let Err(e) = self.store.delete(&format!("idempotency:{}", key)).await {
+            warn!("Idempotency key {} not released: {}", key, e);
+        }
+    }
+}
+
+/// Fingerprint of a request body, which a replayed key must match
+pub fn fingerprint(body: Option<&Value>) -> String {
+    let bytes = body.map(|body| body.to_string()).unwrap_or_default();
+    sha256_hex(bytes.as_bytes())
+}
+
+/// Who is making the request, so callers can't replay each other's
+/// responses by reusing a key: the tenant plus the bearer token's user or a
+/// hash of the API key
+fn caller(request: &Request<'_>) -> String {
+    let tenant = tenant::current(request).unwrap_or("-");
+    let principal = match (
+        jwt::claims(request),
+        request.headers().get_one(API_KEY_HEADER),
+    ) {
+        (Some(claims), _) => format!("user:{}", claims.user_id),
+        (None, Some(key)) => format!("key:{}", sha256_hex(key.as_bytes())),
+        (None, None) => "anonymous".to_string(),
+    };
+    format!("{} {}", tenant, principal)
+}
+
+/// The request's idempotency key, scoped to its caller, method and route
+pub struct IdempotencyKey<'r> {
+    pub key: String,
+    pub store: &'r IdempotencyStore,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey<'r> {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let key = request
+            .headers()
+            .get_one(IDEMPOTENCY_KEY_HEADER)
+            .map(str::trim)
+            .filter(|key| !key.is_empty());
+
+        let (Some(key), Some(store)) = (key, request.rocket().state::<IdempotencyStore>()) else {
+            // Forwarding makes an `Option<IdempotencyKey>` guard resolve to None
+            return Outcome::Forward(Status::Ok);
+        };
+
+        let scoped = format!(
+            "{} {} {} {}",
+            caller(request),
+            request.method(),
+            request.uri().path(),
+            key
+        );
+        debug!("Request carries idempotency key {}", scoped);
+
+        Outcome::Success(IdempotencyKey { key: scoped, store })
+    }
+}
@@ -0,0 +1,82 @@
+// src/services/iplist.rs
+use std::collections::{BTreeMap, HashSet};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// A set of CIDR ranges, e.g. `203.0.113.0/24,2001:db8::/32,198.51.100.7`.
+/// Ranges are grouped by prefix length, so a lookup costs one set probe per
+/// distinct length rather than one comparison per range.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IpList {
+    v4: BTreeMap<u32, HashSet<u32>>,
+    v6: BTreeMap<u32, HashSet<u128>>,
+}
+
+impl IpList {
+    pub fn is_empty(&self) -> bool {
+        self.v4.is_empty() && self.v6.is_empty()
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match ip.to_canonical() {
+            IpAddr::V4(ip) => {
+                let ip = u32::from(ip);
+                self.v4
+                    .iter()
+                    .any(|(prefix, networks)| networks.contains(&mask_v4(ip, *prefix)))
+            }
+            IpAddr::V6(ip) => {
+                let ip = u128::from(ip);
+                self.v6
+                    .iter()
+                    .any(|(prefix, networks)| networks.contains(&mask_v6(ip, *prefix)))
+            }
+        }
+    }
+}
+
+impl FromStr for IpList {
+    type Err = String;
+
+    /// Comma-separated CIDR ranges; a bare address is a range of one
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut list = IpList::default();
+        for range in value.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            let invalid = || format!("invalid CIDR range '{}'", range);
+            let (address, prefix) = match range.split_once('/') {
+                Some((address, prefix)) => (address, Some(prefix)),
+                None => (range, None),
+            };
+            let address = address.parse::<IpAddr>().map_err(|_| invalid())?;
+            let bits = if address.is_ipv4() { 32 } else { 128 };
+            let prefix = match prefix {
+                Some(prefix) => prefix
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|prefix| *prefix <= bits)
+                    .ok_or_else(invalid)?,
+                None => bits,
+            };
+
+            match address {
+                IpAddr::V4(address) => {
+                    let network = mask_v4(u32::from(address), prefix);
+                    list.v4.entry(prefix).or_default().insert(network);
+                }
+                IpAddr::V6(address) => {
+                    let network = mask_v6(u128::from(address), prefix);
+                    list.v6.entry(prefix).or_default().insert(network);
+                }
+            }
+        }
+        Ok(list)
+    }
+}
+
+fn mask_v4(ip: u32, prefix: u32) -> u32 {
+    ip & u32::MAX.checked_shl(32 - prefix).unwrap_or(0)
+}
+
+fn mask_v6(ip: u128, prefix: u32) -> u128 {
+    ip & u128::MAX.checked_shl(128 - prefix).unwrap_or(0)
+}
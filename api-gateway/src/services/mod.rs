@@ -1,3 +1,32 @@
 // src/services/mod.rs
-// This module will be used for any shared service logic
-// Currently a placeholder for future implementation
+// Shared service logic used by the proxy routes
+pub mod breaker;
+pub mod budget;
+pub mod chaos;
+pub mod client;
+pub mod coalesce;
+pub mod digest;
+pub mod cors;
+pub mod grpcweb;
+pub mod headers;
+pub mod idempotency;
+pub mod iplist;
+pub mod process;
+pub mod proxy;
+pub mod ratelimit;
+pub mod redact;
+pub mod remap;
+pub mod required;
+pub mod rewrite;
+pub mod selftest;
+pub mod stale;
+pub mod stats;
+pub mod store;
+pub mod switches;
+pub mod telemetry;
+pub mod tenant;
+pub mod timeouts;
+pub mod timing;
+pub mod transform;
+pub mod upstream;
+pub mod waf;
@@ -0,0 +1,69 @@
+// src/services/process.rs
+use std::fs;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Clock ticks per second in /proc/self/stat, fixed by Linux's userspace ABI
+const USER_HZ: f64 = 100.0;
+
+/// Resource usage of the gateway process, read from /proc on every scrape
+/// of /api/metrics and exported under the usual Prometheus `process_*`
+/// names. Gauges whose source is unavailable, as off Linux, are left out.
+pub struct ProcessMetrics {
+    started: Instant,
+    start_time: f64,
+}
+
+impl Default for ProcessMetrics {
+    fn default() -> Self {
+        let start_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        Self {
+            started: Instant::now(),
+            start_time,
+        }
+    }
+}
+
+impl ProcessMetrics {
+    pub fn record(&self) {
+        metrics::gauge!("process_start_time_seconds").set(self.start_time);
+        metrics::gauge!("process_uptime_seconds").set(self.started.elapsed().as_secs_f64());
+
+        let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+        if let Some(kilobytes) = status_field(&status, "VmRSS:") {
+            metrics::gauge!("process_resident_memory_bytes").set(kilobytes * 1024.0);
+        }
+        if let Some(threads) = status_field(&status, "Threads:") {
+            metrics::gauge!("process_threads").set(threads);
+        }
+
+        if let Ok(fds) = fs::read_dir("/proc/self/fd") {
+            metrics::gauge!("process_open_fds").set(fds.count() as f64);
+        }
+        if let Some(seconds) = cpu_seconds() {
+            metrics::gauge!("process_cpu_seconds_total").set(seconds);
+        }
+    }
+}
+
+/// First number on the `name` line of /proc/self/status
+fn status_field(status: &str, name: &str) -> Option<f64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(name))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// User plus system CPU time, from fields 14 and 15 of /proc/self/stat
+fn cpu_seconds() -> Option<f64> {
+    let stat = fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name may contain spaces, the fields after it can't
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let utime: f64 = fields.get(11)?.parse().ok()?;
+    let stime: f64 = fields.get(12)?.parse().ok()?;
+    Some((utime + stime) / USER_HZ)
+}
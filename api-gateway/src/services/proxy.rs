@@ -0,0 +1,1152 @@
+// src/services/proxy.rs
+use crate::config::app::AppConfig;
+use crate::config::live;
+use crate::errors::{ApiError, ErrorResponder, ErrorResponse, IntoErrorResponse};
+use crate::guards::upload::Upload;
+use crate::middleware::{REQUEST_ID_HEADER, RequestDeadline, RequestIdValue};
+use crate::services::breaker::BreakerState;
+use crate::services::chaos;
+use crate::services::coalesce::{self, Flight};
+use crate::services::headers::ForwardedHeaders;
+use crate::services::idempotency::{self, Claim, IdempotencyKey};
+use crate::services::redact::redact;
+use crate::services::remap::remap_status;
+use crate::services::rewrite::rewrite_path;
+use crate::services::stale;
+use crate::services::timing::UpstreamTimings;
+use crate::services::transform::transform_body;
+use crate::services::upstream::Upstream;
+use log::{debug, error, info, warn};
+use reqwest::Method;
+use reqwest::header::{
+    ACCEPT, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, HeaderMap, HeaderValue,
+};
+use rocket::Either;
+use rocket::data::{Data, ToByteUnit};
+use rocket::futures::stream;
+use rocket::http::{Header, RawStr, Status};
+use rocket::request::Request;
+use rocket::response::stream::ReaderStream;
+use rocket::response::{self, Responder, Response, status};
+use rocket::serde::json::{Json, Value, json};
+use std::fmt::Display;
+use std::io::{self, Cursor};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncReadExt;
+use tokio::sync::{OwnedSemaphorePermit, mpsc};
+use tokio::time::{Instant, timeout_at};
+
+/// Result type returned by every proxied route: the relayed upstream
+/// response, or an error produced by the gateway itself
+pub type ProxyResult = Result<ProxyResponse, ErrorResponder>;
+
+/// Result type of routes that may relay an event stream: a buffered
+/// response as from `ProxyResult`, or the stream itself
+pub type StreamingResult = Result<Either<ProxyResponse, StreamedResponse>, ErrorResponder>;
+
+/// Header telling clients which upstream target answered
+pub const UPSTREAM_HEADER: &str = "X-Upstream";
+
+/// Media type of Server-Sent Events
+pub const EVENT_STREAM: &str = "text/event-stream";
+
+/// An upstream response relayed to the client with the upstream's status
+#[derive(Clone)]
+pub struct ProxyResponse {
+    pub status: Status,
+    pub body: Value,
+    pub headers: Vec<Header<'static>>,
+    /// Time spent waiting on upstreams, absent for replayed responses
+    pub upstream_time: Option<Duration>,
+    /// Attempts after the first one it took to get the response
+    pub retries: u32,
+    /// How a response not fetched for this request was served
+    pub cache_status: Option<&'static str>,
+}
+
+impl<'r> Responder<'r, 'static> for ProxyResponse {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        request.local_cache(|| UpstreamCall {
+            time: self.upstream_time,
+            retries: self.retries,
+            cache_status: self.cache_status,
+        });
+        let mut response = if matches!(self.status.code, 204 | 304) {
+            // These statuses can't carry a body
+            Response::build().status(self.status).finalize()
+        } else {
+            let body = match live::current(request) {
+                Some(config) if config.response_envelope && self.status.code < 400 => {
+                    envelope(self.body, request)
+                }
+                _ => self.body,
+            };
+            status::Custom(self.status, Json(body)).respond_to(request)?
+        };
+        for header in self.headers {
+            response.set_header(header);
+        }
+        Ok(response)
+    }
+}
+
+/// A success body as `{data, meta}`, with the meta of the request it
+/// answers, also when the response itself was replayed
+fn envelope(body: Value, request: &Request<'_>) -> Value {
+    let request_id = request.local_cache(|| RequestIdValue(uuid::Uuid::new_v4().to_string()));
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    json!({
+        "data": body,
+        "meta": { "request_id": request_id.0, "timestamp": timestamp },
+    })
+}
+
+/// An upstream event stream piped to the client chunk by chunk as it
+/// arrives, open until either side closes it
+pub struct StreamedResponse {
+    status: Status,
+    content_type: String,
+    headers: Vec<Header<'static>>,
+    response: reqwest::Response,
+    /// Bulkhead slot, held until the stream ends
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl<'r> Responder<'r, 'static> for StreamedResponse {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let chunks = stream::unfold(
+            (self.response, self.permit),
+            |(mut response, permit)| async move {
+                match response.chunk().await {
+                    Ok(Some(chunk)) => Some((Cursor::new(chunk), (response, permit))),
+                    Ok(None) => None,
+                    Err(e) => {
+                        debug!("Upstream event stream ended: {}", e);
+                        None
+                    }
+                }
+            },
+        );
+
+        let mut response = Response::build();
+        response
+            .status(self.status)
+            .raw_header("Content-Type", self.content_type)
+            .raw_header("Cache-Control", "no-cache")
+            // Asks proxies in front of the gateway (nginx) not to buffer either
+            .raw_header("X-Accel-Buffering", "no")
+            .streamed_body(ReaderStream::from(chunks));
+        for header in self.headers {
+            response.header(header);
+        }
+        Ok(response.finalize())
+    }
+}
+
+/// How the response being served was obtained, for the Server-Timing header
+/// and the access log
+#[derive(Default)]
+pub struct UpstreamCall {
+    pub time: Option<Duration>,
+    pub retries: u32,
+    pub cache_status: Option<&'static str>,
+}
+
+/// A request to be forwarded to one of the upstream services
+pub struct ProxyRequest<'a> {
+    upstream: &'a Upstream,
+    method: Method,
+    path: String,
+    headers: HeaderMap,
+    query: Vec<(&'static str, String)>,
+    body: Option<Value>,
+    deadline: Option<RequestDeadline>,
+    /// `UPSTREAM_TOTAL_TIMEOUT_MS`, for each buffered attempt only
+    attempt_timeout: Option<Duration>,
+    idempotency: Option<IdempotencyKey<'a>>,
+    has_idempotency_key: bool,
+    canary_key: Option<String>,
+}
+
+impl<'a> ProxyRequest<'a> {
+    pub fn new(upstream: &'a Upstream, method: Method, path: impl Into<String>) -> Self {
+        Self {
+            upstream,
+            method,
+            path: path.into(),
+            headers: HeaderMap::new(),
+            query: Vec::new(),
+            body: None,
+            deadline: None,
+            attempt_timeout: None,
+            idempotency: None,
+            has_idempotency_key: false,
+            canary_key: None,
+        }
+    }
+
+    pub fn get(upstream: &'a Upstream, path: impl Into<String>) -> Self {
+        Self::new(upstream, Method::GET, path)
+    }
+
+    pub fn post(upstream: &'a Upstream, path: impl Into<String>) -> Self {
+        Self::new(upstream, Method::POST, path)
+    }
+
+    pub fn headers(mut self, headers: ForwardedHeaders) -> Self {
+        self.headers = headers.into_inner();
+        self
+    }
+
+    /// Add a query parameter to the upstream request when a value is present
+    pub fn query(mut self, name: &'static str, value: Option<&str>) -> Self {
+        if let Some(value) = value {
+            self.query.push((name, value.to_string()));
+        }
+        self
+    }
+
+    pub fn json(mut self, body: Value) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    /// Key the canary split is made on, normally the caller's user ID.
+    /// Requests without one are split on their request ID.
+    pub fn canary_key(mut self, key: Option<impl Into<String>>) -> Self {
+        self.canary_key = key.map(Into::into);
+        self
+    }
+
+    pub fn deadline(mut self, deadline: RequestDeadline) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn idempotency(mut self, key: Option<IdempotencyKey<'a>>) -> Self {
+        self.has_idempotency_key = key.is_some();
+        self.idempotency = key;
+        self
+    }
+
+    /// Send the request, replaying the recorded response instead when its
+    /// idempotency key was already used on this route
+    pub async fn send(mut self, config: &AppConfig) -> ProxyResult {
+        let Some(idempotency) = self.idempotency.take() else {
+            return self.send_or_stale(config).await;
+        };
+
+        let fingerprint = idempotency::fingerprint(self.body.as_ref());
+        match idempotency
+            .store
+            .claim(&idempotency.key, &fingerprint)
+            .await
+        {
+            Claim::Proceed => {}
+            Claim::Replay(response) => {
+                debug!("Replaying recorded response for {}", idempotency.key);
+                return Ok(response);
+            }
+            Claim::InFlight => {
+                let err = ApiError::Conflict(
+                    "A request with this Idempotency-Key is still in progress".into(),
+                );
+                return Err(err.into_error_response(None));
+            }
+            Claim::Mismatch => {
+                let err = ApiError::Conflict(
+                    "This Idempotency-Key was already used with a different request body".into(),
+                );
+                return Err(err.into_error_response(None));
+            }
+        }
+
+        let result = self.send_within_deadline(config).await;
+
+        // Gateway failures and upstream 5xx are left retryable
+        match &result {
+            Ok(response) if response.status.code < 500 => {
+                idempotency
+                    .store
+                    .complete(&idempotency.key, &fingerprint, response)
+                    .await
+            }
+            _ => idempotency.store.release(&idempotency.key).await,
+        }
+
+        result
+    }
+
+    /// Send the request once, piping the answer back as it arrives when the
+    /// upstream responds with an event stream and relaying it as usual
+    /// otherwise. The deadline only bounds the wait for the response head.
+    /// A stream holds its bulkhead slot until it ends and follows the
+    /// breaker like any call, but is never retried, coalesced or cached.
+    pub async fn stream(mut self, config: &AppConfig) -> StreamingResult {
+        self.path = rewrite_path(&config.path_rewrites, &self.path);
+        self.headers
+            .insert(ACCEPT, HeaderValue::from_static(EVENT_STREAM));
+        let Ok(permit) = self.upstream.acquire(config.bulkhead_queue_timeout).await else {
+            warn!(
+                "{} is at its concurrency limit, rejecting stream {}",
+                self.upstream.label, self.path
+            );
+            let err =
+                ApiError::ServiceUnavailable(format!("{} is at capacity", self.upstream.label));
+            return Err(error_response(
+                config,
+                err,
+                "no bulkhead slot became available",
+            ));
+        };
+
+        let key = self
+            .canary_key
+            .as_deref()
+            .unwrap_or_else(|| self.request_id());
+        let canary = self.upstream.canary_for(key);
+        let short_circuit = canary.is_none() && self.upstream.breaker_state() == BreakerState::Open;
+        let (target, via_fallback) = match (short_circuit, self.upstream.fallback()) {
+            (false, _) => (canary.unwrap_or_else(|| self.upstream.next_target()), false),
+            (true, Some(fallback)) => (fallback, true),
+            (true, None) => return Err(self.breaker_open(config)),
+        };
+        let url = format!("{}{}", target, self.path);
+
+        let started = Instant::now();
+        let attempt = self.attempt(self.upstream.client(), &url, None);
+        let result = match self.deadline {
+            Some(deadline) => match timeout_at(Instant::from_std(deadline.at), attempt).await {
+                Ok(result) => result,
+                Err(_) => {
+                    error!("Gateway deadline exceeded while opening stream {}", url);
+                    let err = ApiError::RequestTimeout("Gateway request deadline exceeded".into());
+                    return Err(error_response(
+                        config,
+                        err,
+                        format!("no response within {:?}", deadline.timeout),
+                    ));
+                }
+            },
+            None => attempt.await,
+        };
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                error!("Error opening stream from {}: {:?}", url, e);
+                let err =
+                    ApiError::ServiceUnavailable(format!("{} unavailable", self.upstream.label));
+                return Err(error_response(config, err, e));
+            }
+        };
+
+        let upstream_status = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        if content_type.starts_with(EVENT_STREAM) {
+            debug!(
+                "[{}] Streaming {} events from {}",
+                self.request_id(),
+                self.upstream.label,
+                url
+            );
+            let status = Status::from_code(upstream_status).unwrap_or(Status::InternalServerError);
+            return Ok(Either::Right(StreamedResponse {
+                status: remap_status(&config.status_remaps, status),
+                content_type,
+                headers: response_headers(via_fallback),
+                response,
+                permit,
+            }));
+        }
+
+        let bytes = self.read_upstream_body(config, &url, response).await?;
+        let (status, body) = relay(config, self.upstream.label, upstream_status, &bytes)?;
+        Ok(Either::Left(ProxyResponse {
+            status,
+            body,
+            headers: response_headers(via_fallback),
+            upstream_time: Some(started.elapsed()),
+            retries: 0,
+            cache_status: None,
+        }))
+    }
+
+    /// Stream a multipart upload to the upstream chunk by chunk as it is
+    /// read from the client, with its Content-Type (and so its boundary)
+    /// unchanged. The body can only be read once, so the upload goes to one
+    /// replica and isn't retried or failed over.
+    pub async fn upload(mut self, config: &AppConfig, upload: Upload<'_>) -> ProxyResult {
+        self.path = rewrite_path(&config.path_rewrites, &self.path);
+        let Ok(_permit) = self.upstream.acquire(config.bulkhead_queue_timeout).await else {
+            let err =
+                ApiError::ServiceUnavailable(format!("{} is at capacity", self.upstream.label));
+            return Err(error_response(
+                config,
+                err,
+                "no bulkhead slot became available",
+            ));
+        };
+
+        let key = self
+            .canary_key
+            .as_deref()
+            .unwrap_or_else(|| self.request_id());
+        let target = self
+            .upstream
+            .canary_for(key)
+            .unwrap_or_else(|| self.upstream.next_target());
+        let url = format!("{}{}", target, self.path);
+        debug!("Streaming {} {} upload to {}", self.method, self.path, url);
+
+        // A few chunks in flight at most, so the client is only read as fast
+        // as the upstream takes the body
+        let (sender, receiver) = mpsc::channel::<io::Result<Vec<u8>>>(4);
+        let chunks = stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|chunk| (chunk, receiver))
+        });
+        let mut request = self
+            .outbound(self.upstream.client(), &url)
+            .header(CONTENT_TYPE, upload.content_type)
+            .body(reqwest::Body::wrap_stream(chunks));
+        if let Some(length) = upload.length {
+            request = request.header(CONTENT_LENGTH, length);
+        }
+
+        let started = Instant::now();
+        let exchange = async {
+            tokio::join!(
+                pump(upload.data, upload.limit, sender),
+                self.send_counted(&url, request)
+            )
+        };
+        let (pumped, result) = match self.deadline {
+            Some(deadline) => match timeout_at(Instant::from_std(deadline.at), exchange).await {
+                Ok(exchanged) => exchanged,
+                Err(_) => {
+                    error!("Gateway deadline exceeded while uploading to {}", url);
+                    let err = ApiError::RequestTimeout("Gateway request deadline exceeded".into());
+                    return Err(error_response(
+                        config,
+                        err,
+                        format!("no response within {:?}", deadline.timeout),
+                    ));
+                }
+            },
+            None => exchange.await,
+        };
+
+        if pumped == Pumped::TooLarge {
+            let message = format!("Upload exceeds the {} byte limit", upload.limit);
+            let error = ErrorResponse::new(Status::PayloadTooLarge, message);
+            return Err(ErrorResponder(
+                Status::PayloadTooLarge,
+                Json(Box::new(error)),
+                Vec::new(),
+            ));
+        }
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                error!("Error uploading to {}: {:?}", url, e);
+                let err =
+                    ApiError::ServiceUnavailable(format!("{} unavailable", self.upstream.label));
+                return Err(error_response(config, err, e));
+            }
+        };
+
+        let upstream_status = response.status().as_u16();
+        let bytes = self.read_upstream_body(config, &url, response).await?;
+        let (status, body) = relay(config, self.upstream.label, upstream_status, &bytes)?;
+        Ok(ProxyResponse {
+            status,
+            body,
+            headers: Vec::new(),
+            upstream_time: Some(started.elapsed()),
+            retries: 0,
+            cache_status: None,
+        })
+    }
+
+    /// Fall back to the last good response for a GET, at most
+    /// `STALE_MAX_AGE` old, when the upstream fails. Requests sent with
+    /// `Cache-Control: no-cache` always get the upstream's own answer.
+    async fn send_or_stale(self, config: &AppConfig) -> ProxyResult {
+        let max_age = config.stale_max_age;
+        if max_age.is_zero()
+            || self.method != Method::GET
+            || self.body.is_some()
+            || stale::is_refused(&self.headers)
+        {
+            return self.send_coalesced(config).await;
+        }
+
+        let upstream = self.upstream;
+        let key = coalesce::key(&self.path, &self.query, &self.headers);
+        let request_id = self.request_id().to_string();
+        let result = self.send_coalesced(config).await;
+
+        let cache = upstream.stale_cache();
+        if stale::is_fresh(&result) {
+            if let Ok(response) = &result {
+                cache.store(key, response, max_age);
+            }
+        } else if stale::is_failure(&result)
+            && let Some(response) = cache.stale(&key, max_age)
+        {
+            warn!(
+                "[{}] {} failed, serving a stale response",
+                request_id, upstream.label
+            );
+            return Ok(response);
+        }
+        result
+    }
+
+    /// Share one upstream call between identical GETs in flight at the same
+    /// time, when `COALESCE_GETS` is on
+    async fn send_coalesced(self, config: &AppConfig) -> ProxyResult {
+        if !config.coalesce_gets || self.method != Method::GET || self.body.is_some() {
+            return self.send_within_deadline(config).await;
+        }
+
+        let key = coalesce::key(&self.path, &self.query, &self.headers);
+        match self.upstream.coalescer().join(key) {
+            Flight::Leader(leader) => {
+                let result = self.send_within_deadline(config).await;
+                leader.land(&result);
+                result
+            }
+            Flight::Follower(landing) => {
+                let landed = match self.deadline {
+                    Some(deadline) => {
+                        timeout_at(Instant::from_std(deadline.at), coalesce::follow(landing))
+                            .await
+                            .ok()
+                            .flatten()
+                    }
+                    None => coalesce::follow(landing).await,
+                };
+                match landed {
+                    Some(result) => {
+                        debug!(
+                            "[{}] Shared in-flight {} {}",
+                            self.request_id(),
+                            self.method,
+                            self.path
+                        );
+                        result.map(|response| ProxyResponse {
+                            cache_status: Some("coalesced"),
+                            ..response
+                        })
+                    }
+                    // Without the leader's result the request goes on its own,
+                    // which also reports a deadline that passed while waiting
+                    None => self.send_within_deadline(config).await,
+                }
+            }
+        }
+    }
+
+    /// Proxy the request, giving up with a 504 once the request deadline passes
+    async fn send_within_deadline(self, config: &AppConfig) -> ProxyResult {
+        let Some(deadline) = self.deadline else {
+            return self.dispatch_in_bulkhead(config).await;
+        };
+
+        let path = self.path.clone();
+        debug!(
+            "Proxying {} {} with a {:?} timeout",
+            self.method, path, deadline.timeout
+        );
+        match timeout_at(
+            Instant::from_std(deadline.at),
+            self.dispatch_in_bulkhead(config),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(_) => {
+                error!("Gateway deadline exceeded while proxying {}", path);
+                let err = ApiError::RequestTimeout("Gateway request deadline exceeded".into());
+                Err(error_response(
+                    config,
+                    err,
+                    format!("no response within {:?}", deadline.timeout),
+                ))
+            }
+        }
+    }
+
+    /// Dispatch while holding one of the upstream's bulkhead slots, so a
+    /// slow service can't tie up more than its share of the gateway
+    async fn dispatch_in_bulkhead(self, config: &AppConfig) -> ProxyResult {
+        let Ok(_permit) = self.upstream.acquire(config.bulkhead_queue_timeout).await else {
+            warn!(
+                "{} is at its concurrency limit, rejecting {} {}",
+                self.upstream.label, self.method, self.path
+            );
+            let err =
+                ApiError::ServiceUnavailable(format!("{} is at capacity", self.upstream.label));
+            return Err(error_response(
+                config,
+                err,
+                "no bulkhead slot became available",
+            ));
+        };
+
+        self.dispatch(config).await
+    }
+
+    /// Send the request to the next upstream replica and relay its response,
+    /// failing over to the fallback target when the primary is unreachable,
+    /// or answers with a 5xx and the request is safe to send twice
+    async fn dispatch(mut self, config: &AppConfig) -> ProxyResult {
+        if let Some(body) = &mut self.body {
+            transform_body(&config.body_transforms, &self.path, body);
+        }
+        self.path = rewrite_path(&config.path_rewrites, &self.path);
+        chaos::inject(config).await?;
+        self.attempt_timeout = Some(config.upstream_total_timeout).filter(|t| !t.is_zero());
+
+        let timings = config.log_upstream_timings.then(UpstreamTimings::default);
+        let client = self.upstream.client().clone();
+        if config.log_request_bodies
+            && let Some(body) = &self.body
+        {
+            debug!(
+                "{} {} body: {}",
+                self.method,
+                self.path,
+                redact(body, &config.redact_fields)
+            );
+        }
+
+        // Decided once, so retries stay on the same side of the split
+        let key = self
+            .canary_key
+            .as_deref()
+            .unwrap_or_else(|| self.request_id());
+        let canary = self.upstream.canary_for(key);
+        if let Some(canary) = canary {
+            info!(
+                "[{}] Routing {} {} to {} canary {}",
+                self.request_id(),
+                self.method,
+                self.path,
+                self.upstream.label,
+                canary
+            );
+        }
+        let target = || canary.unwrap_or_else(|| self.upstream.next_target());
+
+        let started = Instant::now();
+        // Every replica refusing calls skips straight to the fallback
+        let short_circuit = canary.is_none() && self.upstream.breaker_state() == BreakerState::Open;
+        let primary = match short_circuit {
+            true => None,
+            false => Some(
+                self.attempt_replicas(config, &client, timings.as_ref(), target)
+                    .await,
+            ),
+        };
+
+        let (url, result, retries, via_fallback) = match (primary, self.upstream.fallback()) {
+            (Some((url, result, retries)), Some(fallback))
+                if has_failed(&result, self.is_retry_safe()) =>
+            {
+                let fallback_url = format!("{}{}", fallback, self.path);
+                warn!(
+                    "{} primary {} failed, failing over to {}",
+                    self.upstream.label, url, fallback_url
+                );
+                let result = self.attempt(&client, &fallback_url, timings.as_ref()).await;
+                (fallback_url, result, retries, true)
+            }
+            (Some((url, result, retries)), _) => (url, result, retries, false),
+            (None, Some(fallback)) => {
+                let fallback_url = format!("{}{}", fallback, self.path);
+                warn!(
+                    "{} breaker is open, sending {} {} to {}",
+                    self.upstream.label, self.method, self.path, fallback_url
+                );
+                let result = self.attempt(&client, &fallback_url, timings.as_ref()).await;
+                (fallback_url, result, 0, true)
+            }
+            (None, None) => return Err(self.breaker_open(config)),
+        };
+
+        let response = match result {
+            Ok(response) => response,
+            Err(e) if e.is_timeout() => {
+                error!("Timed out proxying request to {}: {:?}", url, e);
+                let err = ApiError::RequestTimeout(format!(
+                    "{} did not answer in time",
+                    self.upstream.label
+                ));
+                return Err(error_response(config, err, e));
+            }
+            Err(e) => {
+                error!("Error proxying request to {}: {:?}", url, e);
+                let err =
+                    ApiError::ServiceUnavailable(format!("{} unavailable", self.upstream.label));
+                return Err(error_response(config, err, e));
+            }
+        };
+
+        let upstream_status = response.status().as_u16();
+        let bytes = self.read_upstream_body(config, &url, response).await?;
+        let upstream_time = started.elapsed();
+
+        if let Some(timings) = &timings {
+            debug!(
+                "[{}] {} {} timings: {}",
+                self.request_id(),
+                self.method,
+                url,
+                timings.summary()
+            );
+        }
+
+        let (status, body) = relay(config, self.upstream.label, upstream_status, &bytes)?;
+        if status.code != upstream_status {
+            debug!(
+                "[{}] Relaying {} status {} as {}",
+                self.request_id(),
+                self.upstream.label,
+                upstream_status,
+                status.code
+            );
+        }
+
+        Ok(ProxyResponse {
+            status,
+            body,
+            headers: response_headers(via_fallback),
+            upstream_time: Some(upstream_time),
+            retries,
+            cache_status: None,
+        })
+    }
+
+    /// Call the replicas `target` picks, retrying as configured, and return
+    /// the last URL tried, its outcome and the number of retries
+    async fn attempt_replicas<'t>(
+        &self,
+        config: &AppConfig,
+        client: &reqwest::Client,
+        timings: Option<&UpstreamTimings>,
+        target: impl Fn() -> &'t str,
+    ) -> (String, Result<reqwest::Response, reqwest::Error>, u32) {
+        let mut url = format!("{}{}", target(), self.path);
+        let mut result = self.attempt(client, &url, timings).await;
+
+        let budget = self.upstream.retry_budget();
+        budget.deposit();
+        let mut retries = 0;
+        while retries < config.proxy_max_retries
+            && self.is_retry_safe()
+            && should_retry(&result, &config.proxy_retry_statuses)
+        {
+            if !budget.withdraw() {
+                warn!(
+                    "{} {} {}, retry budget exhausted, not retrying",
+                    self.upstream.label,
+                    url,
+                    outcome(&result)
+                );
+                metrics::counter!("api_retry_budget_exhausted_total", "service" => self.upstream.name)
+                    .increment(1);
+                break;
+            }
+            let backoff = config.proxy_retry_backoff * 2u32.saturating_pow(retries);
+            retries += 1;
+            warn!(
+                "{} {} {}, retry {}/{} in {:?}",
+                self.upstream.label,
+                url,
+                outcome(&result),
+                retries,
+                config.proxy_max_retries,
+                backoff
+            );
+            tokio::time::sleep(backoff).await;
+
+            url = format!("{}{}", target(), self.path);
+            result = self.attempt(client, &url, timings).await;
+        }
+
+        (url, result, retries)
+    }
+
+    /// Read the upstream body within `MAX_UPSTREAM_RESPONSE_BYTES`
+    async fn read_upstream_body(
+        &self,
+        config: &AppConfig,
+        url: &str,
+        response: reqwest::Response,
+    ) -> Result<Vec<u8>, ErrorResponder> {
+        match read_body(response, config.max_upstream_response_bytes).await {
+            Ok(bytes) => Ok(bytes),
+            Err(BodyError::TooLarge) => {
+                error!(
+                    "Response from {} exceeded {} bytes, aborting",
+                    url, config.max_upstream_response_bytes
+                );
+                let err = ApiError::BadGateway(format!(
+                    "{} response exceeded the size limit",
+                    self.upstream.label
+                ));
+                Err(error_response(
+                    config,
+                    err,
+                    format!("limit is {} bytes", config.max_upstream_response_bytes),
+                ))
+            }
+            Err(BodyError::Read(e)) if e.is_timeout() => {
+                error!("Timed out reading response from {}: {:?}", url, e);
+                let err = ApiError::RequestTimeout(format!(
+                    "{} response did not complete in time",
+                    self.upstream.label
+                ));
+                Err(error_response(config, err, e))
+            }
+            Err(BodyError::Read(e)) => {
+                error!("Error reading response from {}: {:?}", url, e);
+                let err =
+                    ApiError::BadGateway(format!("Error reading {} response", self.upstream.label));
+                Err(error_response(config, err, e))
+            }
+        }
+    }
+
+    async fn attempt(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+        timings: Option<&UpstreamTimings>,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        debug!("Proxying {} {} to {}", self.method, self.path, url);
+
+        let mut request = self.outbound(client, url);
+        if let Some(body) = &self.body {
+            request = request.json(body);
+        }
+        if let Some(timeout) = self.attempt_timeout {
+            request = request.timeout(timeout);
+        }
+
+        let result = match timings {
+            Some(timings) => {
+                timings.begin();
+                timings.scope(self.send_counted(url, request)).await
+            }
+            None => self.send_counted(url, request).await,
+        };
+
+        if let Some(timings) = timings {
+            match &result {
+                Ok(_) => timings.first_byte(),
+                Err(_) => debug!(
+                    "[{}] {} {} failed, timings: {}",
+                    self.request_id(),
+                    self.method,
+                    url,
+                    timings.summary()
+                ),
+            }
+        }
+        result
+    }
+
+    /// The request to `url` with the forwarded headers and query, but no body
+    fn outbound(&self, client: &reqwest::Client, url: &str) -> reqwest::RequestBuilder {
+        let mut headers = self.headers.clone();
+        if self.upstream.basic_auth().is_some() {
+            headers.remove(AUTHORIZATION);
+        }
+        let mut request = client.request(self.method.clone(), url).headers(headers);
+        if let Some(host) = self.upstream.host_header() {
+            request = request.header(reqwest::header::HOST, host);
+        }
+        if let Some(auth) = self.upstream.basic_auth() {
+            request = request.basic_auth(&auth.username, Some(&auth.password));
+        }
+        if !self.query.is_empty() {
+            request = request.query(&self.query);
+        }
+        request
+    }
+
+    /// Send the request to `url`, counting its outcome against the upstream
+    async fn send_counted(
+        &self,
+        url: &str,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let call = CountedCall::start(self.upstream.name);
+        let result = request.send().await;
+        call.finish(&result);
+        self.upstream.record(
+            url,
+            matches!(&result, Ok(response) if !response.status().is_server_error()),
+        );
+        result
+    }
+
+    /// The 503 for a request refused because every replica's breaker is
+    /// open and there is no fallback
+    fn breaker_open(&self, config: &AppConfig) -> ErrorResponder {
+        warn!(
+            "{} breaker is open, refusing {} {}",
+            self.upstream.label, self.method, self.path
+        );
+        metrics::counter!("api_breaker_rejections_total", "service" => self.upstream.name)
+            .increment(1);
+        let err = ApiError::ServiceUnavailable(format!("{} unavailable", self.upstream.label));
+        let response = error_response(config, err, "circuit breaker is open");
+        match self.upstream.retry_in() {
+            Some(cooldown) => response.retry_after(cooldown),
+            None => response,
+        }
+    }
+
+    /// Retrying can't duplicate side effects: the method is idempotent or
+    /// the request is protected by an idempotency key
+    fn is_retry_safe(&self) -> bool {
+        self.has_idempotency_key
+            || matches!(
+                self.method,
+                Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+            )
+    }
+
+    /// Gateway request ID, as forwarded upstream
+    fn request_id(&self) -> &str {
+        self.headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("-")
+    }
+}
+
+/// One upstream call, counted in `api_upstream_requests_total` by service,
+/// outcome and status. 4xx answers are the caller's doing and count as
+/// successes; a call dropped unfinished, as when the request deadline
+/// passes, counts as a timeout.
+struct CountedCall {
+    service: &'static str,
+    finished: bool,
+}
+
+impl CountedCall {
+    fn start(service: &'static str) -> Self {
+        Self {
+            service,
+            finished: false,
+        }
+    }
+
+    fn finish(mut self, result: &Result<reqwest::Response, reqwest::Error>) {
+        let (outcome, status) = match result {
+            Ok(response) if response.status().is_server_error() => {
+                ("http_error", response.status().as_u16().to_string())
+            }
+            Ok(response) => ("success", response.status().as_u16().to_string()),
+            Err(e) if e.is_timeout() => ("timeout", "none".to_string()),
+            Err(_) => ("connect_error", "none".to_string()),
+        };
+        self.count(outcome, status);
+    }
+
+    fn count(&mut self, outcome: &'static str, status: String) {
+        self.finished = true;
+        metrics::counter!(
+            "api_upstream_requests_total",
+            "service" => self.service,
+            "outcome" => outcome,
+            "status" => status
+        )
+        .increment(1);
+    }
+}
+
+impl Drop for CountedCall {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.count("timeout", "none".to_string());
+        }
+    }
+}
+
+/// How copying an upload to the upstream ended
+#[derive(PartialEq, Eq)]
+enum Pumped {
+    /// The whole body was sent, or the upstream stopped taking it
+    Done,
+    /// Reading the client's body failed; the upstream call fails with it
+    Failed,
+    /// The body outgrew the limit and was cut short
+    TooLarge,
+}
+
+/// Copy the client's body into `sender` in 64 KiB chunks, failing the
+/// upstream request once more than `limit` bytes were read
+async fn pump(data: Data<'_>, limit: u64, sender: mpsc::Sender<io::Result<Vec<u8>>>) -> Pumped {
+    let mut body = data.open(limit.saturating_add(1).bytes());
+    let mut sent = 0u64;
+    loop {
+        let mut chunk = vec![0; 64 * 1024];
+        let read = match body.read(&mut chunk).await {
+            Ok(0) => return Pumped::Done,
+            Ok(read) => read,
+            Err(e) => {
+                let _ = sender.send(Err(e)).await;
+                return Pumped::Failed;
+            }
+        };
+
+        sent += read as u64;
+        if sent > limit {
+            let _ = sender
+                .send(Err(io::Error::other("upload exceeds the size limit")))
+                .await;
+            return Pumped::TooLarge;
+        }
+        chunk.truncate(read);
+        if sender.send(Ok(chunk)).await.is_err() {
+            return Pumped::Done;
+        }
+    }
+}
+
+enum BodyError {
+    TooLarge,
+    Read(reqwest::Error),
+}
+
+/// Read an upstream body chunk by chunk, giving up as soon as it grows past
+/// `limit`. Chunks are already decompressed, so this also bounds how far a
+/// gzip bomb can inflate.
+async fn read_body(mut response: reqwest::Response, limit: usize) -> Result<Vec<u8>, BodyError> {
+    if response
+        .content_length()
+        .is_some_and(|length| length > limit as u64)
+    {
+        return Err(BodyError::TooLarge);
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(BodyError::Read)? {
+        if body.len() + chunk.len() > limit {
+            return Err(BodyError::TooLarge);
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body)
+}
+
+pub(crate) fn error_response(
+    config: &AppConfig,
+    err: ApiError,
+    cause: impl Display,
+) -> ErrorResponder {
+    err.into_error_response(config.is_development().then(|| cause.to_string()))
+}
+
+/// The status and JSON body the client gets for an upstream's `code` and
+/// `bytes`. Codes Rocket doesn't know become 500. An empty body is relayed
+/// as `null`, and an error body that isn't JSON is replaced by an error
+/// naming the upstream status; other bodies that aren't JSON become 500.
+pub(crate) fn relay(
+    config: &AppConfig,
+    label: &str,
+    code: u16,
+    bytes: &[u8],
+) -> Result<(Status, Value), ErrorResponder> {
+    let status = Status::from_code(code).unwrap_or(Status::InternalServerError);
+    let status = remap_status(&config.status_remaps, status);
+
+    let body = match serde_json::from_slice::<Value>(bytes) {
+        Ok(body) => body,
+        Err(_) if bytes.trim_ascii().is_empty() => Value::Null,
+        Err(_) if status.code >= 400 => {
+            debug!(
+                "{} answered {} with a non-JSON body: {}",
+                label,
+                code,
+                String::from_utf8_lossy(&bytes[..bytes.len().min(256)])
+            );
+            let error = ErrorResponse::new(status, format!("{} responded with {}", label, status));
+            return Ok((status, serde_json::to_value(error).unwrap_or(Value::Null)));
+        }
+        Err(e) => {
+            error!("Error parsing response from {}: {:?}", label, e);
+            let err = ApiError::InternalServerError("Error parsing response".into());
+            return Err(error_response(config, err, e));
+        }
+    };
+
+    let body = if config.wrap_upstream_errors && status.code >= 400 {
+        wrap_upstream_error(label, status, body)
+    } else {
+        body
+    };
+    Ok((status, body))
+}
+
+/// Connection failures and responses with a listed status are retried
+fn should_retry(result: &Result<reqwest::Response, reqwest::Error>, statuses: &[u16]) -> bool {
+    match result {
+        Ok(response) => statuses.contains(&response.status().as_u16()),
+        Err(_) => true,
+    }
+}
+
+/// Gateway headers added to a relayed response
+fn response_headers(via_fallback: bool) -> Vec<Header<'static>> {
+    let mut headers = Vec::new();
+    if via_fallback {
+        headers.push(Header::new(UPSTREAM_HEADER, "fallback"));
+    }
+    headers
+}
+
+/// Whether a call failed in a way the fallback target is tried for. A 5xx
+/// or a timeout may come after the primary acted on the request, so unless
+/// it is `retry_safe` only a refused connection counts.
+fn has_failed(result: &Result<reqwest::Response, reqwest::Error>, retry_safe: bool) -> bool {
+    match result {
+        Ok(response) => retry_safe && response.status().is_server_error(),
+        Err(e) => retry_safe || e.is_connect(),
+    }
+}
+
+fn outcome(result: &Result<reqwest::Response, reqwest::Error>) -> String {
+    match result {
+        Ok(response) => format!("answered {}", response.status()),
+        Err(e) => format!("failed: {}", e),
+    }
+}
+
+/// Percent-encode a value for use as a single upstream path segment
+pub fn path_segment(value: &str) -> String {
+    RawStr::new(value).percent_encode().to_string()
+}
+
+/// Wrap an upstream error body in the gateway's error envelope
+fn wrap_upstream_error(label: &str, status: Status, body: Value) -> Value {
+    let response = ErrorResponse {
+        upstream: Some(body),
+        ..ErrorResponse::new(status, format!("{} responded with {}", label, status))
+    };
+
+    serde_json::to_value(response).unwrap_or(Value::Null)
+}
@@ -0,0 +1,117 @@
+// src/services/ratelimit.rs
+use crate::services::store::Store;
+use log::warn;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Request allowance over a sliding window, e.g. 5 requests per 15 minutes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: u32,
+    pub window: Duration,
+    /// Let requests through unchecked while the store is unavailable,
+    /// instead of refusing them
+    pub fail_open: bool,
+}
+
+impl RateLimit {
+    /// A zero limit leaves the routes unlimited
+    pub fn is_enabled(&self) -> bool {
+        self.limit > 0
+    }
+}
+
+/// Sliding-window request counters per caller, kept in the configured store
+/// so replicas sharing a Redis store share their limits. Limits come with
+/// each hit, so a config reload applies to the next request.
+///
+/// Requests are counted in windows aligned to the epoch. A hit weighs the
+/// previous window's count by how much of it still overlaps the trailing
+/// window, so a burst straddling a window boundary is not let through twice.
+pub struct RateLimiter {
+    store: Arc<dyn Store>,
+}
+
+/// Why a hit was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Denied {
+    /// The limit is used up; one more request is allowed after this long
+    Limited(Duration),
+    /// The store is unavailable and the limit doesn't fail open
+    Unchecked,
+}
+
+impl RateLimiter {
+    pub fn new(store: Arc<dyn Store>) -> Self {
+        Self { store }
+    }
+
+    /// Count a request for `key`, returning the requests left in the
+    /// trailing window, or the time until one is allowed again when the
+    /// limit is already used up. While the store is unavailable callers are
+    /// refused, or let through when the limit fails open.
+    pub async fn hit(&self, key: &str, limit: RateLimit) -> Result<u32, Denied> {
+        let window = limit.window.as_millis().max(1) as u64;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let (index, elapsed) = (now / window, now % window);
+
+        // Kept through the next window, where it is the previous count
+        let counted = self
+            .store
+            .increment(&format!("ratelimit:{}:{}", key, index), limit.window * 2)
+            .await;
+        let previous = self
+            .store
+            .get(&format!("ratelimit:{}:{}", key, index.wrapping_sub(1)))
+            .await;
+        let (count, previous) = match (counted, previous) {
+            (Ok((count, _)), Ok(previous)) => (count, previous),
+            (Err(e), _) | (_, Err(e)) if limit.fail_open => {
+                warn!(
+                    "Rate limit for {} not checked, letting it through: {}",
+                    key, e
+                );
+                return Ok(limit.limit);
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                warn!("Rate limit for {} not checked, refusing it: {}", key, e);
+                return Err(Denied::Unchecked);
+            }
+        };
+        let previous = previous
+            .and_then(|value| String::from_utf8(value).ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(0);
+
+        let overlap = (window - elapsed) as f64 / window as f64;
+        let weighted = previous as f64 * overlap + count as f64;
+        if weighted <= limit.limit as f64 {
+            return Ok(limit.limit - weighted.ceil().min(limit.limit as f64) as u32);
+        }
+        Err(Denied::Limited(retry_in(
+            limit.limit,
+            previous,
+            count,
+            window,
+            elapsed,
+        )))
+    }
+}
+
+/// Time until the weighted count leaves room for one more request
+fn retry_in(limit: u32, previous: u64, count: u64, window: u64, elapsed: u64) -> Duration {
+    let (limit, previous, count, window) =
+        (limit as f64, previous as f64, count as f64, window as f64);
+    let left = window - elapsed as f64;
+    let wait = if count < limit && previous > 0.0 {
+        // The previous window's share decays within this one
+        left - (limit - count - 1.0).max(0.0) * window / previous
+    } else {
+        // This window's count has to decay in the next one
+        left + window * (1.0 - (limit - 1.0) / count)
+    };
+    Duration::from_millis(wait.max(1.0).ceil() as u64)
+}
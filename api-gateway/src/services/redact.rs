@@ -0,0 +1,26 @@
+// src/services/redact.rs
+use rocket::serde::json::Value;
+
+/// Replacement for the value of a sensitive field
+pub const REDACTED: &str = "***";
+
+/// Copy of a JSON value with every configured sensitive key masked,
+/// at any depth. Keys are matched case-insensitively.
+pub fn redact(value: &Value, fields: &[String]) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let value = if fields.iter().any(|f| f.eq_ignore_ascii_case(key)) {
+                        Value::String(REDACTED.into())
+                    } else {
+                        redact(value, fields)
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(|v| redact(v, fields)).collect()),
+        other => other.clone(),
+    }
+}
@@ -0,0 +1,42 @@
+// src/services/remap.rs
+use rocket::http::Status;
+use std::str::FromStr;
+
+/// Rule translating an upstream status before it reaches the client, e.g. `418=400`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusRemap {
+    pub from: u16,
+    pub to: Status,
+}
+
+impl FromStr for StatusRemap {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let (from, to) = rule
+            .split_once('=')
+            .ok_or_else(|| format!("invalid status remap '{}', expected <from>=<to>", rule))?;
+
+        let code = |value: &str| {
+            value
+                .trim()
+                .parse::<u16>()
+                .ok()
+                .and_then(Status::from_code)
+                .ok_or_else(|| format!("invalid status remap '{}', unknown status code", rule))
+        };
+
+        Ok(Self {
+            from: code(from)?.code,
+            to: code(to)?,
+        })
+    }
+}
+
+/// The status the client should see for an upstream status
+pub fn remap_status(rules: &[StatusRemap], status: Status) -> Status {
+    rules
+        .iter()
+        .find(|rule| rule.from == status.code)
+        .map_or(status, |rule| rule.to)
+}
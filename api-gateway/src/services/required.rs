@@ -0,0 +1,79 @@
+// src/services/required.rs
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// A header requests under `prefix` must carry, optionally with a dotted
+/// version of at least `min`: `/api/sales=X-Platform` or
+/// `/api/sales=X-App-Version>=2.3.0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequiredHeader {
+    pub prefix: String,
+    pub name: String,
+    pub min: Option<String>,
+}
+
+impl FromStr for RequiredHeader {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid required header '{}', expected <path>=<header>[>=<version>]",
+                rule
+            )
+        };
+        let (prefix, header) = rule.split_once('=').ok_or_else(invalid)?;
+        let (name, min) = match header.split_once(">=") {
+            Some((name, min)) => (name.trim(), Some(min.trim())),
+            None => (header.trim(), None),
+        };
+
+        let prefix = prefix.trim();
+        if !prefix.starts_with('/') || name.is_empty() {
+            return Err(invalid());
+        }
+        if let Some(min) = min {
+            version(min).ok_or_else(invalid)?;
+        }
+
+        Ok(Self {
+            prefix: prefix.to_string(),
+            name: name.to_string(),
+            min: min.map(str::to_string),
+        })
+    }
+}
+
+impl RequiredHeader {
+    /// Why a request with `value` for the header is refused, if it is
+    pub fn violation(&self, value: Option<&str>) -> Option<String> {
+        let Some(value) = value.map(str::trim).filter(|value| !value.is_empty()) else {
+            return Some(format!("Missing required header {}", self.name));
+        };
+        let min = self.min.as_deref()?;
+
+        let meets_min = version(value)
+            .zip(version(min))
+            .is_some_and(|(value, min)| compare(&value, &min) != Ordering::Less);
+        (!meets_min).then(|| format!("{} must be at least {}, got {}", self.name, min, value))
+    }
+}
+
+/// Numeric components of a dotted version such as `2.3.0`
+fn version(value: &str) -> Option<Vec<u64>> {
+    value
+        .split('.')
+        .map(|component| component.parse().ok())
+        .collect()
+}
+
+/// Compare versions component by component, missing components being 0
+fn compare(a: &[u64], b: &[u64]) -> Ordering {
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            let (a, b) = (a.get(i).unwrap_or(&0), b.get(i).unwrap_or(&0));
+            a.cmp(b)
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
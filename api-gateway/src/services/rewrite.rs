@@ -0,0 +1,51 @@
+// src/services/rewrite.rs
+use log::debug;
+use std::str::FromStr;
+
+/// Prefix rule repointing an upstream path, e.g. `/api/users/login=/v2/auth/login`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathRewrite {
+    pub from: String,
+    pub to: String,
+}
+
+impl FromStr for PathRewrite {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let (from, to) = rule
+            .split_once('=')
+            .ok_or_else(|| format!("invalid path rewrite '{}', expected <from>=<to>", rule))?;
+
+        let (from, to) = (from.trim(), to.trim());
+        if !from.starts_with('/') || !to.starts_with('/') {
+            return Err(format!(
+                "invalid path rewrite '{}', both paths must start with /",
+                rule
+            ));
+        }
+
+        Ok(Self {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+}
+
+/// Apply the rule with the longest matching prefix, keeping the rest of
+/// the path (and query string) intact
+pub fn rewrite_path(rules: &[PathRewrite], path: &str) -> String {
+    let rule = rules
+        .iter()
+        .filter(|rule| path.starts_with(&rule.from))
+        .max_by_key(|rule| rule.from.len());
+
+    match rule {
+        Some(rule) => {
+            let rewritten = format!("{}{}", rule.to, &path[rule.from.len()..]);
+            debug!("Rewrote upstream path {} to {}", path, rewritten);
+            rewritten
+        }
+        None => path.to_string(),
+    }
+}
@@ -0,0 +1,197 @@
+// src/services/selftest.rs
+use crate::config::app::AppConfig;
+use crate::config::live::LiveConfig;
+use crate::services::telemetry;
+use crate::services::upstream::Upstreams;
+use metrics_exporter_prometheus::PrometheusHandle;
+use rocket::futures::future::join_all;
+use rocket::{Build, Rocket};
+use std::fmt;
+
+/// Command-line flag running the self-test instead of serving traffic
+pub const SELF_TEST_FLAG: &str = "--self-test";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    /// Reported but not failing the self-test
+    Warn,
+    Fail,
+}
+
+#[derive(Debug)]
+pub struct Check {
+    pub name: String,
+    pub outcome: Outcome,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, outcome: Outcome, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            outcome,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Checks in the order they ran. The self-test passes when none failed.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.outcome != Outcome::Fail)
+    }
+
+    fn count(&self, outcome: Outcome) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.outcome == outcome)
+            .count()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.checks.iter().map(|check| check.name.len()).max();
+        for check in &self.checks {
+            let outcome = match check.outcome {
+                Outcome::Pass => "PASS",
+                Outcome::Warn => "WARN",
+                Outcome::Fail => "FAIL",
+            };
+            writeln!(
+                f,
+                "{}  {:width$}  {}",
+                outcome,
+                check.name,
+                check.detail,
+                width = width.unwrap_or(0)
+            )?;
+        }
+
+        let verdict = if self.passed() { "passed" } else { "FAILED" };
+        write!(
+            f,
+            "Self-test {}: {} passed, {} warned, {} failed",
+            verdict,
+            self.count(Outcome::Pass),
+            self.count(Outcome::Warn),
+            self.count(Outcome::Fail)
+        )
+    }
+}
+
+/// Whether `--self-test` was passed or `SELF_TEST` is set
+pub fn requested(config: &AppConfig, mut args: impl Iterator<Item = String>) -> bool {
+    config.self_test || args.any(|arg| arg == SELF_TEST_FLAG)
+}
+
+/// Ignite the gateway without listening, then check what it would serve
+/// with: the configuration, the metrics recorder, and every upstream. An
+/// upstream down fails the self-test the way it would fail readiness, when
+/// it is in `CRITICAL_SERVICES` or when no upstream is up.
+pub async fn run(rocket: Rocket<Build>) -> Report {
+    let mut report = Report::default();
+    let rocket = match rocket.ignite().await {
+        Ok(rocket) => {
+            report.checks.push(Check::new(
+                "boot",
+                Outcome::Pass,
+                "routes and fairings ignited",
+            ));
+            rocket
+        }
+        Err(e) => {
+            let detail = e.kind().to_string();
+            report
+                .checks
+                .push(Check::new("boot", Outcome::Fail, detail));
+            return report;
+        }
+    };
+
+    let (Some(live), Some(handle)) = (
+        rocket.state::<LiveConfig>(),
+        rocket.state::<PrometheusHandle>(),
+    ) else {
+        report.checks.push(Check::new(
+            "boot",
+            Outcome::Fail,
+            "gateway state is not managed",
+        ));
+        return report;
+    };
+    let snapshot = live.snapshot();
+
+    report.checks.push(configuration(&snapshot.config));
+    report.checks.push(recorder(handle));
+    report
+        .checks
+        .extend(backends(&snapshot.config, &snapshot.upstreams).await);
+    report
+}
+
+fn configuration(config: &AppConfig) -> Check {
+    let loaded = format!("loaded for {} on port {}", config.environment, config.port);
+    Check::new("configuration", Outcome::Pass, loaded)
+}
+
+fn recorder(handle: &PrometheusHandle) -> Check {
+    if telemetry::recorder_is_working(handle) {
+        Check::new(
+            "metrics",
+            Outcome::Pass,
+            "recorder renders recorded metrics",
+        )
+    } else {
+        Check::new(
+            "metrics",
+            Outcome::Fail,
+            "probe metric missing from the rendered metrics",
+        )
+    }
+}
+
+async fn backends(config: &AppConfig, upstreams: &Upstreams) -> Vec<Check> {
+    let all = upstreams.all();
+    let timeout = config.health_probe_timeout;
+    let up = join_all(all.iter().map(|upstream| upstream.probe(timeout))).await;
+
+    let mut checks: Vec<_> = all
+        .iter()
+        .zip(&up)
+        .map(|(upstream, up)| {
+            let name = format!("upstream {}", upstream.name);
+            let targets = upstream.targets().join(", ");
+            let critical = config
+                .critical_services
+                .iter()
+                .any(|critical| critical == upstream.name);
+            match (up, critical) {
+                (true, _) => Check::new(name, Outcome::Pass, format!("up at {}", targets)),
+                (false, true) => Check::new(
+                    name,
+                    Outcome::Fail,
+                    format!("critical service down at {}", targets),
+                ),
+                (false, false) => Check::new(name, Outcome::Warn, format!("down at {}", targets)),
+            }
+        })
+        .collect();
+
+    if !up.iter().any(|up| *up) {
+        checks.push(Check::new(
+            "upstreams",
+            Outcome::Fail,
+            format!("none of {} answered within {:?}", up.len(), timeout),
+        ));
+    }
+    checks
+}
@@ -0,0 +1,90 @@
+// src/services/stale.rs
+use crate::services::proxy::{ProxyResponse, ProxyResult};
+use dashmap::DashMap;
+use reqwest::header::{CACHE_CONTROL, HeaderMap};
+use rocket::http::Header;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Header marking a response served from the stale cache
+pub const CACHE_HEADER: &str = "X-Cache";
+
+/// Upper bound on responses kept per upstream
+const MAX_ENTRIES: usize = 10_000;
+
+/// Last successful GET responses of one upstream, served for up to
+/// `STALE_MAX_AGE` when the upstream fails. Every request still goes to
+/// the upstream first, so entries are refreshed as soon as it recovers.
+#[derive(Default)]
+pub struct StaleCache {
+    entries: DashMap<String, (ProxyResponse, Instant)>,
+}
+
+impl fmt::Debug for StaleCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaleCache")
+            .field("entries", &self.entries.len())
+            .finish()
+    }
+}
+
+impl StaleCache {
+    pub fn store(&self, key: String, response: &ProxyResponse, max_age: Duration) {
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries
+                .retain(|_, (_, stored)| stored.elapsed() <= max_age);
+            if self.entries.len() >= MAX_ENTRIES {
+                return;
+            }
+        }
+        self.entries.insert(key, (response.clone(), Instant::now()));
+    }
+
+    /// The response stored under `key`, when it is at most `max_age` old
+    pub fn stale(&self, key: &str, max_age: Duration) -> Option<ProxyResponse> {
+        let (response, age) = {
+            let entry = self.entries.get(key)?;
+            let (response, stored) = entry.value();
+            (response.clone(), stored.elapsed())
+        };
+        if age > max_age {
+            self.entries.remove(key);
+            return None;
+        }
+
+        let mut headers = response.headers;
+        headers.push(Header::new(CACHE_HEADER, "STALE"));
+        headers.push(Header::new("Age", age.as_secs().to_string()));
+        Some(ProxyResponse {
+            headers,
+            upstream_time: None,
+            retries: 0,
+            cache_status: Some("stale"),
+            ..response
+        })
+    }
+}
+
+/// Whether `result` is an answer worth keeping for when the upstream fails
+pub fn is_fresh(result: &ProxyResult) -> bool {
+    matches!(result, Ok(response) if response.status.class().is_success())
+}
+
+/// Whether `result` is an upstream or gateway failure a stale response may stand in for
+pub fn is_failure(result: &ProxyResult) -> bool {
+    match result {
+        Ok(response) => response.status.code >= 500,
+        Err(error) => error.0.code >= 500,
+    }
+}
+
+/// Whether the client sent `Cache-Control: no-cache`, asking for the
+/// upstream's answer even when that answer is an error
+pub fn is_refused(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-cache"))
+}
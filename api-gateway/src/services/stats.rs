@@ -0,0 +1,102 @@
+// src/services/stats.rs
+use rocket::serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Length of the rolling window, in one-second buckets
+const WINDOW_SECONDS: usize = 60;
+
+#[derive(Clone, Copy, Default)]
+struct Bucket {
+    second: u64,
+    requests: u64,
+    errors: u64,
+    latency_micros: u64,
+}
+
+/// Rolling in-memory request statistics for quick operational snapshots
+pub struct RequestStats {
+    started: Instant,
+    total_requests: AtomicU64,
+    buckets: Mutex<[Bucket; WINDOW_SECONDS]>,
+}
+
+/// Compact snapshot of recent traffic reported by the admin health endpoint
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RequestSummary {
+    pub total_requests: u64,
+    pub window_seconds: u64,
+    pub window_requests: u64,
+    pub error_rate: f64,
+    pub avg_latency_ms: f64,
+}
+
+impl RequestStats {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            total_requests: AtomicU64::new(0),
+            buckets: Mutex::new([Bucket::default(); WINDOW_SECONDS]),
+        }
+    }
+
+    /// Record a completed request; 5xx responses count as errors
+    pub fn record(&self, status: u16, latency: Duration) {
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+
+        let second = self.started.elapsed().as_secs();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = &mut buckets[second as usize % WINDOW_SECONDS];
+
+        if bucket.second != second {
+            *bucket = Bucket {
+                second,
+                ..Bucket::default()
+            };
+        }
+
+        bucket.requests += 1;
+        bucket.latency_micros += latency.as_micros() as u64;
+        if status >= 500 {
+            bucket.errors += 1;
+        }
+    }
+
+    /// Summarize the requests seen during the last minute
+    pub fn summary(&self) -> RequestSummary {
+        let now = self.started.elapsed().as_secs();
+        let buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        let (requests, errors, latency_micros) = buckets
+            .iter()
+            .filter(|b| b.requests > 0 && now - b.second < WINDOW_SECONDS as u64)
+            .fold((0, 0, 0), |(r, e, l), b| {
+                (r + b.requests, e + b.errors, l + b.latency_micros)
+            });
+
+        let (error_rate, avg_latency_ms) = if requests == 0 {
+            (0.0, 0.0)
+        } else {
+            (
+                errors as f64 / requests as f64,
+                latency_micros as f64 / requests as f64 / 1000.0,
+            )
+        };
+
+        RequestSummary {
+            total_requests: self.total_requests.load(Ordering::Relaxed),
+            window_seconds: WINDOW_SECONDS as u64,
+            window_requests: requests,
+            error_rate,
+            avg_latency_ms,
+        }
+    }
+}
+
+impl Default for RequestStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
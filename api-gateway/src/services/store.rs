@@ -0,0 +1,311 @@
+// src/services/store.rs
+use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use redis::aio::ConnectionManager;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::OnceCell;
+
+/// Prefix of every key the gateway writes to Redis
+const REDIS_KEY_PREFIX: &str = "api-gateway:";
+
+/// How long a Redis command may take before the caller gives up on it
+const REDIS_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Wait after a failed connect before the next attempt, doubling with each
+/// failure up to `MAX_RECONNECT_DELAY`, so an outage doesn't add
+/// `REDIS_TIMEOUT` to every request
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Error, Debug)]
+#[error("Store unavailable: {0}")]
+pub struct StoreError(String);
+
+/// Where shared gateway state (rate limits, idempotency records) lives
+#[derive(Debug, Clone)]
+pub enum StoreBackend {
+    /// In this process only, so each replica keeps its own state
+    Memory,
+    /// Shared by every replica pointed at the same server
+    Redis(redis::Client),
+}
+
+impl StoreBackend {
+    /// `STORE_BACKEND` value with the Redis URL it requires
+    pub fn parse(backend: &str, redis_url: Option<&str>) -> Result<Self, String> {
+        match backend.to_ascii_lowercase().as_str() {
+            "memory" => Ok(Self::Memory),
+            "redis" => {
+                let url = redis_url.ok_or("REDIS_URL is required by the redis store")?;
+                redis::Client::open(url)
+                    .map(Self::Redis)
+                    .map_err(|e| format!("invalid REDIS_URL: {}", e))
+            }
+            other => Err(format!(
+                "unknown store backend '{}', expected memory or redis",
+                other
+            )),
+        }
+    }
+
+    pub fn open(&self) -> Arc<dyn Store> {
+        match self {
+            Self::Memory => Arc::new(MemoryStore::default()),
+            Self::Redis(client) => Arc::new(RedisStore::new(client.clone())),
+        }
+    }
+}
+
+/// Two backends are equal when they reach the same store
+impl PartialEq for StoreBackend {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Memory, Self::Memory) => true,
+            (Self::Redis(a), Self::Redis(b)) => {
+                let (a, b) = (a.get_connection_info(), b.get_connection_info());
+                a.addr == b.addr
+                    && a.redis.db == b.redis.db
+                    && a.redis.username == b.redis.username
+                    && a.redis.password == b.redis.password
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Expiring key-value storage behind the rate limiter and idempotency store
+#[rocket::async_trait]
+pub trait Store: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StoreError>;
+
+    async fn set(&self, key: &str, value: &[u8], ttl: Duration) -> Result<(), StoreError>;
+
+    /// Set `key` only when it is absent, returning whether it was set
+    async fn set_nx(&self, key: &str, value: &[u8], ttl: Duration) -> Result<bool, StoreError>;
+
+    async fn delete(&self, key: &str) -> Result<(), StoreError>;
+
+    /// Add one to the counter at `key`, opening a `window` long expiry on the
+    /// first increment. Returns the new count and the time left in the window.
+    async fn increment(&self, key: &str, window: Duration) -> Result<(u64, Duration), StoreError>;
+}
+
+/// Writes between two sweeps of expired entries from a `MemoryStore`
+const PURGE_EVERY: u64 = 1024;
+
+/// Store kept in process memory. Expired entries are ignored as soon as
+/// they expire, and swept out every `PURGE_EVERY` writes.
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: DashMap<String, (Vec<u8>, Instant)>,
+    writes: AtomicU64,
+}
+
+impl MemoryStore {
+    /// Count a write, sweeping out expired entries when one is due
+    fn purge(&self, now: Instant) {
+        if self.writes.fetch_add(1, Ordering::Relaxed) % PURGE_EVERY == PURGE_EVERY - 1 {
+            self.entries.retain(|_, (_, expires)| *expires > now);
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl Store for MemoryStore {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StoreError> {
+        let now = Instant::now();
+        Ok(self
+            .entries
+            .get(key)
+            .filter(|entry| entry.1 > now)
+            .map(|entry| entry.0.clone()))
+    }
+
+    async fn set(&self, key: &str, value: &[u8], ttl: Duration) -> Result<(), StoreError> {
+        let now = Instant::now();
+        self.purge(now);
+        self.entries
+            .insert(key.to_string(), (value.to_vec(), now + ttl));
+        Ok(())
+    }
+
+    async fn set_nx(&self, key: &str, value: &[u8], ttl: Duration) -> Result<bool, StoreError> {
+        let now = Instant::now();
+        self.purge(now);
+        match self.entries.entry(key.to_string()) {
+            Entry::Occupied(entry) if entry.get().1 > now => Ok(false),
+            // Absent, or expired and not swept out yet
+            entry => {
+                entry.insert((value.to_vec(), now + ttl));
+                Ok(true)
+            }
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StoreError> {
+        self.entries.remove(key);
+        Ok(())
+    }
+
+    async fn increment(&self, key: &str, window: Duration) -> Result<(u64, Duration), StoreError> {
+        let now = Instant::now();
+        self.purge(now);
+        let mut entry = self
+            .entries
+            .entry(key.to_string())
+            .or_insert_with(|| (b"0".to_vec(), now + window));
+
+        let (value, expires) = entry.value_mut();
+        if *expires <= now {
+            *value = b"0".to_vec();
+            *expires = now + window;
+        }
+        let count = std::str::from_utf8(value)
+            .ok()
+            .and_then(|count| count.parse::<u64>().ok())
+            .unwrap_or(0)
+            + 1;
+        *value = count.to_string().into_bytes();
+        Ok((count, *expires - now))
+    }
+}
+
+/// Store on a Redis server, connected on first use and reconnected as needed
+pub struct RedisStore {
+    client: redis::Client,
+    connection: OnceCell<ConnectionManager>,
+    /// When the next connect may be tried, and the delay after that one
+    /// fails, while no connection could be made
+    backoff: Mutex<Option<(Instant, Duration)>>,
+    increment: redis::Script,
+}
+
+impl RedisStore {
+    pub fn new(client: redis::Client) -> Self {
+        Self {
+            client,
+            connection: OnceCell::new(),
+            backoff: Mutex::new(None),
+            // INCR and PEXPIRE in one step, so a counter can't be left without expiry
+            increment: redis::Script::new(
+                r"
+                local count = redis.call('INCR', KEYS[1])
+                if count == 1 then redis.call('PEXPIRE', KEYS[1], ARGV[1]) end
+                return {count, redis.call('PTTL', KEYS[1])}
+                ",
+            ),
+        }
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager, StoreError> {
+        if let Some(connection) = self.connection.get() {
+            return Ok(connection.clone());
+        }
+        let delay = match *self.backoff.lock().unwrap() {
+            Some((next, _)) if Instant::now() < next => {
+                return Err(StoreError("Redis unreachable, waiting to reconnect".into()));
+            }
+            Some((_, delay)) => delay,
+            None => MIN_RECONNECT_DELAY,
+        };
+
+        let connected = self
+            .connection
+            .get_or_try_init(|| bounded(ConnectionManager::new(self.client.clone())))
+            .await;
+        let mut backoff = self.backoff.lock().unwrap();
+        match connected {
+            Ok(connection) => {
+                *backoff = None;
+                Ok(connection.clone())
+            }
+            Err(e) => {
+                *backoff = Some((Instant::now() + delay, (delay * 2).min(MAX_RECONNECT_DELAY)));
+                Err(e)
+            }
+        }
+    }
+}
+
+fn prefixed(key: &str) -> String {
+    format!("{}{}", REDIS_KEY_PREFIX, key)
+}
+
+fn millis(duration: Duration) -> u64 {
+    (duration.as_millis() as u64).max(1)
+}
+
+/// Run a Redis call within `REDIS_TIMEOUT`
+async fn bounded<T>(call: impl Future<Output = redis::RedisResult<T>>) -> Result<T, StoreError> {
+    match tokio::time::timeout(REDIS_TIMEOUT, call).await {
+        Ok(result) => result.map_err(|e| StoreError(e.to_string())),
+        Err(_) => Err(StoreError("Redis call timed out".into())),
+    }
+}
+
+#[rocket::async_trait]
+impl Store for RedisStore {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StoreError> {
+        let mut connection = self.connection().await?;
+        bounded(
+            redis::cmd("GET")
+                .arg(prefixed(key))
+                .query_async(&mut connection),
+        )
+        .await
+    }
+
+    async fn set(&self, key: &str, value: &[u8], ttl: Duration) -> Result<(), StoreError> {
+        let mut connection = self.connection().await?;
+        bounded(
+            redis::cmd("SET")
+                .arg(prefixed(key))
+                .arg(value)
+                .arg("PX")
+                .arg(millis(ttl))
+                .query_async(&mut connection),
+        )
+        .await
+    }
+
+    async fn set_nx(&self, key: &str, value: &[u8], ttl: Duration) -> Result<bool, StoreError> {
+        let mut connection = self.connection().await?;
+        let reply: Option<String> = bounded(
+            redis::cmd("SET")
+                .arg(prefixed(key))
+                .arg(value)
+                .arg("NX")
+                .arg("PX")
+                .arg(millis(ttl))
+                .query_async(&mut connection),
+        )
+        .await?;
+        Ok(reply.is_some())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StoreError> {
+        let mut connection = self.connection().await?;
+        bounded(
+            redis::cmd("DEL")
+                .arg(prefixed(key))
+                .query_async(&mut connection),
+        )
+        .await
+    }
+
+    async fn increment(&self, key: &str, window: Duration) -> Result<(u64, Duration), StoreError> {
+        let mut connection = self.connection().await?;
+        let (count, left): (u64, i64) = bounded(
+            self.increment
+                .key(prefixed(key))
+                .arg(millis(window))
+                .invoke_async(&mut connection),
+        )
+        .await?;
+        Ok((count, Duration::from_millis(left.max(0) as u64)))
+    }
+}
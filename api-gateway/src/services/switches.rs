@@ -0,0 +1,56 @@
+// src/services/switches.rs
+use dashmap::DashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Proxied route groups, named after the path segment they're mounted
+/// under (`/api/<group>`)
+pub const ROUTE_GROUPS: &[&str] = &[
+    "users",
+    "payments",
+    "sales",
+    "purchasing",
+    "inventory",
+    "customers",
+    "notifications",
+];
+
+/// Runtime switches deciding whether proxied routes are served
+pub struct RouteSwitches {
+    maintenance: AtomicBool,
+    disabled: DashSet<&'static str>,
+}
+
+impl RouteSwitches {
+    pub fn new(maintenance: bool) -> Self {
+        Self {
+            maintenance: AtomicBool::new(maintenance),
+            disabled: DashSet::new(),
+        }
+    }
+
+    pub fn maintenance(&self) -> bool {
+        self.maintenance.load(Ordering::Relaxed)
+    }
+
+    pub fn set_maintenance(&self, enabled: bool) {
+        self.maintenance.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self, group: &str) -> bool {
+        !self.disabled.contains(group)
+    }
+
+    /// Enable or disable a route group, returning false for unknown groups
+    pub fn set_enabled(&self, group: &str, enabled: bool) -> bool {
+        let Some(group) = ROUTE_GROUPS.iter().find(|g| **g == group) else {
+            return false;
+        };
+
+        if enabled {
+            self.disabled.remove(group);
+        } else {
+            self.disabled.insert(group);
+        }
+        true
+    }
+}
@@ -0,0 +1,253 @@
+// src/services/telemetry.rs
+use crate::config::app::AppConfig;
+use env_logger::fmt::Formatter;
+use log::Record;
+use log::kv::{self, Key, Value, VisitSource};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use serde_json::Map;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Gauge the health check sets to find out whether metrics are recorded
+pub const PROBE_METRIC: &str = "api_health_metrics_probe";
+
+/// Record a probe metric and check that `handle` renders it, which fails when
+/// the recorder behind the handle is not the one receiving metrics
+pub fn recorder_is_working(handle: &PrometheusHandle) -> bool {
+    metrics::gauge!(PROBE_METRIC).set(1.0);
+    let rendered = handle.render();
+    rendered.lines().any(|line| line.starts_with(PROBE_METRIC))
+}
+
+/// Log target of the one entry written per completed request
+pub const ACCESS_LOG_TARGET: &str = "access";
+
+/// How log records are written to stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// env_logger's human-readable lines
+    Text,
+    /// One JSON object per record, with any structured fields alongside the
+    /// message
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(format!(
+                "unknown log format '{}', expected text or json",
+                other
+            )),
+        }
+    }
+}
+
+/// How the `RequestId` fairing formats the IDs it generates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestIdFormat {
+    /// Random (v4) UUID, e.g. `0b6f4c1e-8d8a-4f57-9f3b-2c1d5e6a7b8c`
+    Uuid,
+    /// 26-character ULID, which sorts by creation time
+    Ulid,
+    /// 16 lowercase hex digits of randomness
+    Hex16,
+}
+
+impl FromStr for RequestIdFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "uuid" => Ok(Self::Uuid),
+            "ulid" => Ok(Self::Ulid),
+            "hex16" => Ok(Self::Hex16),
+            other => Err(format!(
+                "unknown request ID format '{}', expected uuid, ulid or hex16",
+                other
+            )),
+        }
+    }
+}
+
+impl RequestIdFormat {
+    pub fn generate(self) -> String {
+        match self {
+            Self::Uuid => Uuid::new_v4().to_string(),
+            Self::Ulid => {
+                let millis = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_millis())
+                    .unwrap_or(0);
+                ulid(millis, rand::random())
+            }
+            Self::Hex16 => format!("{:016x}", rand::random::<u64>()),
+        }
+    }
+}
+
+/// Crockford's base32 alphabet, which ULIDs are written in
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// A 48-bit millisecond timestamp followed by 80 bits of `random`, as 26
+/// base32 digits
+pub fn ulid(millis: u128, random: u128) -> String {
+    let value = ((millis & 0xFFFF_FFFF_FFFF) << 80) | (random & ((1 << 80) - 1));
+    (0..26)
+        .rev()
+        .map(|digit| CROCKFORD[(value >> (digit * 5)) as usize & 31] as char)
+        .collect()
+}
+
+/// env_logger format writing `record` as a JSON line
+pub fn json_record(buf: &mut Formatter, record: &Record<'_>) -> io::Result<()> {
+    let mut entry = Map::new();
+    entry.insert("ts".into(), buf.timestamp_millis().to_string().into());
+    entry.insert("level".into(), record.level().as_str().into());
+    entry.insert("target".into(), record.target().into());
+    entry.insert("message".into(), record.args().to_string().into());
+    let _ = record.key_values().visit(&mut Fields(&mut entry));
+    writeln!(buf, "{}", serde_json::Value::Object(entry))
+}
+
+/// Copies a record's structured fields into its JSON entry
+struct Fields<'a>(&'a mut Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let value = serde_json::to_value(value).unwrap_or_default();
+        self.0.insert(key.as_str().to_string(), value);
+        Ok(())
+    }
+}
+
+/// Where metrics emitted through the `metrics` macros are sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsBackend {
+    /// Scraped from /api/metrics
+    Prometheus,
+    /// Pushed to a StatsD/DogStatsD agent, while /api/metrics keeps serving
+    /// the same metrics for local inspection
+    Statsd,
+}
+
+impl FromStr for MetricsBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "prometheus" => Ok(Self::Prometheus),
+            "statsd" => Ok(Self::Statsd),
+            other => Err(format!(
+                "unknown metrics backend '{}', expected prometheus or statsd",
+                other
+            )),
+        }
+    }
+}
+
+/// Bucket for header values that aren't on the allowlist
+pub const OTHER_LABEL_VALUE: &str = "other";
+
+/// Upper bound on allowlisted values, keeping the label's cardinality small
+pub const MAX_LABEL_VALUES: usize = 32;
+
+/// A request metric label read from an inbound header. Only allowlisted
+/// values are emitted; anything else, including a missing header, is
+/// reported as `other`.
+#[derive(Debug, Clone)]
+pub struct HeaderLabel {
+    pub name: String,
+    pub header: String,
+    pub allowed: Vec<String>,
+}
+
+impl HeaderLabel {
+    /// Label value for a raw header value
+    pub fn value_for(&self, header: Option<&str>) -> &str {
+        header
+            .map(str::trim)
+            .and_then(|value| {
+                self.allowed
+                    .iter()
+                    .find(|allowed| allowed.eq_ignore_ascii_case(value))
+            })
+            .map(String::as_str)
+            .unwrap_or(OTHER_LABEL_VALUE)
+    }
+}
+
+/// Label added to every metric, so one backend can hold several deployments
+pub const ENVIRONMENT_LABEL: &str = "environment";
+
+/// Prometheus exporter with the labels every metric carries
+pub fn prometheus_builder(config: &AppConfig) -> PrometheusBuilder {
+    PrometheusBuilder::new().add_global_label(ENVIRONMENT_LABEL, &config.environment)
+}
+
+/// Install the global metrics recorder for the configured backend,
+/// returning the handle used to render /api/metrics
+pub fn install_recorder(config: &AppConfig) -> Result<PrometheusHandle, String> {
+    match config.metrics_backend {
+        MetricsBackend::Prometheus => prometheus_builder(config)
+            .install_recorder()
+            .map_err(|e| e.to_string()),
+        MetricsBackend::Statsd => install_statsd_recorder(config),
+    }
+}
+
+#[cfg(feature = "statsd")]
+fn install_statsd_recorder(config: &AppConfig) -> Result<PrometheusHandle, String> {
+    use log::info;
+    use metrics_util::layers::FanoutBuilder;
+    use std::time::Duration;
+
+    let prometheus = prometheus_builder(config).build_recorder();
+    let handle = prometheus.handle();
+    let statsd = statsd_recorder(config)?;
+
+    let fanout = FanoutBuilder::default()
+        .add_recorder(prometheus)
+        .add_recorder(statsd)
+        .build();
+
+    metrics::set_global_recorder(fanout).map_err(|e| e.to_string())?;
+
+    // install_recorder() runs Prometheus upkeep itself; a built recorder doesn't
+    let upkeep_handle = handle.clone();
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(Duration::from_secs(5));
+            upkeep_handle.run_upkeep();
+        }
+    });
+
+    info!(
+        "Sending metrics to StatsD at {}:{}",
+        config.statsd_host, config.statsd_port
+    );
+    Ok(handle)
+}
+
+/// StatsD recorder sending to the configured agent, tagged like the
+/// Prometheus exporter
+#[cfg(feature = "statsd")]
+pub fn statsd_recorder(
+    config: &AppConfig,
+) -> Result<metrics_exporter_statsd::StatsdRecorder, String> {
+    metrics_exporter_statsd::StatsdBuilder::from(config.statsd_host.as_str(), config.statsd_port)
+        .with_default_tag(ENVIRONMENT_LABEL, &config.environment)
+        .build(Some(&config.statsd_prefix))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "statsd"))]
+fn install_statsd_recorder(_config: &AppConfig) -> Result<PrometheusHandle, String> {
+    Err("METRICS_BACKEND=statsd requires building with the `statsd` feature".into())
+}
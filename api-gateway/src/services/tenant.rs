@@ -0,0 +1,74 @@
+// src/services/tenant.rs
+use crate::config::app::AppConfig;
+use rocket::Request;
+use std::str::FromStr;
+
+/// Header carrying the tenant, read from clients and sent to every upstream
+pub const TENANT_HEADER: &str = "X-Tenant-Id";
+
+/// Longest tenant ID accepted
+const MAX_TENANT_LEN: usize = 64;
+
+/// Where the tenant of a request is read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TenantSource {
+    /// The `X-Tenant-Id` request header
+    Header,
+    /// The label in front of `TENANT_DOMAIN` in the Host header, e.g. `acme`
+    /// in `acme.api.example.com`
+    Subdomain,
+}
+
+impl FromStr for TenantSource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "header" => Ok(Self::Header),
+            "subdomain" => Ok(Self::Subdomain),
+            other => Err(format!(
+                "unknown tenant source '{}', expected header or subdomain",
+                other
+            )),
+        }
+    }
+}
+
+/// Tenant resolved for the request by the `Tenants` fairing
+pub struct Tenant(pub Option<String>);
+
+/// The request's tenant, when one was resolved
+pub fn current<'r>(request: &'r Request<'_>) -> Option<&'r str> {
+    request.local_cache(|| Tenant(None)).0.as_deref()
+}
+
+/// The tenant from the first of the configured sources that names one
+pub fn resolve(request: &Request<'_>, config: &AppConfig) -> Option<String> {
+    config.tenant_sources.iter().find_map(|source| {
+        let tenant = match source {
+            TenantSource::Header => request.headers().get_one(TENANT_HEADER)?,
+            TenantSource::Subdomain => {
+                let domain = config.tenant_domain.as_deref()?;
+                let host = request.host()?.domain().as_str();
+                subdomain(host, domain)?
+            }
+        };
+        is_valid(tenant).then(|| tenant.to_ascii_lowercase())
+    })
+}
+
+/// The single label `host` has in front of `domain`
+fn subdomain<'h>(host: &'h str, domain: &str) -> Option<&'h str> {
+    let prefix = host.len().checked_sub(domain.len() + 1)?;
+    let (label, rest) = host.split_at(prefix);
+    let matches = rest.starts_with('.') && rest[1..].eq_ignore_ascii_case(domain);
+    (matches && !label.contains('.')).then_some(label)
+}
+
+fn is_valid(tenant: &str) -> bool {
+    !tenant.is_empty()
+        && tenant.len() <= MAX_TENANT_LEN
+        && tenant
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_')
+}
@@ -0,0 +1,47 @@
+// src/services/timeouts.rs
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Timeout override for gateway paths under a prefix, e.g. `/api/users/login=5000`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteTimeout {
+    pub prefix: String,
+    pub timeout: Duration,
+}
+
+impl FromStr for RouteTimeout {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let (prefix, millis) = rule
+            .split_once('=')
+            .ok_or_else(|| format!("invalid route timeout '{}', expected <path>=<ms>", rule))?;
+
+        let prefix = prefix.trim();
+        if !prefix.starts_with('/') {
+            return Err(format!(
+                "invalid route timeout '{}', path must start with /",
+                rule
+            ));
+        }
+
+        let millis = millis
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| format!("invalid route timeout '{}', expected milliseconds", rule))?;
+
+        Ok(Self {
+            prefix: prefix.to_string(),
+            timeout: Duration::from_millis(millis),
+        })
+    }
+}
+
+/// Timeout of the rule with the longest prefix matching `path`, or `default`
+pub fn route_timeout(rules: &[RouteTimeout], path: &str, default: Duration) -> Duration {
+    rules
+        .iter()
+        .filter(|rule| path.starts_with(&rule.prefix))
+        .max_by_key(|rule| rule.prefix.len())
+        .map_or(default, |rule| rule.timeout)
+}
@@ -0,0 +1,169 @@
+// src/services/timing.rs
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::{Layer, Service};
+
+/// Phase timings of one upstream attempt: DNS resolution, connection
+/// setup, time to the response headers and total time including the body
+#[derive(Debug, Default, Clone, Copy)]
+struct Phases {
+    started: Option<Instant>,
+    dns: Option<Duration>,
+    connect: Option<Duration>,
+    ttfb: Option<Duration>,
+}
+
+tokio::task_local! {
+    /// Timings of the upstream attempt the current task is making
+    static ATTEMPT: UpstreamTimings;
+}
+
+/// Collects phase timings of one attempt made within `scope()` on a client
+/// built by `client()`. Phases that didn't happen (e.g. no DNS lookup for an
+/// IP literal, or a pooled connection) are reported as `-`.
+#[derive(Clone, Default)]
+pub struct UpstreamTimings(Arc<Mutex<Phases>>);
+
+impl UpstreamTimings {
+    /// A client whose resolver and connector report into the timings of the
+    /// attempt in progress, so one client serves every request
+    pub fn client(builder: reqwest::ClientBuilder) -> reqwest::Result<reqwest::Client> {
+        builder
+            .dns_resolver(Arc::new(TimedResolver))
+            .connector_layer(TimedConnectLayer)
+            .build()
+    }
+
+    /// Run an attempt, reporting its DNS and connect phases into these timings
+    pub async fn scope<F: Future>(&self, attempt: F) -> F::Output {
+        ATTEMPT.scope(self.clone(), attempt).await
+    }
+
+    /// Reset the phases at the start of an attempt
+    pub fn begin(&self) {
+        *self.phases() = Phases {
+            started: Some(Instant::now()),
+            ..Phases::default()
+        };
+    }
+
+    /// Mark the arrival of the response headers
+    pub fn first_byte(&self) {
+        let mut phases = self.phases();
+        phases.ttfb = phases.started.map(|started| started.elapsed());
+    }
+
+    /// Summary of the current attempt, with total time measured up to now
+    pub fn summary(&self) -> TimingSummary {
+        let phases = *self.phases();
+        TimingSummary {
+            total: phases.started.map(|started| started.elapsed()),
+            phases,
+        }
+    }
+
+    fn phases(&self) -> std::sync::MutexGuard<'_, Phases> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+pub struct TimingSummary {
+    phases: Phases,
+    total: Option<Duration>,
+}
+
+impl fmt::Display for TimingSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let phase = |d: Option<Duration>| d.map_or("-".to_string(), |d| format!("{:.2?}", d));
+        // The connector resolves names itself, so DNS time is taken out of connect
+        let connect = self
+            .phases
+            .connect
+            .map(|connect| connect.saturating_sub(self.phases.dns.unwrap_or_default()));
+
+        write!(
+            f,
+            "dns={} connect={} ttfb={} total={}",
+            phase(self.phases.dns),
+            phase(connect),
+            phase(self.phases.ttfb),
+            phase(self.total)
+        )
+    }
+}
+
+/// Timings of the attempt in progress, if it is being timed
+fn attempt() -> Option<UpstreamTimings> {
+    ATTEMPT.try_with(UpstreamTimings::clone).ok()
+}
+
+/// System resolver that records how long each lookup took
+struct TimedResolver;
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let timings = attempt();
+        Box::pin(async move {
+            let start = Instant::now();
+            let addrs = tokio::net::lookup_host((name.as_str(), 0)).await?;
+            if let Some(timings) = timings {
+                timings.phases().dns = Some(start.elapsed());
+            }
+
+            let addrs: Addrs = Box::new(addrs.collect::<Vec<_>>().into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Connector layer recording how long establishing a connection took
+#[derive(Clone)]
+struct TimedConnectLayer;
+
+impl<S> Layer<S> for TimedConnectLayer {
+    type Service = TimedConnect<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimedConnect { inner }
+    }
+}
+
+#[derive(Clone)]
+struct TimedConnect<S> {
+    inner: S,
+}
+
+impl<S, R> Service<R> for TimedConnect<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let timings = attempt();
+        let start = Instant::now();
+        let connecting = self.inner.call(request);
+
+        Box::pin(async move {
+            let result = connecting.await;
+            if let Some(timings) = timings {
+                timings.phases().connect = Some(start.elapsed());
+            }
+            result
+        })
+    }
+}
@@ -0,0 +1,97 @@
+// src/services/transform.rs
+use crate::middleware::is_under;
+use log::debug;
+use rocket::serde::json::Value;
+use std::str::FromStr;
+
+/// Change made to the top-level fields of a JSON request body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BodyEdit {
+    /// Set a field to a constant, overwriting what the client sent
+    Set(String, Value),
+    /// Remove a field the client isn't trusted to send
+    Drop(String),
+    /// Move a field's value to another name
+    Rename(String, String),
+}
+
+/// Rule editing bodies proxied under `prefix`, e.g.
+/// `/api/users/register=set:source:web-gateway`,
+/// `/api/users/register=drop:role` or `/api/users=rename:fullName:name`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BodyTransform {
+    pub prefix: String,
+    pub edit: BodyEdit,
+}
+
+impl FromStr for BodyTransform {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid body transform '{}', expected <path>=set:<field>:<value>, \
+                 <path>=drop:<field> or <path>=rename:<from>:<to>",
+                rule
+            )
+        };
+        let (prefix, edit) = rule.split_once('=').ok_or_else(invalid)?;
+        let prefix = prefix.trim();
+        if !prefix.starts_with('/') {
+            return Err(invalid());
+        }
+
+        let mut parts = edit.trim().splitn(3, ':');
+        let (op, field, arg) = (parts.next(), parts.next(), parts.next());
+        let field = field
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .ok_or_else(invalid)?
+            .to_string();
+        let edit = match (op, arg) {
+            (Some("set"), Some(value)) => BodyEdit::Set(field, constant(value)),
+            (Some("drop"), None) => BodyEdit::Drop(field),
+            (Some("rename"), Some(to)) if !to.trim().is_empty() => {
+                BodyEdit::Rename(field, to.trim().to_string())
+            }
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            prefix: prefix.to_string(),
+            edit,
+        })
+    }
+}
+
+/// A constant is JSON when it parses as JSON (`true`, `42`, `null`) and a
+/// string otherwise
+fn constant(value: &str) -> Value {
+    serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+}
+
+/// Apply, in order, the rules whose prefix covers `path`. Bodies that
+/// aren't JSON objects are left as they are.
+pub fn transform_body(rules: &[BodyTransform], path: &str, body: &mut Value) {
+    let Value::Object(fields) = body else {
+        return;
+    };
+    let path = path.split('?').next().unwrap_or(path);
+
+    for rule in rules.iter().filter(|rule| is_under(path, &rule.prefix)) {
+        match &rule.edit {
+            BodyEdit::Set(field, value) => {
+                fields.insert(field.clone(), value.clone());
+            }
+            BodyEdit::Drop(field) => {
+                fields.remove(field);
+            }
+            BodyEdit::Rename(from, to) => {
+                if let Some(value) = fields.remove(from) {
+                    fields.insert(to.clone(), value);
+                }
+            }
+        }
+        debug!("Applied body transform {:?} to {}", rule.edit, path);
+    }
+}
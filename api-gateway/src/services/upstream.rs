@@ -0,0 +1,652 @@
+// src/services/upstream.rs
+use crate::config::app::{AppConfig, ConfigError, ServiceOptions};
+use crate::services::breaker::{Breaker, BreakerPolicy, BreakerState};
+use crate::services::budget::RetryBudget;
+use crate::services::client;
+use crate::services::coalesce::Coalescer;
+use crate::services::stale::StaleCache;
+use crate::services::timing::UpstreamTimings;
+use log::debug;
+use rocket::futures::future::join_all;
+use rocket::serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// A backend service that may be served by several weighted replicas
+#[derive(Debug)]
+pub struct Upstream {
+    pub name: &'static str,
+    pub label: &'static str,
+    replicas: Vec<Replica>,
+    /// Indices into `replicas` in smooth weighted round-robin order
+    schedule: Vec<usize>,
+    next: AtomicUsize,
+    fallback: Option<String>,
+    /// Canary URL and the percentage of requests it takes
+    canary: Option<(String, u8)>,
+    host_header: Option<String>,
+    basic_auth: Option<BasicAuth>,
+    /// Shared client, so connections are pooled across requests
+    client: reqwest::Client,
+    /// Bulkhead bounding concurrent proxied calls, absent when unlimited
+    bulkhead: Option<Arc<Semaphore>>,
+    max_concurrent: usize,
+    /// Identical GETs in flight, shared when `COALESCE_GETS` is on
+    coalescer: Coalescer,
+    /// Last good GET responses, served when the upstream fails
+    stale: StaleCache,
+    retry_budget: RetryBudget,
+    /// Failed calls since the last successful one, to any target
+    consecutive_failures: AtomicU64,
+    total_failures: AtomicU64,
+    last_probe: Mutex<Option<ProbeResult>>,
+    /// What it was built from, to tell whether a reload changes it
+    settings: Settings,
+}
+
+#[derive(Debug, PartialEq)]
+struct Settings {
+    spec: String,
+    options: ServiceOptions,
+    breaker: BreakerPolicy,
+}
+
+/// One replica of an upstream and the breaker that takes it out of the
+/// rotation while it keeps failing
+#[derive(Debug)]
+struct Replica {
+    url: String,
+    breaker: Breaker,
+}
+
+/// HTTP Basic credentials sent to an upstream in place of the caller's
+/// `Authorization`. The password never appears in `Debug` output.
+#[derive(Clone, PartialEq)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: String,
+}
+
+impl FromStr for BasicAuth {
+    type Err = String;
+
+    /// `user:pass`, split on the first colon so the password may contain more
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(':') {
+            Some((username, password)) if !username.is_empty() => Ok(Self {
+                username: username.to_string(),
+                password: password.to_string(),
+            }),
+            _ => Err("expected <user>:<password>".into()),
+        }
+    }
+}
+
+impl fmt::Debug for BasicAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BasicAuth")
+            .field("username", &self.username)
+            .field("password", &"[REDACTED]")
+            .finish()
+    }
+}
+
+/// Operator view of an upstream, served at `/api/admin/upstreams`
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpstreamStatus {
+    pub name: &'static str,
+    pub label: &'static str,
+    /// State of the healthiest replica's breaker
+    pub breaker: BreakerState,
+    pub replicas: Vec<ReplicaStatus>,
+    pub fallback: Option<String>,
+    pub canary: Option<String>,
+    pub canary_percent: u8,
+    /// Failed calls in a row and in total, counting the canary and fallback
+    pub consecutive_failures: u64,
+    pub total_failures: u64,
+    /// Outcome of the latest health probe, absent until one has run
+    pub last_probe: Option<ProbeResult>,
+    /// Calls currently holding a bulkhead slot, absent when unlimited
+    pub in_flight: Option<usize>,
+    pub max_concurrent: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReplicaStatus {
+    pub url: String,
+    pub breaker: BreakerState,
+    pub consecutive_failures: u64,
+    pub total_failures: u64,
+    /// Time until an open breaker lets a trial call through
+    pub retry_in_ms: Option<u64>,
+}
+
+/// One health probe of an upstream replica
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProbeResult {
+    pub target: String,
+    pub up: bool,
+    /// Unix time of the probe, in seconds
+    pub timestamp: u64,
+}
+
+impl Upstream {
+    /// Build an upstream from a comma-separated list of replica URLs,
+    /// each optionally suffixed with `;weight=N` (defaults to 1).
+    /// e.g. `http://a:3000;weight=3,http://b:3000`
+    pub fn from_spec(
+        name: &'static str,
+        label: &'static str,
+        spec: &str,
+        options: &ServiceOptions,
+        breaker: BreakerPolicy,
+        client: &reqwest::Client,
+    ) -> Result<Self, String> {
+        let mut replicas = Vec::new();
+
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (url, weight) = match entry.split_once(';') {
+                Some((url, param)) => {
+                    let weight = param
+                        .trim()
+                        .strip_prefix("weight=")
+                        .and_then(|w| w.parse::<usize>().ok())
+                        .filter(|w| *w > 0)
+                        .ok_or_else(|| {
+                            format!(
+                                "invalid weight in '{}', expected ;weight=<positive integer>",
+                                entry
+                            )
+                        })?;
+                    (url.trim(), weight)
+                }
+                None => (entry, 1),
+            };
+            replicas.push((url.trim_end_matches('/').to_string(), weight));
+        }
+
+        if replicas.is_empty() {
+            return Err(format!("no URL configured for {}", label));
+        }
+
+        Ok(Self {
+            name,
+            label,
+            schedule: smooth_weighted_schedule(&replicas),
+            replicas: replicas
+                .into_iter()
+                .map(|(url, _)| Replica {
+                    url,
+                    breaker: Breaker::new(breaker),
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+            fallback: options.fallback_url.clone(),
+            canary: options
+                .canary_url
+                .clone()
+                .filter(|_| options.canary_percent > 0)
+                .map(|url| (url, options.canary_percent)),
+            host_header: options.host_header.clone(),
+            basic_auth: options.basic_auth.clone(),
+            client: client.clone(),
+            bulkhead: (options.max_concurrent > 0)
+                .then(|| Arc::new(Semaphore::new(options.max_concurrent))),
+            max_concurrent: options.max_concurrent,
+            coalescer: Coalescer::default(),
+            stale: StaleCache::default(),
+            retry_budget: RetryBudget::new(options.retry_budget_ratio),
+            consecutive_failures: AtomicU64::new(0),
+            total_failures: AtomicU64::new(0),
+            last_probe: Mutex::new(None),
+            settings: Settings {
+                spec: spec.to_string(),
+                options: options.clone(),
+                breaker,
+            },
+        })
+    }
+
+    /// Pick the base URL of the replica that should serve the next request,
+    /// skipping replicas whose breaker is open. When every breaker is open
+    /// the replica next in line is picked regardless.
+    pub fn next_target(&self) -> &str {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let replica = (0..self.schedule.len())
+            .map(|offset| self.in_line(start + offset))
+            .find(|replica| replica.breaker.admit())
+            .unwrap_or_else(|| self.in_line(start));
+        debug!("Selected {} replica {}", self.name, replica.url);
+        &replica.url
+    }
+
+    /// The replica at `position` in the weighted rotation
+    fn in_line(&self, position: usize) -> &Replica {
+        &self.replicas[self.schedule[position % self.schedule.len()]]
+    }
+
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    pub fn coalescer(&self) -> &Coalescer {
+        &self.coalescer
+    }
+
+    pub fn stale_cache(&self) -> &StaleCache {
+        &self.stale
+    }
+
+    pub fn retry_budget(&self) -> &RetryBudget {
+        &self.retry_budget
+    }
+
+    /// The canary URL when `key` falls in the canary's share of traffic.
+    /// The same key always gets the same answer, so a user keeps seeing the
+    /// same version for as long as the split is unchanged.
+    pub fn canary_for(&self, key: &str) -> Option<&str> {
+        let (url, percent) = self.canary.as_ref()?;
+        (bucket(key) < *percent).then_some(url.as_str())
+    }
+
+    /// `Host` override for backends routing on virtual host
+    pub fn host_header(&self) -> Option<&str> {
+        self.host_header.as_deref()
+    }
+
+    /// Credentials for backends that only accept HTTP Basic auth
+    pub fn basic_auth(&self) -> Option<&BasicAuth> {
+        self.basic_auth.as_ref()
+    }
+
+    /// Base URL of the passive failover target, if one is configured
+    pub fn fallback(&self) -> Option<&str> {
+        self.fallback.as_deref()
+    }
+
+    /// Count the outcome of one call to `url`: a transport error or 5xx is
+    /// a failure. Calls to a replica also feed its breaker.
+    pub fn record(&self, url: &str, succeeded: bool) {
+        if let Some(replica) = self
+            .replicas
+            .iter()
+            .find(|replica| serves(&replica.url, url))
+        {
+            replica.breaker.record(succeeded);
+        }
+        if succeeded {
+            self.consecutive_failures.store(0, Ordering::Relaxed);
+        } else {
+            self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+            self.total_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The state of the healthiest replica's breaker: closed while any
+    /// replica takes calls, open once every replica refuses them
+    pub fn breaker_state(&self) -> BreakerState {
+        let states: Vec<_> = self
+            .replicas
+            .iter()
+            .map(|replica| replica.breaker.state())
+            .collect();
+        [BreakerState::Closed, BreakerState::HalfOpen]
+            .into_iter()
+            .find(|state| states.contains(state))
+            .unwrap_or(BreakerState::Open)
+    }
+
+    /// Time until the first open breaker lets a trial call through, while
+    /// every replica's breaker is open
+    pub fn retry_in(&self) -> Option<Duration> {
+        self.replicas
+            .iter()
+            .map(|replica| replica.breaker.retry_in())
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .min()
+    }
+
+    /// Replica URLs, sorted
+    pub fn targets(&self) -> Vec<String> {
+        let mut targets: Vec<_> = self
+            .replicas
+            .iter()
+            .map(|replica| replica.url.clone())
+            .collect();
+        targets.sort();
+        targets
+    }
+
+    pub fn status(&self) -> UpstreamStatus {
+        let mut replicas: Vec<_> = self
+            .replicas
+            .iter()
+            .map(|replica| {
+                let (consecutive_failures, total_failures) = replica.breaker.failures();
+                ReplicaStatus {
+                    url: replica.url.clone(),
+                    breaker: replica.breaker.state(),
+                    consecutive_failures,
+                    total_failures,
+                    retry_in_ms: replica
+                        .breaker
+                        .retry_in()
+                        .map(|wait| wait.as_millis() as u64),
+                }
+            })
+            .collect();
+        replicas.sort_by(|a, b| a.url.cmp(&b.url));
+
+        UpstreamStatus {
+            name: self.name,
+            label: self.label,
+            breaker: self.breaker_state(),
+            replicas,
+            fallback: self.fallback.clone(),
+            canary: self.canary.as_ref().map(|(url, _)| url.clone()),
+            canary_percent: self.canary.as_ref().map_or(0, |(_, percent)| *percent),
+            consecutive_failures: self.consecutive_failures.load(Ordering::Relaxed),
+            total_failures: self.total_failures.load(Ordering::Relaxed),
+            last_probe: self.last_probe.lock().unwrap().clone(),
+            in_flight: self
+                .bulkhead
+                .as_ref()
+                .map(|bulkhead| self.max_concurrent - bulkhead.available_permits()),
+            max_concurrent: self.bulkhead.as_ref().map(|_| self.max_concurrent),
+        }
+    }
+
+    /// Open `connections` pooled connections to each replica by sending that
+    /// many concurrent health probes. Returns how many probes were answered;
+    /// unreachable replicas are skipped and don't count as failures.
+    pub async fn warm_up(&self, connections: usize, timeout: Duration) -> usize {
+        let probes = self.targets().into_iter().flat_map(|target| {
+            let url = health_url(&target);
+            (0..connections).map(move |_| {
+                let request = self.client.get(url.clone()).timeout(timeout);
+                async move { request.send().await.is_ok() }
+            })
+        });
+
+        let answered = join_all(probes).await.into_iter().filter(|ok| *ok).count();
+        debug!("Warmed up {} with {} connections", self.name, answered);
+        answered
+    }
+
+    /// Whether the next replica answers its health endpoint within
+    /// `timeout` without a 5xx. Probes ignore breakers and don't count as
+    /// call outcomes.
+    pub async fn probe(&self, timeout: Duration) -> bool {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let target = &self.in_line(start).url;
+        let url = health_url(target);
+        let up = match self.client.get(url).timeout(timeout).send().await {
+            Ok(response) => !response.status().is_server_error(),
+            Err(_) => false,
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        *self.last_probe.lock().unwrap() = Some(ProbeResult {
+            target: target.clone(),
+            up,
+            timestamp,
+        });
+        up
+    }
+
+    /// Take a bulkhead slot, waiting up to `queue_timeout` for one to free
+    /// up. Returns `Ok(None)` when the service has no limit and `Err(())`
+    /// when no slot became available in time.
+    pub async fn acquire(
+        &self,
+        queue_timeout: Duration,
+    ) -> Result<Option<OwnedSemaphorePermit>, ()> {
+        let Some(bulkhead) = &self.bulkhead else {
+            return Ok(None);
+        };
+
+        if let Ok(permit) = bulkhead.clone().try_acquire_owned() {
+            return Ok(Some(permit));
+        }
+        if queue_timeout.is_zero() {
+            return Err(());
+        }
+
+        match tokio::time::timeout(queue_timeout, bulkhead.clone().acquire_owned()).await {
+            Ok(Ok(permit)) => Ok(Some(permit)),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Health endpoint of the replica at `target`. WebSocket upstreams answer
+/// plain HTTP on the same host and port, so `ws`/`wss` map to `http`/`https`.
+fn health_url(target: &str) -> String {
+    let target = match target.split_once("://") {
+        Some(("ws", rest)) => format!("http://{}", rest),
+        Some(("wss", rest)) => format!("https://{}", rest),
+        _ => target.to_string(),
+    };
+    format!("{}/api/health", target)
+}
+
+/// Whether `url` is on the replica at `base`, as opposed to one that merely
+/// shares a prefix with it (`http://a:3000` and `http://a:30001`)
+fn serves(base: &str, url: &str) -> bool {
+    url.strip_prefix(base)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '?']))
+}
+
+/// Stable 0-99 bucket for a key (FNV-1a), identical across gateway
+/// instances and restarts
+fn bucket(key: &str) -> u8 {
+    let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    (hash % 100) as u8
+}
+
+/// Expand weighted replicas into one full round of the smooth weighted
+/// round-robin used by nginx, so heavier replicas are interleaved rather
+/// than served in bursts. Replicas are given by their index.
+fn smooth_weighted_schedule(replicas: &[(String, usize)]) -> Vec<usize> {
+    let total: i64 = replicas.iter().map(|(_, w)| *w as i64).sum();
+    let mut current = vec![0i64; replicas.len()];
+    let mut schedule = Vec::with_capacity(total as usize);
+
+    for _ in 0..total {
+        for (i, (_, weight)) in replicas.iter().enumerate() {
+            current[i] += *weight as i64;
+        }
+
+        let (best, _) = current
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, value)| (**value, std::cmp::Reverse(*i)))
+            .expect("replica list is never empty");
+
+        current[best] -= total;
+        schedule.push(best);
+    }
+
+    schedule
+}
+
+/// All backend services the gateway proxies to, swapped with the
+/// configuration. Upstreams are shared between snapshots while their
+/// settings stay the same, so reloads keep their runtime state.
+#[derive(Debug)]
+pub struct Upstreams {
+    pub users: Arc<Upstream>,
+    pub payments: Arc<Upstream>,
+    pub sales: Arc<Upstream>,
+    pub purchasing: Arc<Upstream>,
+    pub inventory: Arc<Upstream>,
+    pub customers: Arc<Upstream>,
+    pub notifications: Arc<Upstream>,
+}
+
+impl Upstreams {
+    /// Look up an HTTP upstream by its route group name
+    pub fn by_name(&self, name: &str) -> Option<&Upstream> {
+        [
+            &self.users,
+            &self.payments,
+            &self.sales,
+            &self.purchasing,
+            &self.inventory,
+            &self.customers,
+        ]
+        .into_iter()
+        .map(Arc::as_ref)
+        .find(|upstream| upstream.name == name)
+    }
+
+    pub fn all(&self) -> [&Upstream; 7] {
+        [
+            &self.users,
+            &self.payments,
+            &self.sales,
+            &self.purchasing,
+            &self.inventory,
+            &self.customers,
+            &self.notifications,
+        ]
+        .map(Arc::as_ref)
+    }
+
+    pub fn from_config(config: &AppConfig) -> Result<Self, ConfigError> {
+        Self::build(config, None)
+    }
+
+    /// The upstreams for a reloaded `config`, carrying over the ones whose
+    /// settings it leaves alone along with their breakers, retry budget,
+    /// stale cache, coalesced calls and bulkhead. An upstream whose URLs or
+    /// options change starts afresh; changing the client settings shared by
+    /// every upstream (HTTP/2, TLS, connect timeout, timings) rebuilds all.
+    pub fn reload(&self, previous: &AppConfig, config: &AppConfig) -> Result<Self, ConfigError> {
+        let reusable = client::same_settings(previous, config);
+        Self::build(config, reusable.then_some(self))
+    }
+
+    fn build(config: &AppConfig, previous: Option<&Upstreams>) -> Result<Self, ConfigError> {
+        let client = match previous {
+            Some(previous) => previous.users.client.clone(),
+            None => Self::client(config)?,
+        };
+
+        let breaker = BreakerPolicy::from_config(config);
+        let upstream = |name: &'static str,
+                        label: &'static str,
+                        spec: &str,
+                        options: &ServiceOptions,
+                        previous: Option<&Arc<Upstream>>| {
+            let settings = Settings {
+                spec: spec.to_string(),
+                options: options.clone(),
+                breaker,
+            };
+            match previous {
+                Some(previous) if previous.settings == settings => Ok(previous.clone()),
+                _ => Upstream::from_spec(name, label, spec, options, breaker, &client)
+                    .map(Arc::new)
+                    .map_err(ConfigError::Upstream),
+            }
+        };
+
+        let upstreams = Self {
+            users: upstream(
+                "users",
+                "User Service",
+                &config.user_service_url,
+                &config.user_service_options,
+                previous.map(|previous| &previous.users),
+            )?,
+            payments: upstream(
+                "payments",
+                "Payments Service",
+                &config.payments_service_url,
+                &config.payments_service_options,
+                previous.map(|previous| &previous.payments),
+            )?,
+            sales: upstream(
+                "sales",
+                "Sales Service",
+                &config.sales_service_url,
+                &config.sales_service_options,
+                previous.map(|previous| &previous.sales),
+            )?,
+            purchasing: upstream(
+                "purchasing",
+                "Purchasing Service",
+                &config.purchasing_service_url,
+                &config.purchasing_service_options,
+                previous.map(|previous| &previous.purchasing),
+            )?,
+            inventory: upstream(
+                "inventory",
+                "Inventory Service",
+                &config.inventory_service_url,
+                &config.inventory_service_options,
+                previous.map(|previous| &previous.inventory),
+            )?,
+            customers: upstream(
+                "customers",
+                "Customer Service",
+                &config.customer_service_url,
+                &config.customer_service_options,
+                previous.map(|previous| &previous.customers),
+            )?,
+            notifications: upstream(
+                "notifications",
+                "Notifications Service",
+                &config.notifications_service_url,
+                &config.notifications_service_options,
+                previous.map(|previous| &previous.notifications),
+            )?,
+        };
+
+        // A misspelt name would silently leave that service out of the gate
+        let names = upstreams.all().map(|upstream| upstream.name);
+        if let Some(unknown) = config
+            .critical_services
+            .iter()
+            .find(|critical| !names.contains(&critical.as_str()))
+        {
+            return Err(ConfigError::Upstream(format!(
+                "CRITICAL_SERVICES names unknown service '{}', expected one of {}",
+                unknown,
+                names.join(", ")
+            )));
+        }
+        Ok(upstreams)
+    }
+
+    fn client(config: &AppConfig) -> Result<reqwest::Client, ConfigError> {
+        let builder = client::builder(config);
+        let client = match config.log_upstream_timings {
+            true => UpstreamTimings::client(builder),
+            false => builder.build(),
+        };
+        client.map_err(|e| {
+            // reqwest's builder error only names its kind, the cause is in the source
+            let cause = std::error::Error::source(&e).map(|cause| format!(": {}", cause));
+            ConfigError::Upstream(format!(
+                "unable to build HTTP client: {}{}",
+                e,
+                cause.unwrap_or_default()
+            ))
+        })
+    }
+}
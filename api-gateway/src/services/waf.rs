@@ -0,0 +1,76 @@
+// src/services/waf.rs
+use rocket::Request;
+use std::str::FromStr;
+
+/// A check that turns away requests no legitimate client sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WafRule {
+    /// More headers than `WAF_MAX_HEADERS`
+    HeaderCount,
+    /// A header value with a NUL byte
+    NullBytes,
+    /// `..` path segments in the path or query, percent-encoded or not
+    PathTraversal,
+}
+
+impl FromStr for WafRule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "header-count" => Ok(Self::HeaderCount),
+            "null-bytes" => Ok(Self::NullBytes),
+            "path-traversal" => Ok(Self::PathTraversal),
+            other => Err(format!(
+                "unknown WAF rule '{}', expected header-count, null-bytes or path-traversal",
+                other
+            )),
+        }
+    }
+}
+
+impl WafRule {
+    /// Value of the `rule` label on `api_waf_blocked_total`
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::HeaderCount => "header_count",
+            Self::NullBytes => "null_bytes",
+            Self::PathTraversal => "path_traversal",
+        }
+    }
+
+    pub fn matches(&self, request: &Request<'_>, max_headers: usize) -> bool {
+        match self {
+            Self::HeaderCount => request.headers().len() > max_headers,
+            Self::NullBytes => request
+                .headers()
+                .iter()
+                .any(|header| header.value().contains('\0')),
+            Self::PathTraversal => {
+                let uri = request.uri();
+                has_traversal(uri.path().as_str())
+                    || uri
+                        .query()
+                        .is_some_and(|query| has_traversal(query.as_str()))
+            }
+        }
+    }
+}
+
+/// Whether `raw`, once percent-decoded, has a `..` segment
+fn has_traversal(raw: &str) -> bool {
+    let decoded = percent_decode(raw);
+    decoded
+        .split(['/', '\\', '=', '&'])
+        .any(|segment| segment == "..")
+}
+
+fn percent_decode(raw: &str) -> String {
+    // Decoded twice, so double-encoded `%252e%252e` is caught too
+    let once = rocket::http::RawStr::new(raw)
+        .percent_decode_lossy()
+        .into_owned();
+    rocket::http::RawStr::new(&once)
+        .percent_decode_lossy()
+        .into_owned()
+}
@@ -0,0 +1,204 @@
+// src/tests/admin.rs
+use super::support::{MockUpstream, bearer, gateway};
+use crate::guards::api_key::API_KEY_HEADER;
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::{Value, json};
+use std::time::Duration;
+
+#[rocket::async_test]
+async fn admin_routes_are_only_reachable_from_the_allowlist() {
+    let client = gateway(|config| {
+        config.api_keys = vec!["k1".into()];
+        config.admin_ip_allowlist = "10.0.0.0/8".parse().expect("valid range");
+    })
+    .await;
+
+    let maintenance = |remote: &str, key: Option<&'static str>| {
+        let mut request = client
+            .get("/api/admin/maintenance")
+            .remote(remote.parse().expect("socket address"))
+            .header(Header::new("X-Forwarded-For", "10.0.0.1"));
+        if let Some(key) = key {
+            request = request.header(Header::new(API_KEY_HEADER, key));
+        }
+        request.dispatch()
+    };
+
+    let outside = maintenance("192.0.2.1:4000", Some("k1")).await;
+    assert_eq!(outside.status(), Status::Forbidden);
+    assert_eq!(
+        maintenance("192.0.2.1:4000", None).await.status(),
+        Status::Forbidden
+    );
+
+    assert_eq!(
+        maintenance("10.1.2.3:4000", None).await.status(),
+        Status::Unauthorized
+    );
+    assert_eq!(
+        maintenance("10.1.2.3:4000", Some("k1")).await.status(),
+        Status::Ok
+    );
+}
+
+#[rocket::async_test]
+async fn admin_allowlist_ignores_a_spoofed_real_ip() {
+    let client = gateway(|config| {
+        config.api_keys = vec!["k1".into()];
+        config.admin_ip_allowlist = "10.0.0.0/8".parse().expect("valid range");
+    })
+    .await;
+
+    let spoofed = client
+        .get("/api/admin/maintenance")
+        .remote("192.0.2.1:4000".parse().expect("socket address"))
+        .header(Header::new("X-Real-IP", "10.0.0.1"))
+        .header(Header::new(API_KEY_HEADER, "k1"))
+        .dispatch()
+        .await;
+    assert_eq!(spoofed.status(), Status::Forbidden);
+}
+
+#[rocket::async_test]
+async fn admin_allowlist_checks_the_trusted_ip_header_when_set() {
+    let client = gateway(|config| {
+        config.api_keys = vec!["k1".into()];
+        config.admin_ip_allowlist = "10.0.0.0/8".parse().expect("valid range");
+        config.trusted_ip_header = Some("X-Client-Address".into());
+    })
+    .await;
+
+    let behind_proxy = |address: Option<&'static str>| {
+        let mut request = client
+            .get("/api/admin/maintenance")
+            .remote("10.9.9.9:4000".parse().expect("socket address"))
+            .header(Header::new(API_KEY_HEADER, "k1"));
+        if let Some(address) = address {
+            request = request.header(Header::new("X-Client-Address", address));
+        }
+        request.dispatch()
+    };
+
+    assert_eq!(behind_proxy(Some("10.1.2.3")).await.status(), Status::Ok);
+    assert_eq!(
+        behind_proxy(Some("192.0.2.1")).await.status(),
+        Status::Forbidden
+    );
+    assert_eq!(behind_proxy(None).await.status(), Status::Forbidden);
+}
+
+#[rocket::async_test]
+async fn upstream_status_reports_breakers_and_the_last_probe() {
+    let users = MockUpstream::start(200, json!({ "status": "ok" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.api_keys = vec!["k1".into()];
+        config.user_service_url = url;
+        config.breaker_failure_threshold = 1;
+        config.breaker_cooldown = Duration::from_secs(60);
+    })
+    .await;
+
+    client.get("/api/health/ready").dispatch().await;
+    users.set_status(503);
+    client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"secret"}"#)
+        .dispatch()
+        .await;
+
+    let response = client
+        .get("/api/admin/upstreams")
+        .header(Header::new(API_KEY_HEADER, "k1"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = response.into_json::<Value>().await.expect("JSON body");
+    let status = body
+        .as_array()
+        .expect("one entry per upstream")
+        .iter()
+        .find(|upstream| upstream["name"] == "users")
+        .expect("users entry");
+
+    assert_eq!(status["breaker"], "open");
+    assert_eq!(status["replicas"][0]["url"], users.url);
+    assert_eq!(status["replicas"][0]["breaker"], "open");
+    assert_eq!(status["replicas"][0]["consecutive_failures"], 1);
+    assert!(status["replicas"][0]["retry_in_ms"].as_u64().unwrap() > 0);
+    assert_eq!(status["last_probe"]["up"], true);
+    assert_eq!(status["last_probe"]["target"], users.url);
+}
+
+#[rocket::async_test]
+async fn disabled_route_group_answers_503_while_others_keep_serving() {
+    let upstream = MockUpstream::start(200, json!({ "id": "o1" })).await;
+    let url = upstream.url.clone();
+    let client = gateway(|config| {
+        config.api_keys = vec!["k1".into()];
+        config.user_service_url = url.clone();
+        config.sales_service_url = url;
+    })
+    .await;
+
+    let toggle = |enabled: bool| {
+        client
+            .put("/api/admin/routes/sales")
+            .header(Header::new(API_KEY_HEADER, "k1"))
+            .header(ContentType::JSON)
+            .body(json!({ "enabled": enabled }).to_string())
+            .dispatch()
+    };
+    let bearer = bearer();
+    let order = || {
+        client
+            .get("/api/sales/orders/o1")
+            .header(bearer.clone())
+            .dispatch()
+    };
+    let login = || {
+        client
+            .post("/api/users/login")
+            .header(ContentType::JSON)
+            .body(r#"{"email":"a@example.com","password":"secret"}"#)
+            .dispatch()
+    };
+
+    let disabled = toggle(false).await;
+    assert_eq!(disabled.status(), Status::Ok);
+    assert_eq!(
+        disabled.into_json::<Value>().await,
+        Some(json!({ "group": "sales", "enabled": false }))
+    );
+
+    assert_eq!(order().await.status(), Status::ServiceUnavailable);
+    assert_eq!(login().await.status(), Status::Ok);
+    assert_eq!(upstream.only_request().path, "/api/users/login");
+
+    assert_eq!(toggle(true).await.status(), Status::Ok);
+    assert_eq!(order().await.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn traffic_summary_is_only_in_the_admin_health_output() {
+    let client = gateway(|config| config.api_keys = vec!["k1".into()]).await;
+
+    let public = client.get("/api/health").dispatch().await;
+    assert_eq!(public.status(), Status::Ok);
+    let body = public.into_json::<Value>().await.expect("JSON body");
+    assert!(body.get("requests").is_none());
+
+    let anonymous = client.get("/api/admin/health").dispatch().await;
+    assert_eq!(anonymous.status(), Status::Unauthorized);
+
+    let admin = client
+        .get("/api/admin/health")
+        .header(Header::new(API_KEY_HEADER, "k1"))
+        .dispatch()
+        .await;
+    assert_eq!(admin.status(), Status::Ok);
+    let body = admin.into_json::<Value>().await.expect("JSON body");
+    assert_eq!(body["requests"]["window_seconds"], 60);
+    assert!(body["requests"]["window_requests"].as_u64().expect("count") >= 1);
+}
@@ -0,0 +1,65 @@
+// src/tests/auth.rs
+use super::support::{MockUpstream, bearer, gateway};
+use rocket::http::Status;
+use rocket::serde::json::{Value, json};
+
+#[rocket::async_test]
+async fn paths_outside_public_paths_need_a_valid_token() {
+    let client = gateway(|_| {}).await;
+
+    let anonymous = client.get("/api/inventory/products").dispatch().await;
+    assert_eq!(anonymous.status(), Status::Unauthorized);
+    let body = anonymous.into_json::<Value>().await.expect("JSON error");
+    assert_eq!(body["status"], 401);
+
+    let authenticated = client
+        .get("/api/inventory/products")
+        .header(bearer())
+        .dispatch()
+        .await;
+    assert_eq!(authenticated.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn public_paths_cover_everything_below_them() {
+    let client = gateway(|config| {
+        config.public_paths = vec!["/api/inventory".to_string()];
+    })
+    .await;
+
+    let below = client.get("/api/inventory/products").dispatch().await;
+    assert_eq!(below.status(), Status::NotFound);
+
+    let lookalike = client.get("/api/inventory-archive").dispatch().await;
+    assert_eq!(lookalike.status(), Status::Unauthorized);
+
+    let health = client.get("/api/health/live").dispatch().await;
+    assert_eq!(health.status(), Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn proxy_public_routes_cover_whole_path_segments() {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.proxy_public_routes = vec!["users/api/users/login".into()];
+    })
+    .await;
+
+    let post = |path: &'static str| client.post(path).dispatch();
+    assert_eq!(
+        post("/api/proxy/users/api/users/login").await.status(),
+        Status::Ok
+    );
+    assert_eq!(
+        post("/api/proxy/users/api/users/login/otp").await.status(),
+        Status::Ok
+    );
+    for path in [
+        "/api/proxy/users/api/users/login-admin",
+        "/api/proxy/users/api/users/loginx/reset",
+    ] {
+        assert_eq!(post(path).await.status(), Status::Unauthorized, "{}", path);
+    }
+}
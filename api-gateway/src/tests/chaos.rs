@@ -0,0 +1,49 @@
+// src/tests/chaos.rs
+use super::support::{MockUpstream, gateway};
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::serde::json::{Value, json};
+
+async fn chaos_gateway(environment: &str) -> (MockUpstream, Client) {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.environment = environment.into();
+        config.chaos.error_percent = 100;
+        config.chaos.error_status = Status::BadGateway;
+    })
+    .await;
+    (users, client)
+}
+
+async fn login(client: &Client) -> LocalResponse<'_> {
+    client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"secret"}"#)
+        .dispatch()
+        .await
+}
+
+#[rocket::async_test]
+async fn full_error_injection_fails_every_request_with_the_configured_status() {
+    let (users, client) = chaos_gateway("development").await;
+
+    for _ in 0..3 {
+        let response = login(&client).await;
+        assert_eq!(response.status(), Status::BadGateway);
+        let body = response.into_json::<Value>().await.expect("JSON error");
+        assert_eq!(body["status"], 502);
+        assert_eq!(body["message"], "Injected fault");
+    }
+    assert!(users.requests().is_empty());
+}
+
+#[rocket::async_test]
+async fn error_injection_is_ignored_outside_development() {
+    let (users, client) = chaos_gateway("production").await;
+
+    assert_eq!(login(&client).await.status(), Status::Ok);
+    assert_eq!(users.requests().len(), 1);
+}
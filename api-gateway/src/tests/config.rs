@@ -0,0 +1,41 @@
+// src/tests/config.rs
+use crate::config::app::{AppConfig, ConfigError};
+use crate::config::source::ConfigSource;
+
+fn load(pairs: &[(&str, &str)]) -> Result<AppConfig, ConfigError> {
+    AppConfig::from_source(ConfigSource::from_pairs(pairs))
+}
+
+/// The configuration with `contents` as its `CONFIG_FILE`
+fn load_file(contents: &str) -> Result<AppConfig, ConfigError> {
+    let path = std::env::temp_dir().join(format!("gateway-{}.toml", uuid::Uuid::new_v4()));
+    std::fs::write(&path, contents).expect("config file written");
+    let loaded = ConfigSource::from_file(path.to_str().expect("UTF-8 path"))
+        .and_then(AppConfig::from_source);
+    let _ = std::fs::remove_file(&path);
+    loaded
+}
+
+#[test]
+fn default_jwt_secret_is_rejected_outside_development() {
+    let error = load(&[("NODE_ENV", "production")]).expect_err("default secret in production");
+    assert!(error.to_string().contains("JWT_SECRET"), "{}", error);
+
+    assert!(load(&[("NODE_ENV", "production"), ("JWT_SECRET", "s3cret")]).is_ok());
+    assert!(load(&[]).is_ok());
+}
+
+#[test]
+fn unknown_file_keys_are_rejected() {
+    let error = load_file("user_servce_url = \"http://localhost:3001\"").expect_err("misspelt key");
+    assert!(
+        matches!(&error, ConfigError::File(message) if message.contains("user_servce_url")),
+        "{}",
+        error
+    );
+
+    let known = "user_service_url = \"http://localhost:3001\"\n\
+                 [sales_service]\n\
+                 fallback_url = \"http://localhost:3013\"\n";
+    assert!(load_file(known).is_ok());
+}
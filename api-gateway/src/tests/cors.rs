@@ -0,0 +1,136 @@
+// src/tests/cors.rs
+use super::support::gateway;
+use rocket::http::{Header, Status};
+
+#[rocket::async_test]
+async fn preflight_sends_max_age() {
+    let client = gateway(|config| config.cors_max_age = Some(900)).await;
+
+    let response = client
+        .options("/api/users/login")
+        .header(Header::new("Origin", "https://app.example.com"))
+        .header(Header::new("Access-Control-Request-Method", "POST"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(
+        response.headers().get_one("Access-Control-Max-Age"),
+        Some("900")
+    );
+}
+
+#[rocket::async_test]
+async fn request_id_is_exposed_to_browsers() {
+    let client = gateway(|config| config.cors_expose_headers = vec!["X-Upstream".into()]).await;
+
+    let response = client
+        .get("/api/health/live")
+        .header(Header::new("Origin", "https://app.example.com"))
+        .dispatch()
+        .await;
+
+    assert!(response.headers().get_one("X-Request-Id").is_some());
+    let exposed = response
+        .headers()
+        .get_one("Access-Control-Expose-Headers")
+        .expect("exposed headers");
+    assert!(exposed.contains("X-Request-Id"));
+    assert!(exposed.contains("X-Upstream"));
+}
+
+#[rocket::async_test]
+async fn route_override_narrows_allowed_origins() {
+    let client = gateway(|config| {
+        config.cors_overrides = vec!["/api/health=https://docs.example.com".parse().unwrap()];
+    })
+    .await;
+
+    let from = |origin: &'static str| {
+        client
+            .get("/api/health/live")
+            .header(Header::new("Origin", origin))
+            .dispatch()
+    };
+
+    let allowed = from("https://docs.example.com").await;
+    assert_eq!(allowed.status(), Status::Ok);
+    assert_eq!(
+        allowed.headers().get_one("Access-Control-Allow-Origin"),
+        Some("https://docs.example.com")
+    );
+
+    let refused = from("https://app.example.com").await;
+    assert_eq!(refused.status(), Status::Forbidden);
+    assert!(
+        refused
+            .headers()
+            .get_one("Access-Control-Allow-Origin")
+            .is_none()
+    );
+
+    let preflight = client
+        .options("/api/health/live")
+        .header(Header::new("Origin", "https://app.example.com"))
+        .header(Header::new("Access-Control-Request-Method", "GET"))
+        .dispatch()
+        .await;
+    assert_eq!(preflight.status(), Status::Forbidden);
+    assert!(
+        preflight
+            .headers()
+            .get_one("Access-Control-Allow-Origin")
+            .is_none()
+    );
+
+    let elsewhere = client
+        .options("/api/users/login")
+        .header(Header::new("Origin", "https://app.example.com"))
+        .header(Header::new("Access-Control-Request-Method", "POST"))
+        .dispatch()
+        .await;
+    assert_eq!(elsewhere.status(), Status::NoContent);
+}
+
+#[rocket::async_test]
+async fn preflight_allows_custom_headers_on_mounted_routes() {
+    let client = gateway(|_| {}).await;
+
+    let preflight = |uri: &'static str, method: &'static str| {
+        client
+            .options(uri)
+            .header(Header::new("Origin", "https://app.example.com"))
+            .header(Header::new("Access-Control-Request-Method", method))
+            .header(Header::new(
+                "Access-Control-Request-Headers",
+                "X-Client-Version",
+            ))
+            .dispatch()
+    };
+
+    for (uri, method) in [
+        ("/api/users/login", "POST"),
+        ("/api/proxy/sales/api/orders/o1", "PATCH"),
+    ] {
+        let response = preflight(uri, method).await;
+        assert_eq!(response.status(), Status::NoContent, "{}", uri);
+        let headers = response.headers();
+        assert_eq!(
+            headers.get_one("Access-Control-Allow-Origin"),
+            Some("https://app.example.com")
+        );
+        assert!(
+            headers
+                .get_one("Access-Control-Allow-Methods")
+                .is_some_and(|methods| methods.contains(method))
+        );
+        assert_eq!(
+            headers.get_one("Access-Control-Allow-Headers"),
+            Some("X-Client-Version")
+        );
+        assert!(response.body().is_none());
+    }
+
+    let unmounted = preflight("/api/nothing-here", "POST").await;
+    assert_eq!(unmounted.status(), Status::NotFound);
+}
@@ -0,0 +1,118 @@
+// src/tests/customers.rs
+use super::support::{bearer, service_gateway};
+use rocket::http::{ContentType, Status};
+use rocket::serde::json::{Value, json};
+
+#[rocket::async_test]
+async fn create_customer_forwards_the_customer() {
+    let (customers, client) = service_gateway(
+        |config| &mut config.customer_service_url,
+        201,
+        json!({ "id": "c1" }),
+    )
+    .await;
+
+    let response = client
+        .post("/api/customers")
+        .header(ContentType::JSON)
+        .header(bearer())
+        .body(r#"{"name":"Ada","email":"ada@example.com"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Created);
+    let forwarded = customers.only_request();
+    assert_eq!(forwarded.method, "POST");
+    assert_eq!(forwarded.path, "/api/customers");
+    assert_eq!(
+        forwarded.json(),
+        json!({ "name": "Ada", "email": "ada@example.com" })
+    );
+}
+
+#[rocket::async_test]
+async fn customer_and_activity_are_fetched() {
+    let (customers, client) = service_gateway(
+        |config| &mut config.customer_service_url,
+        200,
+        json!({ "id": "c1" }),
+    )
+    .await;
+
+    let customer = client
+        .get("/api/customers/c1")
+        .header(bearer())
+        .dispatch()
+        .await;
+    assert_eq!(customer.status(), Status::Ok);
+    let activity = client
+        .get("/api/customers/c1/activity?since=2024-01-01T00:00:00Z")
+        .header(bearer())
+        .dispatch()
+        .await;
+    assert_eq!(activity.status(), Status::Ok);
+
+    let paths: Vec<_> = customers
+        .requests()
+        .into_iter()
+        .map(|request| request.path)
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "/api/customers/c1",
+            "/api/customers/c1/activity?since=2024-01-01T00%3A00%3A00Z"
+        ]
+    );
+}
+
+#[rocket::async_test]
+async fn activity_with_an_invalid_since_is_rejected_before_proxying() {
+    let (customers, client) =
+        service_gateway(|config| &mut config.customer_service_url, 200, json!([])).await;
+
+    let response = client
+        .get("/api/customers/c1/activity?since=yesterday")
+        .header(bearer())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+    assert!(customers.requests().is_empty());
+}
+
+#[rocket::async_test]
+async fn missing_customer_keeps_the_upstream_404() {
+    let (customers, client) = service_gateway(
+        |config| &mut config.customer_service_url,
+        404,
+        json!({ "message": "No such customer" }),
+    )
+    .await;
+
+    let response = client
+        .get("/api/customers/c9")
+        .header(bearer())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    let body = response.into_json::<Value>().await.expect("JSON error");
+    assert_eq!(body["message"], "No such customer");
+    assert_eq!(customers.requests().len(), 1);
+}
+
+#[rocket::async_test]
+async fn customer_routes_require_a_token() {
+    let (customers, client) = service_gateway(
+        |config| &mut config.customer_service_url,
+        200,
+        json!({ "id": "c1" }),
+    )
+    .await;
+
+    let response = client.get("/api/customers/c1").dispatch().await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert!(customers.requests().is_empty());
+}
@@ -0,0 +1,118 @@
+// src/tests/grpc.rs
+use super::support::{bearer, gateway};
+use crate::services::grpcweb::Reply;
+use rocket::http::{ContentType, Status};
+
+fn grpc_web() -> ContentType {
+    ContentType::new("application", "grpc-web+proto")
+}
+
+#[test]
+fn trailers_are_framed_after_the_messages() {
+    let reply = Reply {
+        messages: vec![0, 0, 0, 0, 1, 42],
+        trailers: vec![("grpc-status".into(), "0".into())],
+    };
+
+    let mut expected = vec![0, 0, 0, 0, 1, 42, 0x80, 0, 0, 0, 16];
+    expected.extend(b"grpc-status: 0\r\n");
+    assert_eq!(reply.into_body(), expected);
+}
+
+#[rocket::async_test]
+async fn grpc_web_routes_are_absent_without_an_upstream() {
+    let client = gateway(|config| config.grpc_web_url = None).await;
+
+    let response = client
+        .post("/api/grpc/profile.v1.Profiles/Get")
+        .header(grpc_web())
+        .header(bearer())
+        .body([0, 0, 0, 0, 0])
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[cfg(feature = "grpc-web")]
+#[rocket::async_test]
+async fn unary_calls_are_relayed_as_grpc_over_one_connection() {
+    use rocket::http::Header;
+    use tokio::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let url = format!("http://{}", listener.local_addr().expect("address"));
+    // Only one connection is ever accepted, so a second call needs it pooled.
+    // The connection is driven on its own while each call is answered.
+    let (calls, mut received_calls) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.expect("connection");
+        let mut connection = h2::server::handshake(stream).await.expect("handshake");
+        while let Some(request) = connection.accept().await {
+            let (request, mut respond) = request.expect("valid request");
+            let calls = calls.clone();
+            tokio::spawn(async move {
+                let (head, mut body) = request.into_parts();
+                let mut received = Vec::new();
+                while let Some(chunk) = body.data().await {
+                    received.extend_from_slice(&chunk.expect("request data"));
+                }
+
+                let response = http::Response::builder()
+                    .header("content-type", "application/grpc+proto")
+                    .body(())
+                    .expect("response");
+                let mut send = respond.send_response(response, false).expect("headers");
+                send.send_data(bytes::Bytes::from_static(&[0, 0, 0, 0, 1, 7]), false)
+                    .expect("data");
+                let mut trailers = http::HeaderMap::new();
+                trailers.insert("grpc-status", "0".parse().expect("status"));
+                send.send_trailers(trailers).expect("trailers");
+                let _ = calls.send((head, received));
+            });
+        }
+    });
+
+    let client = gateway(|config| {
+        config.grpc_web_url = Some(url);
+        config.grpc_web_metadata = vec!["x-client-version".into()];
+        config.gateway_request_timeout = std::time::Duration::from_secs(5);
+    })
+    .await;
+    for _ in 0..2 {
+        let response = client
+            .post("/api/grpc/profile.v1.Profiles/Get")
+            .header(grpc_web())
+            .header(bearer())
+            .header(Header::new("X-Grpc-Web", "1"))
+            .header(Header::new("X-Client-Version", "2.1"))
+            .header(Header::new("X-Api-Key", "gateway-key"))
+            .header(Header::new("X-Tenant-Id", "forged"))
+            .header(Header::new("X-Real-IP", "10.0.0.1"))
+            .body([0, 0, 0, 0, 1, 42])
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let body = response.into_bytes().await.expect("body");
+        let mut expected = vec![0, 0, 0, 0, 1, 7, 0x80, 0, 0, 0, 16];
+        expected.extend(b"grpc-status: 0\r\n");
+        assert_eq!(body, expected);
+    }
+
+    let mut calls = Vec::new();
+    while let Ok(call) = received_calls.try_recv() {
+        calls.push(call);
+    }
+    assert_eq!(calls.len(), 2);
+    for (head, received) in calls {
+        assert_eq!(head.uri.path(), "/profile.v1.Profiles/Get");
+        assert_eq!(head.headers["content-type"], "application/grpc+proto");
+        assert!(head.headers.contains_key("authorization"));
+        assert!(head.headers.contains_key("x-request-id"));
+        assert_eq!(head.headers["x-client-version"], "2.1");
+        for name in ["x-grpc-web", "x-api-key", "x-tenant-id", "x-real-ip"] {
+            assert!(!head.headers.contains_key(name), "{} was forwarded", name);
+        }
+        assert_eq!(received, vec![0, 0, 0, 0, 1, 42]);
+    }
+}
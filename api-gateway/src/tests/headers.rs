@@ -0,0 +1,179 @@
+// src/tests/headers.rs
+use super::support::{MockUpstream, gateway};
+use crate::config::app::AppConfig;
+use crate::services::headers::{DuplicateHeaderMode, OriginPolicy};
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::serde::json::{Value, json};
+
+async fn login_with<'c>(
+    client: &'c Client,
+    headers: &[(&'static str, &'static str)],
+) -> LocalResponse<'c> {
+    let mut request = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"secret"}"#);
+    for (name, value) in headers {
+        request = request.header(Header::new(*name, *value));
+    }
+    request.dispatch().await
+}
+
+#[rocket::async_test]
+async fn request_that_already_passed_this_gateway_is_508() {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.gateway_id = "gw-1".into();
+    })
+    .await;
+
+    let response = login_with(&client, &[("Via", "1.1 edge, 1.1 gw-1, 1.1 mesh")]).await;
+
+    assert_eq!(response.status(), Status::LoopDetected);
+    let body = response.into_json::<Value>().await.expect("JSON error");
+    assert_eq!(body["status"], 508);
+    assert!(users.requests().is_empty());
+}
+
+#[rocket::async_test]
+async fn via_chain_past_the_hop_limit_is_508() {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.max_proxy_hops = 2;
+    })
+    .await;
+
+    let response = login_with(&client, &[("Via", "1.1 a"), ("Via", "1.1 b")]).await;
+
+    assert_eq!(response.status(), Status::LoopDetected);
+    assert!(users.requests().is_empty());
+}
+
+#[rocket::async_test]
+async fn via_chain_is_forwarded_with_this_gateway_appended() {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.gateway_id = "gw-1".into();
+    })
+    .await;
+
+    let response = login_with(&client, &[("Via", "1.1 edge")]).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        users.only_request().headers.get("via").map(String::as_str),
+        Some("1.1 edge, 1.1 gw-1")
+    );
+}
+
+#[rocket::async_test]
+async fn gateway_api_key_is_not_forwarded() {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = login_with(&client, &[("X-Api-Key", "client-key"), ("X-Trace", "a")]).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let headers = users.only_request().headers;
+    assert!(!headers.contains_key("x-api-key"));
+    assert_eq!(headers["x-trace"], "a");
+}
+
+/// The `x-trace` and `cookie` values the user service gets for a login
+/// sending each of them twice
+async fn forwarded_duplicates(mode: DuplicateHeaderMode) -> (String, String) {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.duplicate_header_mode = mode;
+    })
+    .await;
+
+    let response = login_with(
+        &client,
+        &[
+            ("X-Trace", "a"),
+            ("X-Trace", "b"),
+            ("Cookie", "c=1"),
+            ("Cookie", "d=2"),
+        ],
+    )
+    .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let headers = users.only_request().headers;
+    (headers["x-trace"].clone(), headers["cookie"].clone())
+}
+
+#[rocket::async_test]
+async fn duplicate_headers_keep_the_first_value() {
+    let forwarded = forwarded_duplicates(DuplicateHeaderMode::FirstWins).await;
+    assert_eq!(forwarded, ("a".into(), "c=1".into()));
+}
+
+#[rocket::async_test]
+async fn duplicate_headers_keep_the_last_value() {
+    let forwarded = forwarded_duplicates(DuplicateHeaderMode::LastWins).await;
+    assert_eq!(forwarded, ("b".into(), "d=2".into()));
+}
+
+#[rocket::async_test]
+async fn duplicate_headers_are_combined() {
+    let forwarded = forwarded_duplicates(DuplicateHeaderMode::Combine).await;
+    assert_eq!(forwarded, ("a, b".into(), "c=1; d=2".into()));
+}
+
+/// The `Origin` the user service gets for a login under `policy`, with the
+/// client sending `origin`
+async fn forwarded_origin(policy: OriginPolicy, origin: Option<&'static str>) -> Option<String> {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.origin_policy = policy;
+    })
+    .await;
+
+    let headers: Vec<_> = origin
+        .map(|origin| ("Origin", origin))
+        .into_iter()
+        .collect();
+    assert_eq!(login_with(&client, &headers).await.status(), Status::Ok);
+    users.only_request().headers.get("origin").cloned()
+}
+
+#[rocket::async_test]
+async fn origin_is_stripped_by_default() {
+    let defaults = AppConfig::from_env().expect("default configuration");
+    assert_eq!(defaults.origin_policy, OriginPolicy::Strip);
+
+    let forwarded = forwarded_origin(OriginPolicy::Strip, Some("https://app.example.com")).await;
+    assert_eq!(forwarded, None);
+}
+
+#[rocket::async_test]
+async fn origin_is_forwarded_when_configured() {
+    let forwarded = forwarded_origin(OriginPolicy::Forward, Some("https://app.example.com")).await;
+    assert_eq!(forwarded.as_deref(), Some("https://app.example.com"));
+}
+
+#[rocket::async_test]
+async fn origin_is_rewritten_when_configured() {
+    let policy: OriginPolicy = "rewrite:https://gateway.example.com"
+        .parse()
+        .expect("policy");
+
+    let replaced = forwarded_origin(policy.clone(), Some("https://app.example.com")).await;
+    assert_eq!(replaced.as_deref(), Some("https://gateway.example.com"));
+    let added = forwarded_origin(policy, None).await;
+    assert_eq!(added.as_deref(), Some("https://gateway.example.com"));
+}
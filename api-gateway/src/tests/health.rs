@@ -0,0 +1,237 @@
+// src/tests/health.rs
+use super::support::{MockUpstream, closed_url, gateway, instance};
+use crate::config::app::AppConfig;
+use crate::config::live::LiveConfig;
+use crate::services::selftest::{self, Outcome};
+use crate::services::upstream::Upstreams;
+use rocket::futures::future::join_all;
+use rocket::http::{ContentType, Status};
+use rocket::serde::json::{Value, json};
+use std::time::Duration;
+
+fn every_service_at(config: &mut AppConfig, url: &str) {
+    config.user_service_url = url.into();
+    config.payments_service_url = url.into();
+    config.sales_service_url = url.into();
+    config.purchasing_service_url = url.into();
+    config.inventory_service_url = url.into();
+    config.customer_service_url = url.into();
+    // Dialled over WebSocket, as in a real deployment
+    config.notifications_service_url = url.replace("http://", "ws://");
+}
+
+#[rocket::async_test]
+async fn readiness_reuses_the_last_probe_within_the_cache_ttl() {
+    let upstream = MockUpstream::start(200, json!({ "status": "ok" })).await;
+    let url = upstream.url.clone();
+    let client = gateway(|config| {
+        every_service_at(config, &url);
+        config.health_cache_ttl = Duration::from_secs(60);
+    })
+    .await;
+
+    let first = client.get("/api/health/ready").dispatch().await;
+    assert_eq!(first.status(), Status::Ok);
+    let body = first.into_json::<Value>().await.expect("JSON body");
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["upstreams"]["sales"], "up");
+    assert_eq!(body["upstreams"]["notifications"], "up");
+    let probed = upstream.health_checks();
+
+    for _ in 0..5 {
+        let again = client.get("/api/health/ready").dispatch().await;
+        assert_eq!(again.status(), Status::Ok);
+    }
+    assert_eq!(upstream.health_checks(), probed);
+}
+
+#[rocket::async_test]
+async fn simultaneous_readiness_checks_share_one_probe_round() {
+    let upstream =
+        MockUpstream::slow(200, json!({ "status": "ok" }), Duration::from_millis(200)).await;
+    let url = upstream.url.clone();
+    let client = gateway(|config| {
+        every_service_at(config, &url);
+        config.health_cache_ttl = Duration::from_secs(60);
+    })
+    .await;
+    let live = client.rocket().state::<LiveConfig>().expect("live config");
+    let round = live.snapshot().upstreams.all().len();
+    let before = upstream.health_checks();
+
+    let checks = (0..20).map(|_| client.get("/api/health/ready").dispatch());
+    for response in join_all(checks).await {
+        assert_eq!(response.status(), Status::Ok);
+    }
+    assert_eq!(upstream.health_checks() - before, round);
+}
+
+#[rocket::async_test]
+async fn readiness_is_503_when_no_upstream_is_up() {
+    let url = closed_url().await;
+    let client = gateway(|config| {
+        every_service_at(config, &url);
+        config.health_cache_ttl = Duration::ZERO;
+    })
+    .await;
+
+    let response = client.get("/api/health/ready").dispatch().await;
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    let body = response.into_json::<Value>().await.expect("JSON body");
+    assert_eq!(body["status"], "unavailable");
+    assert_eq!(body["upstreams"]["users"], "down");
+}
+
+#[rocket::async_test]
+async fn open_breaker_on_a_critical_service_fails_readiness() {
+    let upstream = MockUpstream::start(200, json!({ "status": "ok" })).await;
+    let url = upstream.url.clone();
+    let client = gateway(|config| {
+        every_service_at(config, &url);
+        config.health_cache_ttl = Duration::from_secs(60);
+        config.breaker_failure_threshold = 2;
+        config.breaker_cooldown = Duration::from_secs(60);
+        config.critical_services = vec!["users".into()];
+    })
+    .await;
+
+    let ready = client.get("/api/health/ready").dispatch().await;
+    assert_eq!(ready.status(), Status::Ok);
+
+    upstream.set_status(503);
+    let login = || {
+        client
+            .post("/api/users/login")
+            .header(ContentType::JSON)
+            .body(r#"{"email":"a@example.com","password":"secret"}"#)
+            .dispatch()
+    };
+    for _ in 0..2 {
+        assert_eq!(login().await.status(), Status::ServiceUnavailable);
+    }
+
+    // The open breaker refuses calls without reaching the upstream
+    let called = upstream.requests().len();
+    let refused = login().await;
+    assert_eq!(refused.status(), Status::ServiceUnavailable);
+    assert_eq!(upstream.requests().len(), called);
+
+    // Breakers are read past the cached probes
+    let response = client.get("/api/health/ready").dispatch().await;
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    let body = response.into_json::<Value>().await.expect("JSON body");
+    assert_eq!(body["upstreams"]["users"], "open");
+    assert_eq!(body["critical"], json!(["users"]));
+}
+
+#[rocket::async_test]
+async fn open_breaker_on_another_service_only_degrades_readiness() {
+    let upstream = MockUpstream::start(200, json!({ "status": "ok" })).await;
+    let url = upstream.url.clone();
+    let client = gateway(|config| {
+        every_service_at(config, &url);
+        config.health_cache_ttl = Duration::from_secs(60);
+        config.breaker_failure_threshold = 1;
+        config.critical_services = vec!["sales".into()];
+    })
+    .await;
+    client.get("/api/health/ready").dispatch().await;
+
+    upstream.set_status(503);
+    let login = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"secret"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(login.status(), Status::ServiceUnavailable);
+
+    let response = client.get("/api/health/ready").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = response.into_json::<Value>().await.expect("JSON body");
+    assert_eq!(body["status"], "degraded");
+    assert_eq!(body["upstreams"]["users"], "open");
+    assert!(body.get("critical").is_none());
+}
+
+#[rocket::async_test]
+async fn self_test_fails_when_a_critical_service_is_down() {
+    let upstream = MockUpstream::start(200, json!({ "status": "ok" })).await;
+    let url = upstream.url.clone();
+    let closed = closed_url().await;
+    let rocket = instance(|config| {
+        every_service_at(config, &url);
+        config.user_service_url = closed;
+        config.critical_services = vec!["users".into()];
+    });
+
+    let report = selftest::run(rocket).await;
+    assert!(!report.passed());
+    let outcome = |name: &str| {
+        report
+            .checks
+            .iter()
+            .find(|check| check.name == name)
+            .map(|check| check.outcome)
+    };
+    assert_eq!(outcome("boot"), Some(Outcome::Pass));
+    assert_eq!(outcome("configuration"), Some(Outcome::Pass));
+    assert_eq!(outcome("upstream users"), Some(Outcome::Fail));
+    assert_eq!(outcome("upstream sales"), Some(Outcome::Pass));
+    assert!(report.to_string().contains("Self-test FAILED"));
+}
+
+#[rocket::async_test]
+async fn blocked_runtime_fails_liveness() {
+    let client = gateway(|config| {
+        config.liveness_max_scheduling_delay = Duration::from_millis(50);
+    })
+    .await;
+
+    let healthy = client.get("/api/health/live").dispatch().await;
+    assert_eq!(healthy.status(), Status::Ok);
+
+    // A task hogging the runtime thread, as blocking code in a handler would
+    let blocker = tokio::spawn(async {
+        loop {
+            std::thread::sleep(Duration::from_millis(200));
+            tokio::task::yield_now().await;
+        }
+    });
+
+    let response = client.get("/api/health/live").dispatch().await;
+    blocker.abort();
+
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    let body = response.into_json::<Value>().await.expect("JSON body");
+    assert_eq!(body["status"], "degraded");
+    assert_eq!(body["threshold_ms"], 50);
+    assert!(body["scheduling_delay_ms"].as_f64().expect("delay") > 50.0);
+}
+
+#[test]
+fn unknown_critical_service_is_a_config_error() {
+    let mut config = AppConfig::from_env().expect("default configuration");
+    config.critical_services = vec!["users".into(), "user".into()];
+    let error = Upstreams::from_config(&config).expect_err("misspelt service");
+    assert!(error.to_string().contains("'user'"), "{}", error);
+
+    config.critical_services = vec!["users".into(), "notifications".into()];
+    assert!(Upstreams::from_config(&config).is_ok());
+}
+
+#[rocket::async_test]
+async fn websocket_upstream_can_be_a_critical_service() {
+    let upstream = MockUpstream::start(200, json!({ "status": "ok" })).await;
+    let url = upstream.url.clone();
+    let client = gateway(|config| {
+        every_service_at(config, &url);
+        config.critical_services = vec!["notifications".into()];
+    })
+    .await;
+
+    let response = client.get("/api/health/ready").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = response.into_json::<Value>().await.expect("JSON body");
+    assert_eq!(body["status"], "ready");
+}
@@ -0,0 +1,84 @@
+// src/tests/idempotency.rs
+use super::support::{bearer_for, service_gateway};
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::serde::json::{Value, json};
+
+const ORDER: &str = r#"{"customer_id":"c1","items":[{"product_id":"p1","quantity":1}]}"#;
+
+async fn create_order<'c>(client: &'c Client, user: &str, body: &str) -> LocalResponse<'c> {
+    client
+        .post("/api/sales/orders")
+        .header(ContentType::JSON)
+        .header(bearer_for(user))
+        .header(Header::new("Idempotency-Key", "k1"))
+        .body(body)
+        .dispatch()
+        .await
+}
+
+#[rocket::async_test]
+async fn repeated_key_replays_the_recorded_response() {
+    let (sales, client) = service_gateway(
+        |config| &mut config.sales_service_url,
+        201,
+        json!({ "id": "o1" }),
+    )
+    .await;
+
+    let first = create_order(&client, "u1", ORDER).await;
+    assert_eq!(first.status(), Status::Created);
+    assert!(first.headers().get_one("Idempotent-Replayed").is_none());
+
+    let again = create_order(&client, "u1", ORDER).await;
+    assert_eq!(again.status(), Status::Created);
+    assert_eq!(again.headers().get_one("Idempotent-Replayed"), Some("true"));
+    assert_eq!(sales.requests().len(), 1);
+}
+
+#[rocket::async_test]
+async fn callers_do_not_share_idempotency_keys() {
+    let (sales, client) = service_gateway(
+        |config| &mut config.sales_service_url,
+        201,
+        json!({ "id": "o1" }),
+    )
+    .await;
+
+    assert_eq!(
+        create_order(&client, "u1", ORDER).await.status(),
+        Status::Created
+    );
+    let other = create_order(&client, "u2", ORDER).await;
+    assert_eq!(other.status(), Status::Created);
+    assert!(other.headers().get_one("Idempotent-Replayed").is_none());
+    assert_eq!(sales.requests().len(), 2);
+}
+
+#[rocket::async_test]
+async fn reused_key_with_another_body_is_a_conflict() {
+    let (sales, client) = service_gateway(
+        |config| &mut config.sales_service_url,
+        201,
+        json!({ "id": "o1" }),
+    )
+    .await;
+
+    assert_eq!(
+        create_order(&client, "u1", ORDER).await.status(),
+        Status::Created
+    );
+    let changed = create_order(
+        &client,
+        "u1",
+        r#"{"customer_id":"c1","items":[{"product_id":"p1","quantity":5}]}"#,
+    )
+    .await;
+    assert_eq!(changed.status(), Status::Conflict);
+    let body = changed.into_json::<Value>().await.expect("JSON error");
+    assert_eq!(
+        body["message"],
+        "Conflict: This Idempotency-Key was already used with a different request body"
+    );
+    assert_eq!(sales.requests().len(), 1);
+}
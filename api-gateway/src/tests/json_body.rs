@@ -0,0 +1,76 @@
+// src/tests/json_body.rs
+use super::support::{MockUpstream, gateway};
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{Value, json};
+
+async fn limited_gateway() -> (MockUpstream, Client) {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.max_json_depth = 8;
+        config.max_json_keys = 16;
+    })
+    .await;
+    (users, client)
+}
+
+async fn login(client: &Client, extra: Value) -> (Status, Value) {
+    let mut body = json!({ "email": "a@example.com", "password": "secret" });
+    body["extra"] = extra;
+    let response = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(body.to_string())
+        .dispatch()
+        .await;
+    let status = response.status();
+    (status, response.into_json().await.unwrap_or_default())
+}
+
+#[rocket::async_test]
+async fn deeply_nested_body_is_rejected_before_proxying() {
+    let (users, client) = limited_gateway().await;
+    let nested = (0..20).fold(json!(1), |inner, _| json!([inner]));
+
+    let (status, body) = login(&client, nested).await;
+
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(
+        body["message"],
+        "Bad request: JSON body exceeds the maximum nesting depth of 8"
+    );
+    assert!(users.requests().is_empty());
+}
+
+#[rocket::async_test]
+async fn body_with_too_many_keys_is_rejected_before_proxying() {
+    let (users, client) = limited_gateway().await;
+    let wide: serde_json::Map<String, Value> = (0..50)
+        .map(|key| (format!("k{}", key), json!(key)))
+        .collect();
+
+    let (status, body) = login(&client, Value::Object(wide)).await;
+
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(
+        body["message"],
+        "Bad request: JSON body exceeds the maximum of 16 keys"
+    );
+    assert!(users.requests().is_empty());
+}
+
+#[rocket::async_test]
+async fn body_within_the_limits_is_proxied() {
+    let (users, client) = limited_gateway().await;
+
+    let (status, _) = login(
+        &client,
+        json!({ "device": { "os": "linux", "tags": ["a", "b"] } }),
+    )
+    .await;
+
+    assert_eq!(status, Status::Ok);
+    assert_eq!(users.requests().len(), 1);
+}
@@ -0,0 +1,209 @@
+// src/tests/metrics.rs
+use super::support::{MockUpstream, closed_url, gateway};
+use crate::config::app::AppConfig;
+use crate::services::process::ProcessMetrics;
+use crate::services::telemetry::{self, HeaderLabel};
+use rocket::http::{ContentType, Header};
+use rocket::serde::json::json;
+
+#[test]
+fn every_metric_carries_the_environment_label() {
+    let mut config = AppConfig::from_env().expect("default configuration");
+    config.environment = "staging".into();
+
+    let recorder = telemetry::prometheus_builder(&config).build_recorder();
+    let handle = recorder.handle();
+    metrics::with_local_recorder(&recorder, || {
+        metrics::counter!("api_requests_total").increment(1);
+        metrics::gauge!("api_requests_in_flight").set(2.0);
+        metrics::histogram!("api_response_time_seconds").record(0.1);
+    });
+
+    let rendered = handle.render();
+    for name in [
+        "api_requests_total",
+        "api_requests_in_flight",
+        "api_response_time_seconds_count",
+    ] {
+        assert!(
+            rendered
+                .lines()
+                .any(|line| line.starts_with(name) && line.contains(r#"environment="staging""#)),
+            "{} is missing the label in:\n{}",
+            name,
+            rendered
+        );
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn process_metrics_are_read_from_proc() {
+    let config = AppConfig::from_env().expect("default configuration");
+    let recorder = telemetry::prometheus_builder(&config).build_recorder();
+    let handle = recorder.handle();
+    metrics::with_local_recorder(&recorder, || ProcessMetrics::default().record());
+
+    let rendered = handle.render();
+    for name in [
+        "process_resident_memory_bytes",
+        "process_open_fds",
+        "process_threads",
+        "process_cpu_seconds_total",
+        "process_uptime_seconds",
+        "process_start_time_seconds",
+    ] {
+        let value = rendered
+            .lines()
+            .find(|line| line.starts_with(name))
+            .and_then(|line| line.rsplit(' ').next())
+            .and_then(|value| value.parse::<f64>().ok());
+        assert!(value.is_some(), "{} is missing from:\n{}", name, rendered);
+    }
+}
+
+#[test]
+fn recorder_probe_needs_the_recorder_behind_the_handle() {
+    let config = AppConfig::from_env().expect("default configuration");
+
+    let recorder = telemetry::prometheus_builder(&config).build_recorder();
+    let handle = recorder.handle();
+    assert!(metrics::with_local_recorder(&recorder, || {
+        telemetry::recorder_is_working(&handle)
+    }));
+
+    let detached = telemetry::prometheus_builder(&config)
+        .build_recorder()
+        .handle();
+    assert!(!telemetry::recorder_is_working(&detached));
+}
+
+#[test]
+fn upstream_calls_are_counted_by_outcome() {
+    let config = AppConfig::from_env().expect("default configuration");
+    let recorder = telemetry::prometheus_builder(&config).build_recorder();
+    let handle = recorder.handle();
+
+    // A single-threaded runtime keeps every call on the local recorder's thread
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("runtime");
+    metrics::with_local_recorder(&recorder, || {
+        runtime.block_on(async {
+            let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+            let unreachable = closed_url().await;
+            for url in [users.url.clone(), unreachable] {
+                let client = gateway(|config| config.user_service_url = url).await;
+                client
+                    .post("/api/users/login")
+                    .header(ContentType::JSON)
+                    .body(r#"{"email":"a@example.com","password":"secret"}"#)
+                    .dispatch()
+                    .await;
+            }
+        })
+    });
+
+    let rendered = handle.render();
+    for labels in [
+        [
+            r#"service="users""#,
+            r#"outcome="success""#,
+            r#"status="200""#,
+        ],
+        [
+            r#"service="users""#,
+            r#"outcome="connect_error""#,
+            r#"status="none""#,
+        ],
+    ] {
+        assert!(
+            rendered.lines().any(|line| {
+                line.starts_with("api_upstream_requests_total")
+                    && labels.iter().all(|label| line.contains(label))
+            }),
+            "no upstream call counted with {:?} in:\n{}",
+            labels,
+            rendered
+        );
+    }
+}
+
+#[test]
+fn unlisted_header_values_are_labelled_other() {
+    let config = AppConfig::from_env().expect("default configuration");
+    let recorder = telemetry::prometheus_builder(&config).build_recorder();
+    let handle = recorder.handle();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("runtime");
+    metrics::with_local_recorder(&recorder, || {
+        runtime.block_on(async {
+            let client = gateway(|config| {
+                config.metric_label = Some(HeaderLabel {
+                    name: "client_app".into(),
+                    header: "X-Client-App".into(),
+                    allowed: vec!["web".into(), "ios".into()],
+                });
+            })
+            .await;
+            for app in [Some("web"), Some(" IOS "), Some("scraper-42"), None] {
+                let mut request = client.get("/api/health");
+                if let Some(app) = app {
+                    request = request.header(Header::new("X-Client-App", app));
+                }
+                request.dispatch().await;
+            }
+        })
+    });
+
+    let counted = |value: &str| {
+        let label = format!(r#"client_app="{}""#, value);
+        handle
+            .render()
+            .lines()
+            .find(|line| line.starts_with("api_requests_total") && line.contains(&label))
+            .and_then(|line| line.rsplit(' ').next())
+            .and_then(|count| count.parse::<u64>().ok())
+    };
+    assert_eq!(counted("web"), Some(1));
+    assert_eq!(counted("ios"), Some(1));
+    assert_eq!(counted("other"), Some(2));
+    assert_eq!(counted("scraper-42"), None);
+}
+
+#[cfg(feature = "statsd")]
+#[test]
+fn statsd_backend_sends_prefixed_tagged_metrics() {
+    let sink = std::net::UdpSocket::bind("127.0.0.1:0").expect("bind StatsD sink");
+    sink.set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .expect("read timeout");
+
+    let mut config = AppConfig::from_env().expect("default configuration");
+    config.environment = "staging".into();
+    config.statsd_host = "127.0.0.1".into();
+    config.statsd_port = sink.local_addr().expect("sink address").port();
+    config.statsd_prefix = "gw".into();
+
+    let recorder = telemetry::statsd_recorder(&config).expect("StatsD recorder");
+    metrics::with_local_recorder(&recorder, || {
+        metrics::counter!("api_requests_total").increment(3);
+    });
+    // Dropping the recorder flushes its buffered sink
+    drop(recorder);
+
+    let mut packet = [0; 1024];
+    let length = sink.recv(&mut packet).expect("StatsD packet");
+    let packet = String::from_utf8_lossy(&packet[..length]);
+    assert!(
+        packet
+            .lines()
+            .any(|line| line.starts_with("gw.api_requests_total:3|c")
+                && line.contains("environment:staging")),
+        "unexpected StatsD packet:\n{}",
+        packet
+    );
+}
@@ -0,0 +1,31 @@
+// src/tests/mod.rs
+// End-to-end tests: a gateway built with `build()` in front of mock upstreams
+mod admin;
+mod auth;
+mod chaos;
+mod config;
+mod cors;
+mod customers;
+mod grpc;
+mod headers;
+mod health;
+mod idempotency;
+mod json_body;
+mod metrics;
+mod notifications;
+mod overrides;
+mod paths;
+mod purchasing;
+mod quota;
+mod relay;
+mod reload;
+mod required;
+mod sales;
+mod stats;
+mod store;
+mod streaming;
+mod support;
+mod tenants;
+mod timing;
+mod users;
+mod waf;
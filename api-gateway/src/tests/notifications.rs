@@ -0,0 +1,87 @@
+// src/tests/notifications.rs
+use super::support::{MockUpstream, bearer, gateway};
+use rocket::http::{Header, Status};
+use rocket::serde::json::json;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+
+/// A WebSocket upstream that accepts connections but never answers the
+/// handshake
+async fn silent_upstream() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let url = format!("ws://{}", listener.local_addr().expect("address"));
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            held.push(socket);
+        }
+    });
+    url
+}
+
+#[rocket::async_test]
+async fn upstream_handshake_is_bounded_by_the_connect_timeout() {
+    let url = silent_upstream().await;
+    let client = gateway(|config| {
+        config.notifications_service_url = url;
+        config.upstream_connect_timeout = Duration::from_millis(200);
+        config.gateway_request_timeout = Duration::from_secs(30);
+    })
+    .await;
+
+    let started = Instant::now();
+    let response = client
+        .get("/api/notifications/ws")
+        .header(bearer())
+        .header(Header::new("Connection", "Upgrade"))
+        .header(Header::new("Upgrade", "websocket"))
+        .header(Header::new("Sec-WebSocket-Version", "13"))
+        .header(Header::new("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[rocket::async_test]
+async fn anonymous_sockets_are_refused_at_the_gateway() {
+    let url = silent_upstream().await;
+    let client = gateway(|config| config.notifications_service_url = url).await;
+
+    let response = client
+        .get("/api/notifications/ws")
+        .header(Header::new("Connection", "Upgrade"))
+        .header(Header::new("Upgrade", "websocket"))
+        .header(Header::new("Sec-WebSocket-Version", "13"))
+        .header(Header::new("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn internal_callers_connect_with_an_api_key() {
+    let notifications = MockUpstream::start(200, json!({})).await;
+    let url = notifications.url.replacen("http://", "ws://", 1);
+    let client = gateway(|config| {
+        config.notifications_service_url = url;
+        config.api_keys = vec!["k1".into()];
+    })
+    .await;
+
+    // The mock isn't a WebSocket server, so the dial fails once it's reached
+    let response = client
+        .get("/api/notifications/ws")
+        .header(Header::new("X-Api-Key", "k1"))
+        .header(Header::new("Connection", "Upgrade"))
+        .header(Header::new("Upgrade", "websocket"))
+        .header(Header::new("Sec-WebSocket-Version", "13"))
+        .header(Header::new("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+        .dispatch()
+        .await;
+
+    assert_ne!(response.status(), Status::Unauthorized);
+    assert_eq!(notifications.only_request().path, "/api/notifications/ws");
+}
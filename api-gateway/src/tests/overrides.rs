@@ -0,0 +1,40 @@
+// src/tests/overrides.rs
+use super::support::{MockUpstream, bearer, gateway};
+use rocket::http::{Header, Status};
+use rocket::serde::json::json;
+
+#[rocket::async_test]
+async fn post_with_method_override_is_proxied_as_that_method() {
+    let users = MockUpstream::start(200, json!({ "deleted": true })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = client
+        .post("/api/proxy/users/api/users/u1")
+        .header(bearer())
+        .header(Header::new("X-HTTP-Method-Override", "delete"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let forwarded = users.only_request();
+    assert_eq!(forwarded.method, "DELETE");
+    assert_eq!(forwarded.path, "/api/users/u1");
+    assert!(!forwarded.headers.contains_key("x-http-method-override"));
+}
+
+#[rocket::async_test]
+async fn method_override_outside_the_allowed_set_is_rejected() {
+    let users = MockUpstream::start(200, json!({})).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = client
+        .post("/api/users/logout")
+        .header(Header::new("X-HTTP-Method-Override", "GET"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+    assert!(users.requests().is_empty());
+}
@@ -0,0 +1,75 @@
+// src/tests/paths.rs
+use super::support::{MockUpstream, bearer, gateway};
+use crate::middleware::TrailingSlash;
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{Value, json};
+
+/// Register twice with one idempotency key, the second time with a
+/// trailing slash, and count the requests that reached the user service
+async fn registrations_proxied(trailing_slash: TrailingSlash) -> usize {
+    let users = MockUpstream::start(201, json!({ "id": "u1" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.trailing_slash = trailing_slash;
+    })
+    .await;
+
+    for path in ["/api/users/register", "/api/users/register/"] {
+        assert_eq!(register(&client, path).await, Status::Created);
+    }
+    users.requests().len()
+}
+
+async fn register(client: &Client, path: &str) -> Status {
+    client
+        .post(path.to_string())
+        .header(ContentType::JSON)
+        .header(Header::new("Idempotency-Key", "k1"))
+        .body(r#"{"name":"Ada","email":"ada@example.com","password":"correct-horse"}"#)
+        .dispatch()
+        .await
+        .status()
+}
+
+#[rocket::async_test]
+async fn stripped_trailing_slash_shares_the_idempotency_scope() {
+    assert_eq!(registrations_proxied(TrailingSlash::Strip).await, 1);
+}
+
+#[rocket::async_test]
+async fn kept_trailing_slash_is_a_distinct_path() {
+    assert_eq!(registrations_proxied(TrailingSlash::Keep).await, 2);
+}
+
+#[rocket::async_test]
+async fn unknown_api_paths_get_a_descriptive_404() {
+    let client = gateway(|config| {
+        config.environment = "development".into();
+        config.expose_version = false;
+    })
+    .await;
+
+    let response = client
+        .get("/api/nonexistent")
+        .header(bearer())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+    let body = response.into_json::<Value>().await.expect("JSON body");
+    assert_eq!(
+        body["message"],
+        "Not found: no API route for GET /api/nonexistent"
+    );
+    let details = body["details"].as_str().expect("details");
+    assert!(details.contains("/api/users"), "{}", details);
+    assert!(details.contains("/api/health"), "{}", details);
+
+    // A route answering 404 itself keeps the plain message
+    let hidden = client.get("/api/version").dispatch().await;
+    assert_eq!(hidden.status(), Status::NotFound);
+    let body = hidden.into_json::<Value>().await.expect("JSON body");
+    assert_eq!(body["message"], "Not Found");
+    assert!(body.get("details").is_none());
+}
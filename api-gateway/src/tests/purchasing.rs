@@ -0,0 +1,124 @@
+// src/tests/purchasing.rs
+use super::support::{bearer, service_gateway};
+use rocket::http::{ContentType, Status};
+use rocket::serde::json::{Value, json};
+
+#[rocket::async_test]
+async fn create_purchase_order_forwards_the_order() {
+    let (purchasing, client) = service_gateway(
+        |config| &mut config.purchasing_service_url,
+        201,
+        json!({ "id": "po1" }),
+    )
+    .await;
+
+    let response = client
+        .post("/api/purchasing/orders")
+        .header(ContentType::JSON)
+        .header(bearer())
+        .body(r#"{"supplier_id":"s1","items":[{"product_id":"p1","quantity":10}]}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Created);
+    let forwarded = purchasing.only_request();
+    assert_eq!(forwarded.method, "POST");
+    assert_eq!(forwarded.path, "/api/purchasing/orders");
+    assert_eq!(
+        forwarded.json(),
+        json!({ "supplier_id": "s1", "items": [{ "product_id": "p1", "quantity": 10 }] })
+    );
+}
+
+#[rocket::async_test]
+async fn purchase_order_without_a_supplier_is_rejected_before_proxying() {
+    let (purchasing, client) = service_gateway(
+        |config| &mut config.purchasing_service_url,
+        201,
+        json!({ "id": "po1" }),
+    )
+    .await;
+
+    let response = client
+        .post("/api/purchasing/orders")
+        .header(ContentType::JSON)
+        .header(bearer())
+        .body(r#"{"supplier_id":" ","items":[{"product_id":"p1","quantity":10}]}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+    assert!(purchasing.requests().is_empty());
+}
+
+#[rocket::async_test]
+async fn purchase_orders_are_fetched_and_listed() {
+    let (purchasing, client) = service_gateway(
+        |config| &mut config.purchasing_service_url,
+        200,
+        json!({ "id": "po1" }),
+    )
+    .await;
+
+    let got = client
+        .get("/api/purchasing/orders/po1")
+        .header(bearer())
+        .dispatch()
+        .await;
+    assert_eq!(got.status(), Status::Ok);
+    let listed = client
+        .get("/api/purchasing/orders?status=draft&supplier_id=s1")
+        .header(bearer())
+        .dispatch()
+        .await;
+    assert_eq!(listed.status(), Status::Ok);
+
+    let paths: Vec<_> = purchasing
+        .requests()
+        .into_iter()
+        .map(|request| request.path)
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "/api/purchasing/orders/po1",
+            "/api/purchasing/orders?status=draft&supplier_id=s1"
+        ]
+    );
+}
+
+#[rocket::async_test]
+async fn purchasing_errors_keep_the_upstream_status() {
+    let (purchasing, client) = service_gateway(
+        |config| &mut config.purchasing_service_url,
+        409,
+        json!({ "message": "Order already received" }),
+    )
+    .await;
+
+    let response = client
+        .get("/api/purchasing/orders/po1")
+        .header(bearer())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Conflict);
+    let body = response.into_json::<Value>().await.expect("JSON error");
+    assert_eq!(body["message"], "Order already received");
+    assert_eq!(purchasing.requests().len(), 1);
+}
+
+#[rocket::async_test]
+async fn purchasing_routes_require_a_token() {
+    let (purchasing, client) = service_gateway(
+        |config| &mut config.purchasing_service_url,
+        200,
+        json!({ "id": "po1" }),
+    )
+    .await;
+
+    let response = client.get("/api/purchasing/orders/po1").dispatch().await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert!(purchasing.requests().is_empty());
+}
@@ -0,0 +1,77 @@
+// src/tests/quota.rs
+use super::support::gateway;
+use crate::guards::api_key::API_KEY_HEADER;
+use crate::services::ratelimit::{Denied, RateLimit, RateLimiter};
+use crate::services::store::MemoryStore;
+use rocket::http::{Header, Status};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Sleep until `offset` into the next `window` aligned to the epoch
+async fn sleep_past_boundary(window: Duration, offset: Duration) {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let into = Duration::from_millis((now.as_millis() % window.as_millis()) as u64);
+    tokio::time::sleep(window - into + offset).await;
+}
+
+#[rocket::async_test]
+async fn limits_slide_across_window_boundaries() {
+    let limiter = RateLimiter::new(Arc::new(MemoryStore::default()));
+    let limit = RateLimit {
+        limit: 2,
+        window: Duration::from_secs(1),
+        fail_open: false,
+    };
+
+    // Start early in a window, so the burst stays within it
+    sleep_past_boundary(limit.window, Duration::from_millis(50)).await;
+    assert_eq!(limiter.hit("k1", limit).await, Ok(1));
+    assert_eq!(limiter.hit("k1", limit).await, Ok(0));
+
+    // A fixed window would start over here; the burst still weighs on it
+    sleep_past_boundary(limit.window, Duration::from_millis(50)).await;
+    let Err(Denied::Limited(wait)) = limiter.hit("k1", limit).await else {
+        panic!("expected the burst to throttle this hit");
+    };
+    assert!(wait > Duration::ZERO && wait <= limit.window);
+
+    // Once that burst has slid out of the window, requests pass again
+    sleep_past_boundary(limit.window, Duration::from_millis(50)).await;
+    assert!(limiter.hit("k1", limit).await.is_ok());
+}
+
+#[rocket::async_test]
+async fn api_key_quota_is_enforced_with_rate_limit_headers() {
+    let client = gateway(|config| {
+        config.api_keys = vec!["k1".into(), "k2".into()];
+        config.quota.limit = 2;
+    })
+    .await;
+
+    let maintenance = |key: &'static str| {
+        client
+            .get("/api/admin/maintenance")
+            .header(Header::new(API_KEY_HEADER, key))
+            .dispatch()
+    };
+
+    assert_eq!(maintenance("k1").await.status(), Status::Ok);
+    assert_eq!(maintenance("k1").await.status(), Status::Ok);
+
+    let exhausted = maintenance("k1").await;
+    assert_eq!(exhausted.status(), Status::TooManyRequests);
+    assert_eq!(
+        exhausted.headers().get_one("X-RateLimit-Remaining"),
+        Some("0")
+    );
+    let reset: u64 = exhausted
+        .headers()
+        .get_one("X-RateLimit-Reset")
+        .and_then(|value| value.parse().ok())
+        .expect("reset seconds");
+    // Requests over the limit still count, so they decay in the next window
+    assert!(reset > 0 && reset <= 2 * 86_400);
+
+    // Quotas are per key
+    assert_eq!(maintenance("k2").await.status(), Status::Ok);
+}
@@ -0,0 +1,75 @@
+// src/tests/relay.rs
+// Property tests for how upstream answers are turned into client responses
+use crate::config::app::AppConfig;
+use crate::services::proxy::relay;
+use proptest::prelude::*;
+use rocket::serde::json::Value;
+
+fn config(wrap_upstream_errors: bool) -> AppConfig {
+    let mut config = AppConfig::from_env().expect("default configuration");
+    config.wrap_upstream_errors = wrap_upstream_errors;
+    config
+}
+
+fn json_value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        any::<f64>()
+            .prop_filter("finite", |n| n.is_finite())
+            .prop_map(Value::from),
+        ".*".prop_map(Value::from),
+    ];
+    leaf.prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..8).prop_map(Value::from),
+            prop::collection::hash_map(".*", inner, 0..8)
+                .prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+proptest! {
+    #[test]
+    fn any_upstream_answer_gets_a_valid_status_and_json_body(
+        code in any::<u16>(),
+        bytes in prop::collection::vec(any::<u8>(), 0..256),
+        wrap in any::<bool>(),
+    ) {
+        let (status, body) = match relay(&config(wrap), "User service", code, &bytes) {
+            Ok((status, body)) => (status, body),
+            Err(error) => (error.0, serde_json::to_value(&error.1.0).expect("error body")),
+        };
+        prop_assert!((100..=599).contains(&status.code));
+        prop_assert!(serde_json::to_string(&body).is_ok());
+    }
+
+    #[test]
+    fn json_bodies_with_known_statuses_are_relayed_unchanged(
+        code in 100u16..=599,
+        body in json_value(),
+    ) {
+        let known = rocket::http::Status::from_code(code);
+        let bytes = serde_json::to_vec(&body).expect("serializable body");
+
+        let (status, relayed) = relay(&config(false), "User service", code, &bytes)
+            .expect("JSON body is relayed");
+        prop_assert_eq!(status.code, known.map_or(500, |status| status.code));
+        prop_assert_eq!(relayed, body);
+    }
+
+    #[test]
+    fn wrapped_error_bodies_keep_the_upstream_body(
+        code in 400u16..=599,
+        body in json_value(),
+    ) {
+        let bytes = serde_json::to_vec(&body).expect("serializable body");
+
+        let (status, relayed) = relay(&config(true), "User service", code, &bytes)
+            .expect("JSON body is relayed");
+        prop_assert!(status.code >= 400);
+        prop_assert_eq!(&relayed["status"], &Value::from(status.code));
+        prop_assert_eq!(&relayed["upstream"], &body);
+    }
+}
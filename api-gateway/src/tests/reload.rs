@@ -0,0 +1,83 @@
+// src/tests/reload.rs
+use super::support::{MockUpstream, gateway};
+use crate::config::app::AppConfig;
+use crate::config::live::LiveConfig;
+use crate::services::store::StoreBackend;
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn login(client: &Client) -> Status {
+    client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"secret"}"#)
+        .dispatch()
+        .await
+        .status()
+}
+
+#[rocket::async_test]
+async fn reload_keeps_the_state_of_unchanged_upstreams() {
+    let failing = MockUpstream::start(503, json!({ "message": "down" })).await;
+    let url = failing.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.breaker_failure_threshold = 1;
+        config.breaker_cooldown = Duration::from_secs(60);
+    })
+    .await;
+    let live = client.rocket().state::<LiveConfig>().expect("live config");
+
+    assert_eq!(login(&client).await, Status::ServiceUnavailable);
+    let called = failing.requests().len();
+
+    // Same settings: the open breaker survives the reload
+    let before = live.snapshot();
+    let after = live.replace(before.config.clone()).expect("reloaded");
+    assert!(Arc::ptr_eq(&before.upstreams.users, &after.upstreams.users));
+    assert_eq!(login(&client).await, Status::ServiceUnavailable);
+    assert_eq!(failing.requests().len(), called);
+
+    // New URLs: only that upstream starts afresh
+    let healthy = MockUpstream::start(200, json!({ "token": "t1" })).await;
+    let mut config = after.config.clone();
+    config.user_service_url = healthy.url.clone();
+    let moved = live.replace(config).expect("reloaded");
+    assert!(!Arc::ptr_eq(&after.upstreams.users, &moved.upstreams.users));
+    assert!(Arc::ptr_eq(&after.upstreams.sales, &moved.upstreams.sales));
+    assert_eq!(login(&client).await, Status::Ok);
+}
+
+#[rocket::async_test]
+async fn reload_with_new_client_settings_rebuilds_every_upstream() {
+    let client = gateway(|_| {}).await;
+    let live = client.rocket().state::<LiveConfig>().expect("live config");
+
+    let before = live.snapshot();
+    let mut config = before.config.clone();
+    config.upstream_connect_timeout += Duration::from_secs(1);
+    let after = live.replace(config).expect("reloaded");
+
+    for (old, new) in before.upstreams.all().iter().zip(after.upstreams.all()) {
+        assert!(!std::ptr::eq(*old, new));
+    }
+}
+
+#[test]
+fn startup_only_changes_are_reported() {
+    let config = AppConfig::from_env().expect("default configuration");
+    assert!(config.restart_required(&config.clone()).is_empty());
+
+    let mut reloaded = config.clone();
+    reloaded.port += 1;
+    reloaded.max_inflight += 1;
+    reloaded.store = StoreBackend::parse("redis", Some("redis://cache:6379")).expect("store");
+    reloaded.upstream_connect_timeout += Duration::from_secs(1);
+    assert_eq!(
+        config.restart_required(&reloaded),
+        vec!["PORT", "MAX_INFLIGHT", "STORE_BACKEND"]
+    );
+}
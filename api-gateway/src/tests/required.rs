@@ -0,0 +1,54 @@
+// src/tests/required.rs
+use super::support::{MockUpstream, gateway};
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::{Value, json};
+
+#[rocket::async_test]
+async fn required_headers_gate_old_and_unlabelled_clients() {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.required_headers = vec![
+            "/api/users=X-Platform".parse().expect("valid rule"),
+            "/api/users=X-App-Version>=2.3".parse().expect("valid rule"),
+        ];
+    })
+    .await;
+
+    let login = |headers: &[(&'static str, &'static str)]| {
+        let mut request = client
+            .post("/api/users/login")
+            .header(ContentType::JSON)
+            .body(r#"{"email":"a@example.com","password":"secret"}"#);
+        for (name, value) in headers {
+            request = request.header(Header::new(*name, *value));
+        }
+        request.dispatch()
+    };
+
+    let unlabelled = login(&[("X-App-Version", "2.3.0")]).await;
+    assert_eq!(unlabelled.status(), Status::BadRequest);
+    let body = unlabelled.into_json::<Value>().await.expect("JSON body");
+    assert_eq!(
+        body["message"],
+        "Bad request: Missing required header X-Platform"
+    );
+
+    let outdated = login(&[("X-Platform", "ios"), ("X-App-Version", "2.2.10")]).await;
+    assert_eq!(outdated.status(), Status::BadRequest);
+    let body = outdated.into_json::<Value>().await.expect("JSON body");
+    assert_eq!(
+        body["message"],
+        "Bad request: X-App-Version must be at least 2.3, got 2.2.10"
+    );
+    assert!(users.requests().is_empty());
+
+    assert_eq!(
+        login(&[("X-Platform", "ios"), ("X-App-Version", "2.3.0")])
+            .await
+            .status(),
+        Status::Ok
+    );
+    assert_eq!(users.requests().len(), 1);
+}
@@ -0,0 +1,136 @@
+// src/tests/sales.rs
+use super::support::{MockUpstream, bearer, gateway, service_gateway};
+use crate::guards::api_key::API_KEY_HEADER;
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::{Value, json};
+
+#[rocket::async_test]
+async fn create_order_forwards_the_order() {
+    let (sales, client) = service_gateway(
+        |config| &mut config.sales_service_url,
+        201,
+        json!({ "id": "o1" }),
+    )
+    .await;
+
+    let response = client
+        .post("/api/sales/orders")
+        .header(ContentType::JSON)
+        .header(bearer())
+        .body(r#"{"customer_id":"c1","items":[{"product_id":"p1","quantity":2}]}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(
+        response.into_json::<Value>().await,
+        Some(json!({ "id": "o1" }))
+    );
+    let forwarded = sales.only_request();
+    assert_eq!(forwarded.method, "POST");
+    assert_eq!(forwarded.path, "/api/sales/orders");
+    assert_eq!(
+        forwarded.json(),
+        json!({ "customer_id": "c1", "items": [{ "product_id": "p1", "quantity": 2 }] })
+    );
+}
+
+#[rocket::async_test]
+async fn get_order_forwards_the_order_id() {
+    let (sales, client) = service_gateway(
+        |config| &mut config.sales_service_url,
+        200,
+        json!({ "id": "o 1" }),
+    )
+    .await;
+
+    let response = client
+        .get("/api/sales/orders/o%201")
+        .header(bearer())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let forwarded = sales.only_request();
+    assert_eq!(forwarded.method, "GET");
+    assert_eq!(forwarded.path, "/api/sales/orders/o%201");
+}
+
+#[rocket::async_test]
+async fn list_orders_forwards_the_filters() {
+    let (sales, client) = service_gateway(
+        |config| &mut config.sales_service_url,
+        200,
+        json!([{ "id": "o1" }]),
+    )
+    .await;
+
+    let response = client
+        .get("/api/sales/orders?status=open&customer_id=c1")
+        .header(bearer())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_json::<Value>().await,
+        Some(json!([{ "id": "o1" }]))
+    );
+    assert_eq!(
+        sales.only_request().path,
+        "/api/sales/orders?status=open&customer_id=c1"
+    );
+}
+
+#[rocket::async_test]
+async fn sales_routes_require_a_token() {
+    let (sales, client) = service_gateway(
+        |config| &mut config.sales_service_url,
+        200,
+        json!({ "id": "o1" }),
+    )
+    .await;
+
+    let get = client.get("/api/sales/orders/o1").dispatch().await;
+    assert_eq!(get.status(), Status::Unauthorized);
+    let list = client.get("/api/sales/orders").dispatch().await;
+    assert_eq!(list.status(), Status::Unauthorized);
+    let create = client
+        .post("/api/sales/orders")
+        .header(ContentType::JSON)
+        .body(r#"{"customer_id":"c1","items":[{"product_id":"p1","quantity":1}]}"#)
+        .dispatch()
+        .await;
+    assert_eq!(create.status(), Status::Unauthorized);
+
+    assert!(sales.requests().is_empty());
+}
+
+#[rocket::async_test]
+async fn internal_callers_reach_sales_with_an_api_key() {
+    let sales = MockUpstream::start(200, json!([{ "id": "o1" }])).await;
+    let url = sales.url.clone();
+    let client = gateway(|config| {
+        config.sales_service_url = url;
+        config.api_keys = vec!["k1".into()];
+    })
+    .await;
+
+    let response = client
+        .get("/api/sales/orders")
+        .header(Header::new(API_KEY_HEADER, "k1"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let forwarded = sales.only_request();
+    assert_eq!(forwarded.path, "/api/sales/orders");
+    assert!(!forwarded.headers.contains_key("x-api-key"));
+
+    let wrong = client
+        .get("/api/sales/orders")
+        .header(Header::new(API_KEY_HEADER, "k2"))
+        .dispatch()
+        .await;
+    assert_eq!(wrong.status(), Status::Unauthorized);
+}
@@ -0,0 +1,23 @@
+// src/tests/stats.rs
+use crate::services::stats::RequestStats;
+use std::time::Duration;
+
+#[test]
+fn summary_covers_the_requests_in_the_window() {
+    let stats = RequestStats::new();
+    assert_eq!(stats.summary().window_requests, 0);
+    assert_eq!(stats.summary().avg_latency_ms, 0.0);
+
+    stats.record(200, Duration::from_millis(10));
+    stats.record(404, Duration::from_millis(20));
+    stats.record(503, Duration::from_millis(30));
+    stats.record(502, Duration::from_millis(40));
+
+    let summary = stats.summary();
+    assert_eq!(summary.total_requests, 4);
+    assert_eq!(summary.window_seconds, 60);
+    assert_eq!(summary.window_requests, 4);
+    // Only 5xx answers count as errors
+    assert_eq!(summary.error_rate, 0.5);
+    assert_eq!(summary.avg_latency_ms, 25.0);
+}
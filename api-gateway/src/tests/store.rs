@@ -0,0 +1,79 @@
+// src/tests/store.rs
+use super::support::{closed_url, gateway};
+use crate::guards::api_key::API_KEY_HEADER;
+use crate::services::store::{MemoryStore, RedisStore, Store, StoreBackend};
+use rocket::http::{Header, Status};
+use std::time::Duration;
+
+/// Status of two API-key requests under a one-request quota kept in a
+/// Redis store nothing listens on
+async fn quota_statuses_without_redis(fail_open: bool) -> Vec<Status> {
+    let redis_url = closed_url().await.replace("http://", "redis://");
+    let client = gateway(|config| {
+        config.api_keys = vec!["k1".into()];
+        config.quota.limit = 1;
+        config.quota.fail_open = fail_open;
+        config.store = StoreBackend::parse("redis", Some(&redis_url)).unwrap();
+    })
+    .await;
+
+    let mut statuses = Vec::new();
+    for _ in 0..2 {
+        let response = client
+            .get("/api/admin/maintenance")
+            .header(Header::new(API_KEY_HEADER, "k1"))
+            .dispatch()
+            .await;
+        statuses.push(response.status());
+    }
+    statuses
+}
+
+#[rocket::async_test]
+async fn unreachable_redis_store_refuses_requests_unless_the_limit_fails_open() {
+    assert_eq!(
+        quota_statuses_without_redis(false).await,
+        [Status::ServiceUnavailable; 2]
+    );
+    assert_eq!(quota_statuses_without_redis(true).await, [Status::Ok; 2]);
+}
+
+#[rocket::async_test]
+async fn unreachable_redis_store_backs_off_between_connects() {
+    let redis_url = closed_url().await.replace("http://", "redis://");
+    let StoreBackend::Redis(client) = StoreBackend::parse("redis", Some(&redis_url)).unwrap()
+    else {
+        panic!("expected a Redis backend");
+    };
+    let store = RedisStore::new(client);
+
+    let first = store.get("a").await.expect_err("nothing listens");
+    assert!(
+        !first.to_string().contains("waiting to reconnect"),
+        "{}",
+        first
+    );
+    let second = store.get("a").await.expect_err("still backing off");
+    assert!(
+        second.to_string().contains("waiting to reconnect"),
+        "{}",
+        second
+    );
+}
+
+#[rocket::async_test]
+async fn memory_store_ignores_expired_entries_until_they_are_swept() {
+    let store = MemoryStore::default();
+    let ttl = Duration::from_millis(50);
+    store.set("a", b"1", ttl).await.unwrap();
+    assert!(store.set_nx("b", b"1", ttl).await.unwrap());
+    assert!(!store.set_nx("b", b"2", ttl).await.unwrap());
+    assert_eq!(store.increment("c", ttl).await.unwrap().0, 1);
+    assert_eq!(store.increment("c", ttl).await.unwrap().0, 2);
+
+    tokio::time::sleep(ttl * 2).await;
+    assert_eq!(store.get("a").await.unwrap(), None);
+    assert!(store.set_nx("b", b"3", ttl).await.unwrap());
+    assert_eq!(store.get("b").await.unwrap(), Some(b"3".to_vec()));
+    assert_eq!(store.increment("c", ttl).await.unwrap().0, 1);
+}
@@ -0,0 +1,222 @@
+// src/tests/streaming.rs
+use super::support::{MockUpstream, bearer, closed_url, gateway};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use rocket::http::{Accept, ContentType, Header, MediaType, Status};
+use rocket::serde::json::{Value, json};
+use std::io::Write;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+
+#[rocket::async_test]
+async fn event_stream_is_relayed_while_upstream_keeps_it_open() {
+    let sales = MockUpstream::events("data: order 1 shipped\n\n").await;
+    let url = sales.url.clone();
+    let client = gateway(|config| config.sales_service_url = url).await;
+
+    let mut response = client
+        .get("/api/proxy/sales/api/orders/events")
+        .header(Accept::new([MediaType::EventStream.into()]))
+        .header(bearer())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(MediaType::EventStream.into()));
+    assert_eq!(
+        response.headers().get_one("Cache-Control"),
+        Some("no-cache")
+    );
+
+    // The upstream never finishes, so a buffering gateway would hang here
+    let mut chunk = [0; 64];
+    let read = tokio::time::timeout(Duration::from_secs(5), response.read(&mut chunk))
+        .await
+        .expect("first event before the stream ends")
+        .expect("readable body");
+    assert_eq!(&chunk[..read], b"data: order 1 shipped\n\n");
+
+    let forwarded = sales.only_request();
+    assert_eq!(forwarded.path, "/api/orders/events");
+    assert_eq!(forwarded.headers["accept"], "text/event-stream");
+}
+
+#[rocket::async_test]
+async fn streaming_route_answered_with_json_is_relayed_as_usual() {
+    let sales = MockUpstream::start(200, json!({ "events": [] })).await;
+    let url = sales.url.clone();
+    let client = gateway(|config| {
+        config.sales_service_url = url;
+        config.proxy_stream_routes = vec!["sales/api/orders/events".into()];
+    })
+    .await;
+
+    let response = client
+        .get("/api/proxy/sales/api/orders/events")
+        .header(bearer())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_json::<Value>().await,
+        Some(json!({ "events": [] }))
+    );
+    assert_eq!(sales.only_request().headers["accept"], "text/event-stream");
+}
+
+#[rocket::async_test]
+async fn open_event_streams_hold_a_bulkhead_slot() {
+    let sales = MockUpstream::events("data: order 1 shipped\n\n").await;
+    let url = sales.url.clone();
+    let client = gateway(|config| {
+        config.sales_service_url = url;
+        config.sales_service_options.max_concurrent = 1;
+        config.bulkhead_queue_timeout = Duration::ZERO;
+    })
+    .await;
+
+    let bearer = bearer();
+    let events = || {
+        client
+            .get("/api/proxy/sales/api/orders/events")
+            .header(Accept::new([MediaType::EventStream.into()]))
+            .header(bearer.clone())
+            .dispatch()
+    };
+
+    let open = events().await;
+    assert_eq!(open.status(), Status::Ok);
+    assert_eq!(events().await.status(), Status::ServiceUnavailable);
+
+    drop(open);
+    assert_eq!(events().await.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn event_stream_from_the_fallback_is_marked() {
+    let fallback = MockUpstream::events("data: order 1 shipped\n\n").await;
+    let (primary, fallback_url) = (closed_url().await, fallback.url.clone());
+    let client = gateway(|config| {
+        config.sales_service_url = primary;
+        config.sales_service_options.fallback_url = Some(fallback_url);
+        config.breaker_failure_threshold = 1;
+        config.breaker_cooldown = Duration::from_secs(60);
+    })
+    .await;
+
+    let bearer = bearer();
+    let events = || {
+        client
+            .get("/api/proxy/sales/api/orders/events")
+            .header(Accept::new([MediaType::EventStream.into()]))
+            .header(bearer.clone())
+            .dispatch()
+    };
+
+    // The refused connection opens the breaker, so the next stream skips the primary
+    assert_eq!(events().await.status(), Status::ServiceUnavailable);
+    let response = events().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.headers().get_one("X-Upstream"), Some("fallback"));
+    assert_eq!(fallback.only_request().path, "/api/orders/events");
+}
+
+const BOUNDARY: &str = "gateway-test-boundary";
+
+fn multipart_body() -> String {
+    format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+         Content-Type: text/plain\r\n\r\nhello\r\n--{b}--\r\n",
+        b = BOUNDARY
+    )
+}
+
+#[rocket::async_test]
+async fn multipart_upload_is_passed_on_unparsed() {
+    let sales = MockUpstream::start(201, json!({ "id": "d1" })).await;
+    let url = sales.url.clone();
+    let client = gateway(|config| config.sales_service_url = url).await;
+
+    let body = multipart_body();
+    let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+    let response = client
+        .post("/api/proxy/sales/api/documents")
+        .header(Header::new("Content-Type", content_type.clone()))
+        .header(Header::new("Content-Length", body.len().to_string()))
+        .header(bearer())
+        .body(body.clone())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Created);
+    let forwarded = sales.only_request();
+    assert_eq!(forwarded.path, "/api/documents");
+    assert_eq!(forwarded.headers["content-type"], content_type);
+    assert_eq!(forwarded.body, body.as_bytes());
+}
+
+#[rocket::async_test]
+async fn oversized_upload_is_refused_before_proxying() {
+    let sales = MockUpstream::start(201, json!({ "id": "d1" })).await;
+    let url = sales.url.clone();
+    let client = gateway(|config| {
+        config.sales_service_url = url;
+        config.max_upload_bytes = 16;
+    })
+    .await;
+
+    let body = multipart_body();
+    let response = client
+        .post("/api/proxy/sales/api/documents")
+        .header(ContentType::new("multipart", "form-data").with_params(("boundary", BOUNDARY)))
+        .header(Header::new("Content-Length", body.len().to_string()))
+        .header(bearer())
+        .body(body)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+    assert!(sales.requests().is_empty());
+}
+
+#[rocket::async_test]
+async fn gzip_bomb_is_cut_off_at_the_inflated_size_limit() {
+    // 64 MiB of zeros, a few dozen KiB once compressed
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    let zeros = vec![0u8; 1 << 20];
+    for _ in 0..64 {
+        encoder.write_all(&zeros).expect("compress");
+    }
+    let bomb = encoder.finish().expect("compress");
+    assert!(bomb.len() < 1 << 20);
+
+    let users = MockUpstream::raw(
+        200,
+        &[
+            ("Content-Type", "application/json"),
+            ("Content-Encoding", "gzip"),
+        ],
+        bomb,
+    )
+    .await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.max_upstream_response_bytes = 1 << 20;
+    })
+    .await;
+
+    let response = client
+        .get("/api/users/me")
+        .header(bearer())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadGateway);
+    let body = response.into_json::<Value>().await.expect("JSON error");
+    assert_eq!(
+        body["message"],
+        "Bad gateway: User Service response exceeded the size limit"
+    );
+}
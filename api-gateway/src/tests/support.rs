@@ -0,0 +1,296 @@
+// src/tests/support.rs
+use crate::config::app::{AppConfig, DEFAULT_JWT_SECRET};
+use crate::services::telemetry;
+use crate::services::upstream::Upstreams;
+use rocket::http::Header;
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::Value;
+use rocket::{Build, Rocket};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A request as the mock upstream received it
+#[derive(Debug, Clone)]
+pub struct Recorded {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Recorded {
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).expect("recorded body is JSON")
+    }
+}
+
+/// Minimal HTTP/1.1 upstream answering every request with one canned JSON
+/// response and recording what it was sent
+pub struct MockUpstream {
+    pub url: String,
+    status: Arc<AtomicU16>,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+}
+
+impl MockUpstream {
+    pub async fn start(status: u16, body: Value) -> Self {
+        Self::slow(status, body, Duration::ZERO).await
+    }
+
+    /// A mock that waits `delay` before answering each request
+    pub async fn slow(status: u16, body: Value, delay: Duration) -> Self {
+        let headers = vec![("Content-Type".to_string(), "application/json".to_string())];
+        Self::serving(status, headers, body.to_string().into_bytes(), delay).await
+    }
+
+    /// A mock answering with `headers` and a body that needn't be JSON
+    pub async fn raw(status: u16, headers: &[(&str, &str)], body: impl Into<Vec<u8>>) -> Self {
+        let headers = headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Self::serving(status, headers, body.into(), Duration::ZERO).await
+    }
+
+    async fn serving(
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+        delay: Duration,
+    ) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock");
+        let url = format!("http://{}", listener.local_addr().expect("mock address"));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let status = Arc::new(AtomicU16::new(status));
+
+        let recorded = requests.clone();
+        let answer = status.clone();
+        let answer_with = Arc::new((headers, body));
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+                let status = answer.load(Ordering::Relaxed);
+                let answer_with = answer_with.clone();
+                tokio::spawn(async move {
+                    let (headers, body) = &*answer_with;
+                    serve(stream, status, headers, body, delay, &recorded).await
+                });
+            }
+        });
+
+        Self {
+            url,
+            status,
+            requests,
+        }
+    }
+
+    /// A mock answering with a Server-Sent Events stream that sends `event`
+    /// and then stays open without ever finishing
+    pub async fn events(event: &str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock");
+        let url = format!("http://{}", listener.local_addr().expect("mock address"));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        let event = event.to_string();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+                let event = event.clone();
+                tokio::spawn(async move {
+                    let mut reader = BufReader::new(stream);
+                    let Some(request) = read_request(&mut reader).await else {
+                        return;
+                    };
+                    recorded.lock().unwrap().push(request);
+                    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\n";
+                    let stream = reader.get_mut();
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let _ = stream.write_all(event.as_bytes()).await;
+                    tokio::time::sleep(Duration::from_secs(3600)).await;
+                });
+            }
+        });
+
+        Self {
+            url,
+            status: Arc::new(AtomicU16::new(200)),
+            requests,
+        }
+    }
+
+    /// Answer later requests with `status` instead
+    pub fn set_status(&self, status: u16) {
+        self.status.store(status, Ordering::Relaxed);
+    }
+
+    /// Requests received so far, leaving out the gateway's startup health check
+    pub fn requests(&self) -> Vec<Recorded> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.path != "/api/health")
+            .cloned()
+            .collect()
+    }
+
+    /// Health checks received so far, the startup one included
+    pub fn health_checks(&self) -> usize {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.path == "/api/health")
+            .count()
+    }
+
+    pub fn only_request(&self) -> Recorded {
+        let requests = self.requests();
+        assert_eq!(requests.len(), 1, "expected one upstream request");
+        requests[0].clone()
+    }
+}
+
+async fn serve(
+    stream: TcpStream,
+    status: u16,
+    headers: &[(String, String)],
+    body: &[u8],
+    delay: Duration,
+    recorded: &Mutex<Vec<Recorded>>,
+) {
+    let mut reader = BufReader::new(stream);
+    let Some(request) = read_request(&mut reader).await else {
+        return;
+    };
+    recorded.lock().unwrap().push(request);
+
+    tokio::time::sleep(delay).await;
+    let mut head = format!("HTTP/1.1 {} Mock\r\n", status);
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    ));
+    let stream = reader.get_mut();
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(body).await;
+}
+
+async fn read_request(reader: &mut BufReader<TcpStream>) -> Option<Recorded> {
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).await.unwrap_or(0) == 0 {
+        return None;
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.expect("read header");
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    let mut request_body = vec![0; length];
+    reader
+        .read_exact(&mut request_body)
+        .await
+        .expect("read body");
+
+    Some(Recorded {
+        method,
+        path,
+        headers,
+        body: request_body,
+    })
+}
+
+/// A URL nothing listens on, for connection failures
+pub async fn closed_url() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    format!("http://{}", listener.local_addr().expect("address"))
+}
+
+/// A gateway with the default configuration, adjusted by `configure`
+pub async fn gateway(configure: impl FnOnce(&mut AppConfig)) -> Client {
+    Client::tracked(instance(configure))
+        .await
+        .expect("valid rocket instance")
+}
+
+/// A gateway whose service at `url_field` is a mock upstream answering
+/// every request with `status` and `body`
+pub async fn service_gateway(
+    url_field: fn(&mut AppConfig) -> &mut String,
+    status: u16,
+    body: Value,
+) -> (MockUpstream, Client) {
+    let upstream = MockUpstream::start(status, body).await;
+    let url = upstream.url.clone();
+    let client = gateway(|config| *url_field(config) = url).await;
+    (upstream, client)
+}
+
+/// The unlaunched gateway `gateway` would serve requests with
+pub fn instance(configure: impl FnOnce(&mut AppConfig)) -> Rocket<Build> {
+    let mut config = AppConfig::from_env().expect("default configuration");
+    config.jwt_secret = DEFAULT_JWT_SECRET.to_string();
+    configure(&mut config);
+
+    let upstreams = Upstreams::from_config(&config).expect("upstreams");
+    let prometheus = telemetry::prometheus_builder(&config)
+        .build_recorder()
+        .handle();
+    crate::build(config, upstreams, prometheus)
+}
+
+/// A bearer token for `user_id` signed with `DEFAULT_JWT_SECRET`, expiring
+/// `expires_in` seconds from now (negative for an expired token)
+pub fn token(user_id: &str, expires_in: i64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock after epoch")
+        .as_secs() as i64;
+    let claims = serde_json::json!({
+        "userId": user_id,
+        "email": format!("{}@example.com", user_id),
+        "role": "user",
+        "exp": now + expires_in,
+    });
+    jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &claims,
+        &jsonwebtoken::EncodingKey::from_secret(DEFAULT_JWT_SECRET.as_bytes()),
+    )
+    .expect("signed token")
+}
+
+/// `Authorization` with a valid bearer token for user `u1`
+pub fn bearer() -> Header<'static> {
+    bearer_for("u1")
+}
+
+/// `Authorization` with a valid bearer token for `user_id`
+pub fn bearer_for(user_id: &str) -> Header<'static> {
+    Header::new("Authorization", format!("Bearer {}", token(user_id, 3600)))
+}
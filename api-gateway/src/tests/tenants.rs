@@ -0,0 +1,70 @@
+// src/tests/tenants.rs
+use super::support::{MockUpstream, gateway};
+use crate::services::tenant::TenantSource;
+use rocket::http::uri::Host;
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::json;
+
+#[rocket::async_test]
+async fn tenant_header_is_forwarded_upstream() {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .header(Header::new("X-Tenant-Id", "Acme"))
+        .body(r#"{"email":"a@example.com","password":"secret"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(users.only_request().headers["x-tenant-id"], "acme");
+}
+
+#[rocket::async_test]
+async fn subdomain_tenant_replaces_client_header() {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.tenant_sources = vec![TenantSource::Subdomain];
+        config.tenant_domain = Some("api.example.com".into());
+    })
+    .await;
+
+    let mut request = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .header(Header::new("X-Tenant-Id", "other"))
+        .body(r#"{"email":"a@example.com","password":"secret"}"#);
+    request
+        .inner_mut()
+        .set_host(Host::parse("globex.api.example.com").expect("valid host"));
+    let response = request.dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(users.only_request().headers["x-tenant-id"], "globex");
+}
+
+#[rocket::async_test]
+async fn tenant_scoped_paths_need_a_tenant() {
+    let users = MockUpstream::start(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.tenant_scoped_paths = vec!["/api/users".into()];
+    })
+    .await;
+
+    let response = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"secret"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+    assert!(users.requests().is_empty());
+}
@@ -0,0 +1,51 @@
+// src/tests/timing.rs
+use super::support::{MockUpstream, bearer, gateway};
+use crate::services::timing::UpstreamTimings;
+use rocket::http::Status;
+use rocket::serde::json::json;
+
+#[rocket::async_test]
+async fn each_attempt_reports_into_its_own_timings() {
+    let upstream = MockUpstream::start(200, json!({ "ok": true })).await;
+    let client = UpstreamTimings::client(reqwest::Client::builder()).expect("timed client");
+
+    let timed = UpstreamTimings::default();
+    timed.begin();
+    let response = timed.scope(client.get(&upstream.url).send()).await;
+    assert!(response.is_ok());
+    timed.first_byte();
+
+    // Outside a scope the same client runs, but reports to no one
+    let untimed = UpstreamTimings::default();
+    untimed.begin();
+    let other = client.get(format!("{}/other", upstream.url)).send().await;
+    assert!(other.is_ok());
+
+    let summary = timed.summary().to_string();
+    assert!(!summary.contains("connect=-"), "{}", summary);
+    assert!(!summary.contains("ttfb=-"), "{}", summary);
+    assert!(untimed.summary().to_string().contains("connect=-"));
+}
+
+#[rocket::async_test]
+async fn timed_gateway_proxies_concurrent_requests() {
+    let users = MockUpstream::start(200, json!({ "id": "u1" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.log_upstream_timings = true;
+    })
+    .await;
+
+    let bearer = bearer();
+    let me = || {
+        client
+            .get("/api/users/me")
+            .header(bearer.clone())
+            .dispatch()
+    };
+    let (first, second) = tokio::join!(me(), me());
+    assert_eq!(first.status(), Status::Ok);
+    assert_eq!(second.status(), Status::Ok);
+    assert_eq!(users.requests().len(), 2);
+}
@@ -0,0 +1,741 @@
+// src/tests/users.rs
+use super::support::{MockUpstream, bearer, closed_url, gateway, token};
+use crate::services::telemetry::{self, RequestIdFormat};
+use crate::services::upstream::BasicAuth;
+use rocket::futures::future::join_all;
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::{Value, json};
+use std::time::Duration;
+
+async fn user_service(status: u16, body: Value) -> MockUpstream {
+    MockUpstream::start(status, body).await
+}
+
+#[rocket::async_test]
+async fn login_forwards_credentials_to_user_service() {
+    let users = user_service(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"secret"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_json::<Value>().await,
+        Some(json!({ "token": "t" }))
+    );
+
+    let forwarded = users.only_request();
+    assert_eq!(forwarded.method, "POST");
+    assert_eq!(forwarded.path, "/api/users/login");
+    assert_eq!(
+        forwarded.json(),
+        json!({ "email": "a@example.com", "password": "secret" })
+    );
+}
+
+#[rocket::async_test]
+async fn register_forwards_new_user() {
+    let users = user_service(201, json!({ "id": "u1" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = client
+        .post("/api/users/register")
+        .header(ContentType::JSON)
+        .body(r#"{"name":"Ada","email":"ada@example.com","password":"correct-horse"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Created);
+    let forwarded = users.only_request();
+    assert_eq!(forwarded.path, "/api/users/register");
+    assert_eq!(forwarded.json()["name"], "Ada");
+}
+
+#[rocket::async_test]
+async fn body_transforms_edit_fields_before_forwarding() {
+    let users = user_service(201, json!({ "id": "u1" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.body_transforms = [
+            "/api/users/register=set:source:web-gateway",
+            "/api/users/register=set:verified:false",
+            "/api/users/register=rename:name:full_name",
+            "/api/users/register=drop:password",
+            "/api/users/login=set:source:elsewhere",
+        ]
+        .iter()
+        .map(|rule| rule.parse().expect("valid rule"))
+        .collect();
+    })
+    .await;
+
+    let response = client
+        .post("/api/users/register")
+        .header(ContentType::JSON)
+        .body(r#"{"name":"Ada","email":"ada@example.com","password":"correct-horse"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(
+        users.only_request().json(),
+        json!({
+            "full_name": "Ada",
+            "email": "ada@example.com",
+            "source": "web-gateway",
+            "verified": false,
+        })
+    );
+}
+
+#[rocket::async_test]
+async fn refresh_forwards_refresh_token() {
+    let users = user_service(200, json!({ "token": "t2" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = client
+        .post("/api/users/refresh")
+        .header(ContentType::JSON)
+        .body(r#"{"refresh_token":"r1"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let forwarded = users.only_request();
+    assert_eq!(forwarded.path, "/api/users/refresh");
+    assert_eq!(forwarded.json(), json!({ "refresh_token": "r1" }));
+}
+
+#[rocket::async_test]
+async fn logout_forwards_authorization_and_request_id() {
+    let users = user_service(200, json!({ "logged_out": true })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = client
+        .post("/api/users/logout")
+        .header(Header::new("Authorization", "Bearer abc"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let forwarded = users.only_request();
+    assert_eq!(forwarded.path, "/api/users/logout");
+    assert_eq!(forwarded.headers["authorization"], "Bearer abc");
+    assert!(uuid::Uuid::parse_str(&forwarded.headers["x-request-id"]).is_ok());
+}
+
+#[rocket::async_test]
+async fn empty_logout_answer_is_relayed_as_204() {
+    let users = MockUpstream::raw(204, &[], "").await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = client
+        .post("/api/users/logout")
+        .header(Header::new("Authorization", "Bearer abc"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::NoContent);
+    assert!(response.into_bytes().await.unwrap_or_default().is_empty());
+}
+
+#[rocket::async_test]
+async fn non_json_upstream_errors_keep_their_status() {
+    let users = MockUpstream::raw(
+        502,
+        &[("Content-Type", "text/html")],
+        "<html><body>Bad Gateway</body></html>",
+    )
+    .await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.proxy_max_retries = 0;
+    })
+    .await;
+
+    let response = client
+        .post("/api/users/logout")
+        .header(Header::new("Authorization", "Bearer abc"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadGateway);
+    let body = response.into_json::<Value>().await.expect("JSON error");
+    assert_eq!(body["status"], 502);
+    assert_eq!(
+        body["message"],
+        "User Service responded with 502 Bad Gateway"
+    );
+}
+
+#[rocket::async_test]
+async fn request_ids_follow_the_configured_format() {
+    let users = user_service(200, json!({ "logged_out": true })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.request_id_format = RequestIdFormat::Ulid;
+    })
+    .await;
+
+    let response = client.post("/api/users/logout").dispatch().await;
+    let request_id = response
+        .headers()
+        .get_one("X-Request-Id")
+        .expect("request id")
+        .to_string();
+    assert_eq!(request_id.len(), 26);
+    assert_eq!(users.only_request().headers["x-request-id"], request_id);
+
+    // The timestamp from the ULID spec's example, with no randomness
+    assert_eq!(
+        telemetry::ulid(1469918176385, 0),
+        "01ARYZ6S410000000000000000"
+    );
+}
+
+#[rocket::async_test]
+async fn upstream_error_status_is_passed_through() {
+    let users = user_service(401, json!({ "error": "bad credentials" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"wrong"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(
+        response.into_json::<Value>().await,
+        Some(json!({ "error": "bad credentials" }))
+    );
+}
+
+#[rocket::async_test]
+async fn unreachable_user_service_is_503() {
+    let url = closed_url().await;
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"secret"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    let body = response.into_json::<Value>().await.expect("JSON error");
+    assert_eq!(body["status"], 503);
+}
+
+#[rocket::async_test]
+async fn dead_primary_fails_over_to_the_fallback() {
+    let primary = closed_url().await;
+    let fallback = user_service(200, json!({ "token": "from-fallback" })).await;
+    let fallback_url = fallback.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = primary;
+        config.user_service_options.fallback_url = Some(fallback_url);
+    })
+    .await;
+
+    let response = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"secret"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.headers().get_one("X-Upstream"), Some("fallback"));
+    assert_eq!(
+        response.into_json::<Value>().await,
+        Some(json!({ "token": "from-fallback" }))
+    );
+    assert_eq!(fallback.only_request().path, "/api/users/login");
+}
+
+#[rocket::async_test]
+async fn failing_primary_only_fails_over_requests_safe_to_repeat() {
+    let primary = user_service(503, json!({ "error": "down" })).await;
+    let fallback = user_service(200, json!({ "id": "u1" })).await;
+    let (primary_url, fallback_url) = (primary.url.clone(), fallback.url.clone());
+    let client = gateway(|config| {
+        config.user_service_url = primary_url;
+        config.user_service_options.fallback_url = Some(fallback_url);
+        config.proxy_max_retries = 0;
+    })
+    .await;
+
+    let login = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"secret"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(login.status(), Status::ServiceUnavailable);
+    assert!(fallback.requests().is_empty());
+
+    let me = client
+        .get("/api/users/me")
+        .header(bearer())
+        .dispatch()
+        .await;
+    assert_eq!(me.status(), Status::Ok);
+    assert_eq!(me.headers().get_one("X-Upstream"), Some("fallback"));
+    assert_eq!(fallback.only_request().path, "/api/users/me");
+}
+
+#[rocket::async_test]
+async fn remapped_upstream_status_reaches_client() {
+    let users = user_service(418, json!({ "error": "teapot" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.status_remaps = vec!["418=400".parse().unwrap()];
+    })
+    .await;
+
+    let response = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"secret"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[rocket::async_test]
+async fn me_forwards_bearer_token() {
+    let users = user_service(200, json!({ "id": "u1", "name": "Ada" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let bearer = bearer();
+    let response = client
+        .get("/api/users/me")
+        .header(bearer.clone())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_json::<Value>().await,
+        Some(json!({ "id": "u1", "name": "Ada" }))
+    );
+    let forwarded = users.only_request();
+    assert_eq!(forwarded.method, "GET");
+    assert_eq!(forwarded.path, "/api/users/me");
+    assert_eq!(forwarded.headers["authorization"], bearer.value());
+}
+
+#[rocket::async_test]
+async fn me_rejects_missing_and_expired_tokens_before_proxying() {
+    let users = user_service(200, json!({ "id": "u1" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let missing = client.get("/api/users/me").dispatch().await;
+    assert_eq!(missing.status(), Status::Unauthorized);
+
+    let expired = client
+        .get("/api/users/me")
+        .header(Header::new(
+            "Authorization",
+            format!("Bearer {}", token("u1", -3600)),
+        ))
+        .dispatch()
+        .await;
+    assert_eq!(expired.status(), Status::Unauthorized);
+    let body = expired.into_json::<Value>().await.expect("JSON error");
+    assert_eq!(body["message"], "Unauthorized: Token expired");
+
+    assert!(users.requests().is_empty());
+}
+
+#[rocket::async_test]
+async fn reset_password_forwards_token_and_new_password() {
+    let users = user_service(200, json!({ "reset": true })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = client
+        .post("/api/users/reset-password")
+        .header(ContentType::JSON)
+        .body(r#"{"token":"r1","new_password":"correct-horse"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let forwarded = users.only_request();
+    assert_eq!(forwarded.path, "/api/users/reset-password");
+    assert_eq!(
+        forwarded.json(),
+        json!({ "token": "r1", "new_password": "correct-horse" })
+    );
+}
+
+#[rocket::async_test]
+async fn forgot_password_is_throttled_per_client() {
+    let users = user_service(202, json!({ "sent": true })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.password_reset_rate_limit.limit = 2;
+    })
+    .await;
+
+    // A fresh X-Real-IP on each request doesn't earn a fresh allowance
+    let forgot = |real_ip: &'static str| {
+        client
+            .post("/api/users/forgot-password")
+            .header(ContentType::JSON)
+            .header(Header::new("X-Real-IP", real_ip))
+            .body(r#"{"email":"a@example.com"}"#)
+            .dispatch()
+    };
+
+    assert_eq!(forgot("192.0.2.1").await.status(), Status::Accepted);
+    assert_eq!(forgot("192.0.2.2").await.status(), Status::Accepted);
+
+    let throttled = forgot("192.0.2.3").await;
+    assert_eq!(throttled.status(), Status::TooManyRequests);
+    assert!(throttled.headers().get_one("Retry-After").is_some());
+    assert_eq!(users.requests().len(), 2);
+}
+
+#[rocket::async_test]
+async fn invalid_registration_is_rejected_before_proxying() {
+    let users = user_service(201, json!({ "id": "u1" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let response = client
+        .post("/api/users/register")
+        .header(ContentType::JSON)
+        .body(r#"{"name":" ","email":"not-an-email","password":"short"}"#)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+    let body = response.into_json::<Value>().await.expect("JSON error");
+    assert_eq!(
+        body["message"],
+        "Bad request: name must not be empty; email must be a valid email address; \
+         password must be at least 8 characters"
+    );
+    assert!(users.requests().is_empty());
+}
+
+async fn concurrent_profile_requests(coalesce: bool) -> usize {
+    let users = MockUpstream::slow(200, json!({ "id": "u1" }), Duration::from_millis(200)).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.coalesce_gets = coalesce;
+    })
+    .await;
+
+    let bearer = bearer();
+    let requests = (0..5).map(|_| {
+        client
+            .get("/api/users/me")
+            .header(bearer.clone())
+            .dispatch()
+    });
+    for response in join_all(requests).await {
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(
+            response.into_json::<Value>().await,
+            Some(json!({ "id": "u1" }))
+        );
+    }
+    users.requests().len()
+}
+
+#[rocket::async_test]
+async fn identical_concurrent_gets_share_one_upstream_call() {
+    assert_eq!(concurrent_profile_requests(true).await, 1);
+    assert_eq!(concurrent_profile_requests(false).await, 5);
+}
+
+#[rocket::async_test]
+async fn failed_get_serves_last_good_response_as_stale() {
+    let users = user_service(200, json!({ "id": "u1" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.stale_max_age = Duration::from_secs(60);
+        config.proxy_max_retries = 0;
+    })
+    .await;
+
+    let bearer = bearer();
+    let me = || {
+        client
+            .get("/api/users/me")
+            .header(bearer.clone())
+            .dispatch()
+    };
+
+    let fresh = me().await;
+    assert_eq!(fresh.status(), Status::Ok);
+    assert!(fresh.headers().get_one("X-Cache").is_none());
+
+    users.set_status(503);
+    let stale = me().await;
+    assert_eq!(stale.status(), Status::Ok);
+    assert_eq!(stale.headers().get_one("X-Cache"), Some("STALE"));
+    assert_eq!(
+        stale.into_json::<Value>().await,
+        Some(json!({ "id": "u1" }))
+    );
+    assert_eq!(users.requests().len(), 2);
+}
+
+#[rocket::async_test]
+async fn no_cache_request_gets_the_upstream_failure_instead_of_stale() {
+    let users = user_service(200, json!({ "id": "u1" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.stale_max_age = Duration::from_secs(60);
+        config.proxy_max_retries = 0;
+    })
+    .await;
+
+    let bearer = bearer();
+    let me = |cache_control: &'static str| {
+        client
+            .get("/api/users/me")
+            .header(bearer.clone())
+            .header(Header::new("Cache-Control", cache_control))
+            .dispatch()
+    };
+
+    assert_eq!(me("max-age=0, no-cache").await.status(), Status::Ok);
+    assert_eq!(me("max-age=0").await.status(), Status::Ok);
+
+    users.set_status(503);
+    let refused = me("max-age=0, no-cache").await;
+    assert_eq!(refused.status(), Status::ServiceUnavailable);
+    assert!(refused.headers().get_one("X-Cache").is_none());
+
+    let stale = me("max-age=0").await;
+    assert_eq!(stale.status(), Status::Ok);
+    assert_eq!(stale.headers().get_one("X-Cache"), Some("STALE"));
+}
+
+#[rocket::async_test]
+async fn error_bodies_carry_the_request_id() {
+    let url = closed_url().await;
+    let client = gateway(|config| config.user_service_url = url).await;
+
+    let unreachable = client
+        .post("/api/users/login")
+        .header(ContentType::JSON)
+        .body(r#"{"email":"a@example.com","password":"secret"}"#)
+        .dispatch();
+    let unauthenticated = client.get("/api/users/me").dispatch();
+    let rejected = client.get("/api/inventory/products").dispatch();
+
+    for response in [unreachable.await, unauthenticated.await, rejected.await] {
+        let request_id = response
+            .headers()
+            .get_one("X-Request-Id")
+            .expect("request ID header")
+            .to_string();
+        let body = response.into_json::<Value>().await.expect("JSON error");
+        assert_eq!(body["request_id"], request_id.as_str());
+    }
+}
+
+#[rocket::async_test]
+async fn basic_auth_replaces_the_callers_authorization() {
+    let users = user_service(200, json!({ "logged_out": true })).await;
+    let url = users.url.clone();
+    let auth: BasicAuth = "gateway:s3cret".parse().unwrap();
+    assert!(!format!("{:?}", auth).contains("s3cret"));
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.user_service_options.basic_auth = Some(auth);
+    })
+    .await;
+
+    let response = client
+        .post("/api/users/logout")
+        .header(Header::new("Authorization", "Bearer abc"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        users.only_request().headers["authorization"],
+        "Basic Z2F0ZXdheTpzM2NyZXQ="
+    );
+}
+
+#[rocket::async_test]
+async fn envelope_wraps_success_bodies_only() {
+    let users = user_service(200, json!({ "token": "t" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.response_envelope = true;
+    })
+    .await;
+
+    let login = || {
+        client
+            .post("/api/users/login")
+            .header(ContentType::JSON)
+            .body(r#"{"email":"a@example.com","password":"secret"}"#)
+            .dispatch()
+    };
+
+    let response = login().await;
+    assert_eq!(response.status(), Status::Ok);
+    let request_id = response
+        .headers()
+        .get_one("X-Request-Id")
+        .expect("request ID header")
+        .to_string();
+    let body = response.into_json::<Value>().await.expect("JSON body");
+    assert_eq!(body["data"], json!({ "token": "t" }));
+    assert_eq!(body["meta"]["request_id"], request_id.as_str());
+    assert!(body["meta"]["timestamp"].is_u64());
+
+    users.set_status(401);
+    let failed = login().await;
+    assert_eq!(failed.status(), Status::Unauthorized);
+    assert_eq!(
+        failed.into_json::<Value>().await,
+        Some(json!({ "token": "t" }))
+    );
+}
+
+#[rocket::async_test]
+async fn retries_stop_once_the_retry_budget_is_spent() {
+    let users = user_service(503, json!({ "error": "overloaded" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.user_service_options.retry_budget_ratio = 0.1;
+        config.breaker_failure_threshold = 0;
+        config.proxy_max_retries = 1;
+        config.proxy_retry_backoff = Duration::ZERO;
+    })
+    .await;
+
+    let bearer = bearer();
+    for _ in 0..15 {
+        let response = client
+            .get("/api/users/me")
+            .header(bearer.clone())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    // The 10 banked retries plus the one 11 requests' deposits paid for
+    assert_eq!(users.requests().len(), 15 + 11);
+}
+
+#[rocket::async_test]
+async fn slow_attempts_are_cut_off_by_the_total_timeout() {
+    let users = MockUpstream::slow(200, json!({ "id": "u1" }), Duration::from_secs(2)).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.upstream_total_timeout = Duration::from_millis(100);
+        config.proxy_max_retries = 0;
+    })
+    .await;
+
+    let started = std::time::Instant::now();
+    let response = client
+        .get("/api/users/me")
+        .header(bearer())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::GatewayTimeout);
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[rocket::async_test]
+async fn replicas_with_an_open_breaker_leave_the_rotation() {
+    let failing = user_service(503, json!({ "error": "down" })).await;
+    let healthy = user_service(200, json!({ "id": "u1" })).await;
+    let spec = format!("{},{}", failing.url, healthy.url);
+    let client = gateway(|config| {
+        config.user_service_url = spec;
+        config.breaker_failure_threshold = 2;
+        config.breaker_cooldown = Duration::from_secs(60);
+        config.proxy_max_retries = 0;
+    })
+    .await;
+
+    let bearer = bearer();
+    for _ in 0..6 {
+        client
+            .get("/api/users/me")
+            .header(bearer.clone())
+            .dispatch()
+            .await;
+    }
+
+    // Round-robin until the second failure opens the failing replica's breaker
+    assert_eq!(failing.requests().len(), 2);
+    assert_eq!(healthy.requests().len(), 4);
+}
+
+#[rocket::async_test]
+async fn open_breaker_503s_carry_the_remaining_cooldown() {
+    let users = user_service(503, json!({ "error": "down" })).await;
+    let url = users.url.clone();
+    let client = gateway(|config| {
+        config.user_service_url = url;
+        config.breaker_failure_threshold = 1;
+        config.breaker_cooldown = Duration::from_secs(30);
+    })
+    .await;
+
+    let login = || {
+        client
+            .post("/api/users/login")
+            .header(ContentType::JSON)
+            .body(r#"{"email":"a@example.com","password":"secret"}"#)
+            .dispatch()
+    };
+    let tripped = login().await;
+    assert_eq!(tripped.status(), Status::ServiceUnavailable);
+    assert!(tripped.headers().get_one("Retry-After").is_none());
+
+    let refused = login().await;
+    assert_eq!(refused.status(), Status::ServiceUnavailable);
+    let retry_after: u64 = refused
+        .headers()
+        .get_one("Retry-After")
+        .expect("Retry-After")
+        .parse()
+        .expect("whole seconds");
+    assert!((29..=30).contains(&retry_after));
+    assert_eq!(users.requests().len(), 1);
+}
@@ -0,0 +1,119 @@
+// src/tests/waf.rs
+use super::support::gateway;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::{Client, LocalRequest};
+use rocket::serde::json::Value;
+
+#[rocket::async_test]
+async fn path_traversal_is_blocked() {
+    let client = gateway(|_| {}).await;
+
+    for uri in [
+        "/api/health/../admin/maintenance",
+        "/api/health/%2e%2e/admin",
+        "/api/health/live?file=..%252f..%252fetc%252fpasswd",
+    ] {
+        let response = client.get(uri).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest, "{}", uri);
+        let body = response.into_json::<Value>().await.expect("JSON error");
+        assert_eq!(body["message"], "Bad request: Request blocked");
+    }
+}
+
+fn with_extra_headers(client: &Client) -> LocalRequest<'_> {
+    let mut request = client.get("/api/health/live");
+    for i in 0..5 {
+        request = request.header(Header::new(format!("X-Extra-{}", i), "1"));
+    }
+    request
+}
+
+#[rocket::async_test]
+async fn too_many_headers_are_blocked_unless_the_rule_is_off() {
+    let strict = gateway(|config| config.waf_max_headers = 3).await;
+    assert_eq!(
+        with_extra_headers(&strict).dispatch().await.status(),
+        Status::BadRequest
+    );
+
+    let relaxed = gateway(|config| {
+        config.waf_max_headers = 3;
+        config.waf_rules.clear();
+    })
+    .await;
+    assert_eq!(
+        with_extra_headers(&relaxed).dispatch().await.status(),
+        Status::Ok
+    );
+}
+
+#[rocket::async_test]
+async fn null_bytes_in_header_values_are_blocked() {
+    let client = gateway(|_| {}).await;
+
+    let response = client
+        .get("/api/health/live")
+        .header(Header::new("X-Note", "a\0b"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[rocket::async_test]
+async fn overlong_uris_get_a_414() {
+    let client = gateway(|config| config.max_uri_length = 64).await;
+
+    let long_path = format!("/api/health/{}", "a".repeat(64));
+    let long_query = format!("/api/health/live?q={}", "a".repeat(64));
+    for uri in [long_path, long_query] {
+        let response = client.get(uri.clone()).dispatch().await;
+        assert_eq!(response.status(), Status::UriTooLong, "{}", uri);
+        let body = response.into_json::<Value>().await.expect("JSON error");
+        assert_eq!(body["message"], "URI too long: URI exceeds 64 bytes");
+    }
+
+    let response = client.get("/api/health/live?q=short").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn denylisted_clients_are_forbidden() {
+    let client = gateway(|config| {
+        config.ip_denylist = "203.0.113.0/24,2001:db8::/32"
+            .parse()
+            .expect("valid ranges");
+    })
+    .await;
+
+    let from = |forwarded: &'static str| {
+        client
+            .get("/api/health/live")
+            .header(Header::new("X-Forwarded-For", forwarded))
+            .dispatch()
+    };
+
+    for forwarded in ["203.0.113.77", "198.51.100.1, 203.0.113.5", "2001:db8::1"] {
+        let response = from(forwarded).await;
+        assert_eq!(response.status(), Status::Forbidden, "{}", forwarded);
+        let body = response.into_json::<Value>().await.expect("JSON error");
+        assert_eq!(body["message"], "Forbidden: Access denied");
+    }
+    assert_eq!(from("203.0.114.1").await.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn denylisted_peers_are_forbidden_despite_a_spoofed_real_ip() {
+    let client = gateway(|config| {
+        config.ip_denylist = "203.0.113.0/24".parse().expect("valid range");
+    })
+    .await;
+
+    let response = client
+        .get("/api/health/live")
+        .remote("203.0.113.9:4000".parse().expect("socket address"))
+        .header(Header::new("X-Real-IP", "1.1.1.1"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Forbidden);
+}